@@ -18,6 +18,16 @@ mod autocfg {
         println!("cargo:rustc-cfg={}", cfg);
     }
 
+    /// Writes a line telling Cargo that `cfg` is an expected config name.
+    ///
+    /// This looks like: `cargo:rustc-check-cfg=cfg(CFG)`
+    ///
+    /// Earlier versions of cargo without check-cfg support will simply ignore
+    /// the directive.
+    pub fn emit_possibility(cfg: &str) {
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+    }
+
     /// Writes a line telling Cargo to rerun the build script if `path` changes.
     ///
     /// This looks like: `cargo:rerun-if-changed=PATH`
@@ -28,6 +38,17 @@ mod autocfg {
         println!("cargo:rerun-if-changed={}", path);
     }
 
+    /// Writes a line telling Cargo to rerun the build script if the environment
+    /// variable `var` changes.
+    ///
+    /// This looks like: `cargo:rerun-if-env-changed=VAR`
+    ///
+    /// This requires at least cargo 0.21.0, corresponding to rustc 1.20.0.  Earlier
+    /// versions of cargo will simply ignore the directive.
+    pub fn rerun_env(var: &str) {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+
     /// Helper to detect compiler features for `cfg` output in build scripts.
     #[derive(Clone, Debug)]
     pub struct AutoCfg {
@@ -38,15 +59,6 @@ mod autocfg {
         rustflags: Vec<String>,
     }
 
-    /// Create a new `AutoCfg` instance.
-    ///
-    /// # Panics
-    ///
-    /// Panics if `AutoCfg::new()` returns an error.
-    pub fn new() -> AutoCfg {
-        AutoCfg::new().unwrap()
-    }
-
     impl AutoCfg {
         /// Create a new `AutoCfg` instance.
         ///
@@ -126,7 +138,9 @@ mod autocfg {
 
             command.arg("-").stdin(Stdio::piped());
             let mut child = command.spawn()?;
-            let mut stdin = child.stdin.take().expect("rustc stdin");
+            let mut stdin = child.stdin.take().ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::Other, "rustc has no stdin")
+            })?;
 
             if self.no_std {
                 stdin.write_all(b"#![no_std]\n")?;
@@ -152,27 +166,149 @@ mod autocfg {
                 a.split('\x1f').map(str::to_string).collect()
             };
         }
-        panic!("expected $env:CARGO_ENCODED_RUSTFLAGS");
+
+        // Otherwise, we have to take a more heuristic approach, and we don't
+        // support values from toml config at all. Proc macros are always
+        // built for the host, which is when older Cargo applies RUSTFLAGS
+        // as long as no `--target` is given.
+        if let Ok(rustflags) = env::var("RUSTFLAGS") {
+            // This is meant to match how cargo handles the RUSTFLAGS environment variable.
+            return rustflags
+                .split(' ')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        Vec::new()
     }
 }
 
 fn main() {
     autocfg::rerun_path("build.rs");
+    autocfg::rerun_env("DOCS_RS");
+    autocfg::rerun_env("RUSTC_BOOTSTRAP");
+    autocfg::emit_possibility("has_simple_decl_macro");
+    autocfg::emit_possibility("has_semiopaque_transparency");
+    autocfg::emit_possibility("doc_decl_macro");
+    autocfg::emit_possibility("has_span_mixed_site");
+    autocfg::emit_possibility("has_span_local_file");
 
-    let autocfg = autocfg::new();
-    let has_simple_decl_macro = autocfg
-        .probe(
-            r##"
-                #![feature(decl_macro, rustc_attrs)]
-                #[rustc_macro_transparency = "semitransparent"]
-                pub macro m {
-                    () => {},
-                    () => {},
-                }
-            "##,
-        )
-        .unwrap_or_default();
-    if has_simple_decl_macro {
+    // Detection is best-effort: if it can't be done, warn and use the stable
+    // expansion, rather than failing the build of every crate using this one.
+    let autocfg = autocfg::AutoCfg::new()
+        .map_err(|error| {
+            println!(
+                "cargo:warning=macro_pub: could not set up compiler feature detection ({}); \
+                 using the stable expansion",
+                error,
+            );
+        })
+        .ok();
+    let warned = std::cell::Cell::new(false);
+    let probe = |code: String| match autocfg.as_ref().map(|autocfg| autocfg.probe(code)) {
+        Some(Ok(supported)) => supported,
+        Some(Err(error)) => {
+            if !warned.replace(true) {
+                println!(
+                    "cargo:warning=macro_pub: could not run rustc to detect compiler features \
+                     ({}); using the stable expansion",
+                    error,
+                );
+            }
+            false
+        }
+        None => false,
+    };
+
+    // Stabilized in Rust 1.45; fall back to `Span::call_site` without.
+    let has_span_mixed_site = probe(
+        r##"
+            extern crate proc_macro;
+            pub fn probe() -> proc_macro::Span {
+                proc_macro::Span::mixed_site()
+            }
+        "##
+        .to_string(),
+    );
+    if has_span_mixed_site {
+        autocfg::emit("has_span_mixed_site");
+    }
+
+    // Stabilized in Rust 1.88; without it, the lock file only has macros'
+    // names to tell them apart by.
+    let has_span_local_file = probe(
+        r##"
+            extern crate proc_macro;
+            pub fn probe() -> Option<std::path::PathBuf> {
+                proc_macro::Span::call_site().local_file()
+            }
+        "##
+        .to_string(),
+    );
+    if has_span_local_file {
+        autocfg::emit("has_span_local_file");
+    }
+
+    // `semitransparent` was renamed to `semiopaque`; probe the current
+    // spelling first and fall back to the old one for older nightlies.
+    let transparency = ["semiopaque", "semitransparent"]
+        .iter()
+        .find(|&&transparency| {
+            probe(format!(
+                r##"
+                    #![feature(decl_macro, rustc_attrs)]
+                    #[rustc_macro_transparency = "{}"]
+                    pub macro m {{
+                        () => {{}},
+                        () => {{}},
+                    }}
+                "##,
+                transparency,
+            ))
+        })
+        .cloned();
+
+    // Reported in diagnostics that depend on what was detected.
+    let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let version = std::process::Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .filter(|version| !version.is_empty())
+        .unwrap_or_else(|| "an unknown rustc".to_string());
+    println!("cargo:rustc-env=MACRO_PUB_RUSTC_VERSION={}", version);
+
+    if let Some(transparency) = transparency {
         autocfg::emit("has_simple_decl_macro");
+        if transparency == "semiopaque" {
+            autocfg::emit("has_semiopaque_transparency");
+        }
+    }
+
+    // docs.rs always documents with a recent nightly, so use the documented
+    // expansion there even if the probe was somehow unable to succeed. The
+    // `nightly-docs` feature requests the same without relying on detection.
+    // Either only applies to compilers that accept `#![feature]`, since on
+    // others even the `cfg(doc)` copy warns that `macro` is experimental, and
+    // only to the `cfg(doc)` copy: the probe failed, so nothing else may use
+    // `macro`.
+    let docs_rs = std::env::var_os("DOCS_RS").is_some();
+    let nightly_docs = std::env::var_os("CARGO_FEATURE_NIGHTLY_DOCS").is_some();
+    let bootstrapped = std::env::var("RUSTC_BOOTSTRAP").map_or(false, |bootstrap| {
+        !bootstrap.is_empty() && bootstrap != "-1"
+    });
+    let unstable = version.contains("-nightly") || version.contains("-dev") || bootstrapped;
+    if transparency.is_some() || ((docs_rs || nightly_docs) && unstable) {
+        autocfg::emit("doc_decl_macro");
+    }
+
+    if docs_rs {
+        println!(
+            "cargo:warning=macro_pub: documenting public macros for docs.rs; crates using \
+             `#[macro_pub]` need `#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]`"
+        );
     }
 }
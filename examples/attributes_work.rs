@@ -1,4 +1,4 @@
-#![cfg_attr(has_simple_decl_macro, cfg_attr(doc, feature(decl_macro, rustc_attrs)))]
+#![cfg_attr(doc_decl_macro, cfg_attr(doc, feature(decl_macro, rustc_attrs)))]
 
 #[macro_use]
 extern crate macro_pub;
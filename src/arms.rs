@@ -0,0 +1,815 @@
+use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use crate::docs::signature;
+use crate::expand::{macro_pub, unraw, Config};
+use crate::lints::lint_level;
+use crate::parse::{arms, matchers, Args, Level, MacroRules, Strategy};
+
+/// The arms of a `#[macro_pub]` macro, rewritten for its options.
+pub(crate) struct Arms {
+    /// The arms as written, with the macro's invocations of itself qualified,
+    /// and without its internal rules.
+    pub(crate) written: TokenStream,
+    /// The arms the macro is defined with, which add the catch-all arm, and
+    /// record the macro's uses.
+    pub(crate) arms: TokenStream,
+    /// The hidden helper the internal rules moved to, if any.
+    pub(crate) helper: TokenStream,
+    /// The `#[doc(hidden)]` arms of `written`, and where they're hidden.
+    pub(crate) hidden: Vec<(usize, Span)>,
+    /// The catch-all arm of `arms`, if it has one.
+    pub(crate) catch_all: Option<usize>,
+    /// The `cfg`s the arms of `written` are documented as needing.
+    pub(crate) cfgs: Vec<(usize, TokenStream)>,
+    /// Whether the macro invokes itself by a name only textual scope finds.
+    pub(crate) textual: bool,
+}
+
+impl Arms {
+    /// The arms left out of the documented macro: the hidden ones, and the
+    /// catch-all arm.
+    pub(crate) fn hidden_indices(&self) -> Vec<usize> {
+        self.hidden
+            .iter()
+            .map(|&(arm, _)| arm)
+            .chain(self.catch_all)
+            .collect()
+    }
+}
+
+/// The arms of `item`, rewritten for `args`, adding lints about them to
+/// `lints`. The macro is `mangled` where it's exported under a mangled name,
+/// and `cross_crate` if it's used in other crates.
+pub(crate) fn rewrite_arms(
+    item: &MacroRules,
+    args: &Args,
+    config: &Config,
+    mangled: Option<&str>,
+    cross_crate: bool,
+    lints: &mut Vec<(&'static str, String, Span)>,
+) -> Arms {
+    // `#[macro_export(local_inner_macros)]` resolved each macro its arms
+    // invoke by name at the crate root, which `$crate::` still does.
+    let arms = if item.local_inner_macros {
+        qualify_inner_calls(item.arms.clone())
+    } else {
+        item.arms.clone()
+    };
+
+    let (arms, textual) = qualify_own_calls(arms, &item.name, args, config, cross_crate, lints);
+
+    // Internal rules move to a hidden helper, defined first so the macro can
+    // also use it by name, and as visible as the macro is anywhere.
+    let mut hidden = item.hidden_arms.clone();
+    let mut cfgs = item.arm_cfgs.clone();
+    let (helper, written) = if args.internal_rules {
+        split_internal_rules(
+            arms,
+            &mut hidden,
+            &mut cfgs,
+            item,
+            args,
+            config,
+            cross_crate,
+        )
+    } else {
+        (TokenStream::new(), arms)
+    };
+
+    // The catch-all arm is hidden like the arms it leaves out.
+    let (arms, catch_all) = if args.catch_all {
+        let hidden = hidden.iter().map(|&(arm, _)| arm).collect::<Vec<_>>();
+        let mut arms = written.clone();
+        arms.extend(catch_all_arm(&item.name, written.clone(), &hidden));
+        (arms, Some(matchers(written.clone()).len()))
+    } else {
+        (written.clone(), None)
+    };
+
+    // Every copy of the macro records its uses under the one exported name.
+    let arms = match mangled {
+        Some(mangled) if args.track_uses => track_arms(arms, mangled),
+        _ => arms,
+    };
+
+    Arms {
+        written,
+        arms,
+        helper,
+        hidden,
+        catch_all,
+        cfgs,
+        textual,
+    }
+}
+
+/// `macro_arms`, with the macro's invocations of itself pointed at the path it
+/// uses for itself elsewhere, or `expect_path` gives, and lints about those
+/// that can't be added to `lints`. Also returns whether the macro needs a copy
+/// under its own name for the invocations left as they are.
+fn qualify_own_calls(
+    macro_arms: TokenStream,
+    macro_name: &Ident,
+    args: &Args,
+    config: &Config,
+    cross_crate: bool,
+    lints: &mut Vec<(&'static str, String, Span)>,
+) -> (TokenStream, bool) {
+    // `$crate::name!` assumes the macro is at the crate root, as it was with
+    // `#[macro_export]`, so it's pointed at the path the macro uses for itself
+    // elsewhere, if any.
+    let legacy_named =
+        args.strategy == Some(Strategy::LegacyExport) || args.also_export_legacy_name;
+    let own_path = self_path(macro_arms.clone(), macro_name).or_else(|| {
+        args.expect_path
+            .as_ref()
+            .and_then(|path| crate_path(path, macro_name))
+    });
+    let root_paths = root_self_paths(macro_arms.clone(), macro_name);
+    let macro_arms = match own_path {
+        Some(ref path) if !legacy_named && !root_paths.is_empty() => {
+            qualify_root_self_paths(macro_arms, macro_name, path)
+        }
+        _ => macro_arms,
+    };
+    if lint_level(args, config, "crate_root_paths") != Level::Allow
+        && cross_crate
+        && own_path.is_none()
+        && !legacy_named
+    {
+        for span in root_paths {
+            lints.push((
+                "crate_root_paths",
+                format!(
+                    "`#[macro_pub]` macro `{0}!` uses `$crate::{0}!`, which only finds it if \
+                     it's defined at the crate root, since it's no longer `#[macro_export]`ed \
+                     there; write `$crate::path::to::{0}!` for the module defining it instead",
+                    macro_name,
+                ),
+                span,
+            ));
+        }
+    }
+
+    // A world-public macro invoking itself by name found itself before it was
+    // renamed, so it's pointed at the path it uses for itself elsewhere, or
+    // `expect_path` gives, or else also defined under its name for textual
+    // scope.
+    let self_calls = bare_self_calls(macro_arms.clone(), macro_name);
+    if !args.restriction.is_empty()
+        || args.strategy == Some(Strategy::LegacyExport)
+        || self_calls.is_empty()
+    {
+        return (macro_arms, false);
+    }
+    match own_path {
+        Some(path) => (qualify_self_calls(macro_arms, macro_name, &path), false),
+        None => {
+            for span in self_calls {
+                lints.push((
+                    "self_calls",
+                    format!(
+                        "`#[macro_pub]` macro `{0}!` invokes itself as `{0}!`, which only \
+                         finds it where it's in textual scope, not in other crates or other \
+                         modules; give `expect_path = \"crate::path::to::{0}\"` for the \
+                         module defining it, or write `$crate::path::to::{0}!`",
+                        macro_name,
+                    ),
+                    span,
+                ));
+            }
+            (macro_arms, true)
+        }
+    }
+}
+
+/// Moves the internal rules in `macro_arms` of `item`, whose matchers start
+/// with `@`, to a hidden helper, retargeting calls with `@` in either to it.
+/// Returns the helper's expansion, if there are any, and the remaining arms,
+/// which `hidden_arms` and `arm_cfgs` are renumbered for.
+fn split_internal_rules(
+    macro_arms: TokenStream,
+    hidden_arms: &mut Vec<(usize, Span)>,
+    arm_cfgs: &mut Vec<(usize, TokenStream)>,
+    item: &MacroRules,
+    args: &Args,
+    config: &Config,
+    cross_crate: bool,
+) -> (TokenStream, TokenStream) {
+    let macro_name = &item.name;
+    let helper_name = Ident::new(
+        &format!("__macro_pub_internal_{}", unraw(&macro_name.to_string())),
+        Span::call_site().located_at(macro_name.span()),
+    );
+    let mut public = TokenStream::new();
+    let mut internal = TokenStream::new();
+    let mut public_hidden = Vec::new();
+    let mut public_cfgs = Vec::new();
+    let mut public_count = 0;
+    for (i, arm) in arms(macro_arms).into_iter().enumerate() {
+        let arm = retarget_internal_calls(arm, macro_name, &helper_name);
+        if is_internal_arm(arm.clone()) {
+            internal.extend(arm);
+            continue;
+        }
+        for &(hidden, span) in hidden_arms.iter() {
+            if hidden == i {
+                public_hidden.push((public_count, span));
+            }
+        }
+        for (arm, predicate) in arm_cfgs.iter() {
+            if *arm == i {
+                public_cfgs.push((public_count, predicate.clone()));
+            }
+        }
+        public_count += 1;
+        public.extend(arm);
+    }
+    *hidden_arms = public_hidden;
+    *arm_cfgs = public_cfgs;
+    if internal.is_empty() {
+        return (TokenStream::new(), public);
+    }
+
+    // The helper is as visible as the macro is anywhere.
+    let mut attr = if cross_crate {
+        TokenStream::new()
+    } else {
+        let mut attr = args.restriction.clone();
+        attr.extend(",".parse::<TokenStream>().unwrap());
+        attr
+    };
+    attr.extend("hidden".parse::<TokenStream>().unwrap());
+    let strategy = match args.strategy {
+        Some(Strategy::UseAlias) => Some("use_alias"),
+        Some(Strategy::DeclMacro) => Some("decl_macro"),
+        Some(Strategy::LegacyExport) => Some("legacy_export"),
+        None => None,
+    };
+    if let Some(strategy) = strategy {
+        attr.extend(
+            format!(", strategy = {:?}", strategy)
+                .parse::<TokenStream>()
+                .unwrap(),
+        );
+    }
+    let mut helper_item = item.internal_attrs.clone();
+    helper_item.extend(vec![
+        TokenTree::Ident(item.macro_rules.clone()),
+        TokenTree::Punct(item.bang.clone()),
+        TokenTree::Ident(helper_name),
+        TokenTree::Group(Group::new(Delimiter::Brace, internal)),
+    ]);
+    let helper = macro_pub(attr.clone(), &Args::parse(attr), helper_item, config);
+    (helper, public)
+}
+
+/// The arms of `arms` except the `hidden` ones, each forwarding what it
+/// matched to the macro `target!` in scope where it's defined.
+pub(crate) fn forward_arms(arms: TokenStream, hidden: &[usize], target: &Ident) -> TokenStream {
+    let mut forwarded = TokenStream::new();
+    for (i, matcher) in matchers(arms).into_iter().enumerate() {
+        if hidden.contains(&i) {
+            continue;
+        }
+        let mut invocation = format!("{}!", target).parse::<TokenStream>().unwrap();
+        invocation.extend(Some(TokenTree::Group(Group::new(
+            Delimiter::Brace,
+            rematch(matcher.stream()),
+        ))));
+        forwarded.extend(Some(TokenTree::Group(matcher)));
+        forwarded.extend("=>".parse::<TokenStream>().unwrap());
+        forwarded.extend(vec![
+            TokenTree::Group(Group::new(Delimiter::Brace, invocation)),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ]);
+    }
+    forwarded
+}
+
+/// A transcriber reproducing what `matcher` matched, with each `$name:fragment`
+/// replaced by `$name`.
+fn rematch(matcher: TokenStream) -> TokenStream {
+    let mut transcriber = TokenStream::new();
+    let mut tokens = matcher.into_iter();
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Punct(ref dollar) if dollar.as_char() == '$' => match tokens.next() {
+                Some(TokenTree::Ident(name)) => {
+                    let mut lookahead = tokens.clone();
+                    if let (Some(TokenTree::Punct(colon)), Some(TokenTree::Ident(_))) =
+                        (lookahead.next(), lookahead.next())
+                    {
+                        if colon.as_char() == ':' {
+                            tokens = lookahead;
+                        }
+                    }
+                    transcriber.extend(vec![tt, TokenTree::Ident(name)]);
+                }
+                // Repetitions without metavariables can't be repeated in a
+                // transcriber, so write them out as few times as they match.
+                Some(TokenTree::Group(repeated)) if !has_metavariables(repeated.stream()) => {
+                    let mut operator = tokens.next();
+                    match operator {
+                        Some(TokenTree::Punct(ref op)) if "*+?".contains(op.as_char()) => {}
+                        _ => operator = tokens.next(),
+                    }
+                    match operator {
+                        Some(TokenTree::Punct(ref op)) if op.as_char() == '+' => {
+                            transcriber.extend(rematch(repeated.stream()))
+                        }
+                        _ => {}
+                    }
+                }
+                Some(TokenTree::Group(repeated)) => {
+                    let mut group = Group::new(repeated.delimiter(), rematch(repeated.stream()));
+                    group.set_span(repeated.span());
+                    transcriber.extend(vec![tt, TokenTree::Group(group)]);
+                }
+                next => transcriber.extend(Some(tt).into_iter().chain(next)),
+            },
+            TokenTree::Group(group) => {
+                let mut rematched = Group::new(group.delimiter(), rematch(group.stream()));
+                rematched.set_span(group.span());
+                transcriber.extend(Some(TokenTree::Group(rematched)));
+            }
+            tt => transcriber.extend(Some(tt)),
+        }
+    }
+    transcriber
+}
+
+/// `tokens` with each `$crate::path::to::name!` replaced by
+/// `$crate::path::to::target!`.
+pub(crate) fn retarget_self_paths(
+    tokens: TokenStream,
+    name: &Ident,
+    target: &Ident,
+) -> TokenStream {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut retargeted = TokenStream::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        // `$crate`, then `::segment`s, the last of which is followed by `!`.
+        let is_crate = match (&tokens[i], tokens.get(i + 1)) {
+            (TokenTree::Punct(dollar), Some(TokenTree::Ident(krate))) => {
+                dollar.as_char() == '$' && krate == "crate"
+            }
+            _ => false,
+        };
+        if is_crate {
+            let mut end = i + 2;
+            let mut last = None;
+            while let (
+                Some(TokenTree::Punct(a)),
+                Some(TokenTree::Punct(b)),
+                Some(TokenTree::Ident(segment)),
+            ) = (tokens.get(end), tokens.get(end + 1), tokens.get(end + 2))
+            {
+                if a.as_char() != ':' || b.as_char() != ':' {
+                    break;
+                }
+                last = Some(segment);
+                end += 3;
+            }
+            let is_call = match tokens.get(end) {
+                Some(TokenTree::Punct(bang)) => bang.as_char() == '!',
+                _ => false,
+            };
+            if is_call && last == Some(name) {
+                retargeted.extend(tokens[i..end - 1].iter().cloned());
+                let mut target = target.clone();
+                target.set_span(tokens[end - 1].span());
+                retargeted.extend(Some(TokenTree::Ident(target)));
+                i = end;
+                continue;
+            }
+        }
+        match tokens[i] {
+            TokenTree::Group(ref group) => {
+                let mut rewritten = Group::new(
+                    group.delimiter(),
+                    retarget_self_paths(group.stream(), name, target),
+                );
+                rewritten.set_span(group.span());
+                retargeted.extend(Some(TokenTree::Group(rewritten)));
+            }
+            ref tt => retargeted.extend(Some(tt.clone())),
+        }
+        i += 1;
+    }
+    retargeted
+}
+
+/// The spans of each `name!` in `tokens` invoking it by itself, rather than
+/// by path.
+fn bare_self_calls(tokens: TokenStream, name: &Ident) -> Vec<Span> {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut spans = Vec::new();
+    for i in 0..tokens.len() {
+        match tokens[i] {
+            TokenTree::Group(ref group) => spans.extend(bare_self_calls(group.stream(), name)),
+            _ if is_bare_self_call(&tokens, i, name) => spans.push(tokens[i].span()),
+            _ => {}
+        }
+    }
+    spans
+}
+
+/// Whether `tokens[i]` is `name` followed by `!`, and not the end of a path
+/// or a metavariable.
+fn is_bare_self_call(tokens: &[TokenTree], i: usize, name: &Ident) -> bool {
+    let is_name = match tokens[i] {
+        TokenTree::Ident(ref ident) => ident == name,
+        _ => false,
+    };
+    let is_call = match tokens.get(i + 1) {
+        Some(TokenTree::Punct(bang)) => bang.as_char() == '!',
+        _ => false,
+    };
+    let after_path = match i.checked_sub(1).map(|before| &tokens[before]) {
+        Some(TokenTree::Punct(punct)) => punct.as_char() == ':' || punct.as_char() == '$',
+        _ => false,
+    };
+    is_name && is_call && !after_path
+}
+
+/// The first `$crate::path::to::name!` in `tokens`, with at least one module
+/// in its path.
+fn self_path(tokens: TokenStream, name: &Ident) -> Option<TokenStream> {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    for i in 0..tokens.len() {
+        if let TokenTree::Group(ref group) = tokens[i] {
+            if let Some(path) = self_path(group.stream(), name) {
+                return Some(path);
+            }
+        }
+        let is_crate = match (&tokens[i], tokens.get(i + 1)) {
+            (TokenTree::Punct(dollar), Some(TokenTree::Ident(krate))) => {
+                dollar.as_char() == '$' && krate == "crate"
+            }
+            _ => false,
+        };
+        if !is_crate {
+            continue;
+        }
+        let mut end = i + 2;
+        while let (
+            Some(TokenTree::Punct(a)),
+            Some(TokenTree::Punct(b)),
+            Some(TokenTree::Ident(segment)),
+        ) = (tokens.get(end), tokens.get(end + 1), tokens.get(end + 2))
+        {
+            if a.as_char() != ':' || b.as_char() != ':' {
+                break;
+            }
+            end += 3;
+            let is_call = match tokens.get(end) {
+                Some(TokenTree::Punct(bang)) => bang.as_char() == '!',
+                _ => false,
+            };
+            if is_call && segment == name && end > i + 5 {
+                return Some(tokens[i..end].iter().cloned().collect());
+            }
+        }
+    }
+    None
+}
+
+/// `path`, from the `expect_path` option, as `$crate::path::to::name`, if
+/// it's `crate::path::to::name`.
+fn crate_path(path: &TokenStream, name: &Ident) -> Option<TokenStream> {
+    let tokens = path.clone().into_iter().collect::<Vec<_>>();
+    let is_crate = match tokens.first() {
+        Some(TokenTree::Ident(krate)) => krate == "crate",
+        _ => false,
+    };
+    let is_name = match tokens.last() {
+        Some(TokenTree::Ident(last)) => last == name,
+        _ => false,
+    };
+    if !is_crate || !is_name || tokens.len() < 4 {
+        return None;
+    }
+    let mut dollar = Punct::new('$', Spacing::Alone);
+    dollar.set_span(tokens[0].span());
+    let mut path = TokenStream::from(TokenTree::Punct(dollar));
+    path.extend(tokens);
+    Some(path)
+}
+
+/// Whether `tokens[i..]` start with `$crate::name!`.
+fn is_root_self_path(tokens: &[TokenTree], i: usize, name: &Ident) -> bool {
+    match (
+        &tokens[i],
+        tokens.get(i + 1),
+        tokens.get(i + 2),
+        tokens.get(i + 3),
+        tokens.get(i + 4),
+        tokens.get(i + 5),
+    ) {
+        (
+            TokenTree::Punct(dollar),
+            Some(TokenTree::Ident(krate)),
+            Some(TokenTree::Punct(a)),
+            Some(TokenTree::Punct(b)),
+            Some(TokenTree::Ident(segment)),
+            Some(TokenTree::Punct(bang)),
+        ) => {
+            dollar.as_char() == '$'
+                && krate == "crate"
+                && a.as_char() == ':'
+                && b.as_char() == ':'
+                && segment == name
+                && bang.as_char() == '!'
+        }
+        _ => false,
+    }
+}
+
+/// The spans of each `$crate::name!` in `tokens`.
+fn root_self_paths(tokens: TokenStream, name: &Ident) -> Vec<Span> {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut spans = Vec::new();
+    for i in 0..tokens.len() {
+        match tokens[i] {
+            TokenTree::Group(ref group) => spans.extend(root_self_paths(group.stream(), name)),
+            _ if is_root_self_path(&tokens, i, name) => spans.push(tokens[i + 1].span()),
+            _ => {}
+        }
+    }
+    spans
+}
+
+/// `tokens` with each `$crate::name!` replaced by `path!`.
+fn qualify_root_self_paths(tokens: TokenStream, name: &Ident, path: &TokenStream) -> TokenStream {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut qualified = TokenStream::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i] {
+            TokenTree::Group(ref group) => {
+                let mut rewritten = Group::new(
+                    group.delimiter(),
+                    qualify_root_self_paths(group.stream(), name, path),
+                );
+                rewritten.set_span(group.span());
+                qualified.extend(Some(TokenTree::Group(rewritten)));
+            }
+            _ if is_root_self_path(&tokens, i, name) => {
+                qualified.extend(path.clone());
+                i += 5;
+                continue;
+            }
+            ref tt => qualified.extend(Some(tt.clone())),
+        }
+        i += 1;
+    }
+    qualified
+}
+
+/// `tokens` with each `name!` invoking the macro by itself replaced by
+/// `path!`.
+fn qualify_self_calls(tokens: TokenStream, name: &Ident, path: &TokenStream) -> TokenStream {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut qualified = TokenStream::new();
+    for (i, tt) in tokens.iter().enumerate() {
+        match *tt {
+            TokenTree::Group(ref group) => {
+                let mut rewritten = Group::new(
+                    group.delimiter(),
+                    qualify_self_calls(group.stream(), name, path),
+                );
+                rewritten.set_span(group.span());
+                qualified.extend(Some(TokenTree::Group(rewritten)));
+            }
+            _ if is_bare_self_call(&tokens, i, name) => qualified.extend(path.clone()),
+            ref tt => qualified.extend(Some(tt.clone())),
+        }
+    }
+    qualified
+}
+
+/// `arms` with each `name!` in their transcribers replaced by
+/// `$crate::name!`, as `#[macro_export(local_inner_macros)]` resolved them.
+fn qualify_inner_calls(arms: TokenStream) -> TokenStream {
+    let mut qualified = TokenStream::new();
+    let mut transcriber = false;
+    for tt in arms {
+        let tt = match tt {
+            TokenTree::Punct(ref punct) if punct.as_char() == '>' => {
+                transcriber = true;
+                tt
+            }
+            TokenTree::Group(ref group) if transcriber => {
+                transcriber = false;
+                let mut rewritten =
+                    Group::new(group.delimiter(), crate_qualified_calls(group.stream()));
+                rewritten.set_span(group.span());
+                TokenTree::Group(rewritten)
+            }
+            tt => tt,
+        };
+        qualified.extend(Some(tt));
+    }
+    qualified
+}
+
+/// `tokens` with each `name!(...)` not on a path, or defining a macro,
+/// replaced by `$crate::name!(...)`.
+fn crate_qualified_calls(tokens: TokenStream) -> TokenStream {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut qualified = TokenStream::new();
+    for (i, tt) in tokens.iter().enumerate() {
+        match *tt {
+            TokenTree::Group(ref group) => {
+                let mut rewritten =
+                    Group::new(group.delimiter(), crate_qualified_calls(group.stream()));
+                rewritten.set_span(group.span());
+                qualified.extend(Some(TokenTree::Group(rewritten)));
+            }
+            TokenTree::Ident(ref ident) if ident != "macro_rules" => {
+                let invoked = match (tokens.get(i + 1), tokens.get(i + 2)) {
+                    (Some(TokenTree::Punct(bang)), Some(TokenTree::Group(_))) => {
+                        bang.as_char() == '!'
+                    }
+                    _ => false,
+                };
+                let on_path = match i.checked_sub(1).map(|i| &tokens[i]) {
+                    Some(TokenTree::Punct(punct)) => {
+                        punct.as_char() == ':' || punct.as_char() == '$'
+                    }
+                    _ => false,
+                };
+                if invoked && !on_path {
+                    let mut dollar = Punct::new('$', Spacing::Alone);
+                    dollar.set_span(ident.span());
+                    let mut colons = Punct::new(':', Spacing::Joint);
+                    colons.set_span(ident.span());
+                    let mut colon = Punct::new(':', Spacing::Alone);
+                    colon.set_span(ident.span());
+                    qualified.extend(vec![
+                        TokenTree::Punct(dollar),
+                        TokenTree::Ident(Ident::new("crate", ident.span())),
+                        TokenTree::Punct(colons),
+                        TokenTree::Punct(colon),
+                    ]);
+                }
+                qualified.extend(Some(tt.clone()));
+            }
+            ref tt => qualified.extend(Some(tt.clone())),
+        }
+    }
+    qualified
+}
+
+/// Whether the matcher of `arm` starts with `@`.
+fn is_internal_arm(arm: TokenStream) -> bool {
+    match arm.into_iter().next() {
+        Some(TokenTree::Group(matcher)) => match matcher.stream().into_iter().next() {
+            Some(TokenTree::Punct(at)) => at.as_char() == '@',
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// `tokens` with each `name!` or `$crate::path::to::name!` invocation whose
+/// input starts with `@` retargeted to `target!` or
+/// `$crate::path::to::target!`.
+fn retarget_internal_calls(tokens: TokenStream, name: &Ident, target: &Ident) -> TokenStream {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut retargeted = TokenStream::new();
+    for (i, tt) in tokens.iter().enumerate() {
+        let tt = match *tt {
+            TokenTree::Ident(ref ident) if ident == name && is_internal_call(&tokens[i + 1..]) => {
+                let mut target = target.clone();
+                target.set_span(ident.span());
+                TokenTree::Ident(target)
+            }
+            TokenTree::Group(ref group) => {
+                let mut rewritten = Group::new(
+                    group.delimiter(),
+                    retarget_internal_calls(group.stream(), name, target),
+                );
+                rewritten.set_span(group.span());
+                TokenTree::Group(rewritten)
+            }
+            ref tt => tt.clone(),
+        };
+        retargeted.extend(Some(tt));
+    }
+    retargeted
+}
+
+/// Whether `tokens` start with `!` and a group starting with `@`.
+fn is_internal_call(tokens: &[TokenTree]) -> bool {
+    match (tokens.first(), tokens.get(1)) {
+        (Some(TokenTree::Punct(bang)), Some(TokenTree::Group(input))) if bang.as_char() == '!' => {
+            match input.stream().into_iter().next() {
+                Some(TokenTree::Punct(at)) => at.as_char() == '@',
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Checks whether `matcher` has any `$name:fragment`s.
+fn has_metavariables(matcher: TokenStream) -> bool {
+    let mut dollar = false;
+    matcher.into_iter().any(|tt| {
+        let found = match tt {
+            TokenTree::Ident(ref name) => dollar && name != "crate",
+            TokenTree::Group(ref group) => has_metavariables(group.stream()),
+            _ => false,
+        };
+        dollar = match tt {
+            TokenTree::Punct(ref punct) => punct.as_char() == '$',
+            _ => false,
+        };
+        found
+    })
+}
+
+/// Prefixes the matcher of each arm in `arms` with `guard`.
+pub(crate) fn guard_arms(arms: TokenStream, guard: &Ident) -> TokenStream {
+    let mut guarded = TokenStream::new();
+    let mut arm_start = true;
+    for tt in arms {
+        let tt = match tt {
+            TokenTree::Group(matcher) if arm_start => {
+                let mut stream: TokenStream = TokenTree::Ident(guard.clone()).into();
+                stream.extend(matcher.stream());
+                let mut group = Group::new(matcher.delimiter(), stream);
+                group.set_span(matcher.span());
+                TokenTree::Group(group)
+            }
+            tt => tt,
+        };
+        arm_start = match tt {
+            TokenTree::Punct(ref punct) => punct.as_char() == ';',
+            _ => false,
+        };
+        guarded.extend(Some(tt));
+    }
+    guarded
+}
+
+/// Wraps the transcriber of each arm in `arms` in an invocation of the
+/// crate's re-export of `macro_pub::track_use!`, which records a use of the
+/// macro exported as `mangled` and expands to the transcriber's contents.
+fn track_arms(arms: TokenStream, mangled: &str) -> TokenStream {
+    let mut tracked = TokenStream::new();
+    // The matcher is the first group of each arm, and the transcriber the second.
+    let mut groups = 0;
+    for tt in arms {
+        let tt = match tt {
+            TokenTree::Group(transcriber) if groups == 1 => {
+                let mut stream = "$crate::__macro_pub_track_use!"
+                    .parse::<TokenStream>()
+                    .unwrap();
+                let mut contents: TokenStream = TokenTree::Literal(Literal::string(mangled)).into();
+                contents.extend(transcriber.stream());
+                stream.extend(Some(TokenTree::Group(Group::new(
+                    Delimiter::Brace,
+                    contents,
+                ))));
+                let mut group = Group::new(transcriber.delimiter(), stream);
+                group.set_span(transcriber.span());
+                TokenTree::Group(group)
+            }
+            tt => tt,
+        };
+        match tt {
+            TokenTree::Group(_) => groups += 1,
+            TokenTree::Punct(ref punct) if punct.as_char() == ';' => groups = 0,
+            _ => {}
+        }
+        tracked.extend(Some(tt));
+    }
+    tracked
+}
+
+/// A last arm for `arms` of `name!`, failing with the signatures of the arms
+/// not in `hidden`, and not internal rules.
+fn catch_all_arm(name: &Ident, arms: TokenStream, hidden: &[usize]) -> TokenStream {
+    let mut expected = String::new();
+    for (i, matcher) in matchers(arms).into_iter().enumerate() {
+        let internal = match matcher.stream().into_iter().next() {
+            Some(TokenTree::Punct(ref punct)) => punct.as_char() == '@',
+            _ => false,
+        };
+        if !hidden.contains(&i) && !internal {
+            expected.push_str("\n    ");
+            expected.push_str(&signature(name, &matcher));
+        }
+    }
+    format!(
+        "($($tokens:tt)*) => {{ compile_error! {{ concat!({:?}, stringify!($($tokens)*), {:?}) }} }};",
+        format!("`{}!` doesn't accept `", name),
+        format!("`; expected one of:{}", expected),
+    )
+    .parse()
+    .unwrap()
+}
@@ -0,0 +1,196 @@
+use proc_macro2::{Delimiter, Group, Punct, Spacing, TokenStream, TokenTree};
+
+/// Expands `cfg_macros! { input }`.
+///
+/// The input is a `macro_rules!` macro, optionally with a `#[macro_pub(...)]`
+/// attribute, whose body is a list of `#[cfg(...)]`-gated groups of arms:
+///
+/// ```text
+/// /// Docs for every variant.
+/// #[macro_pub(crate)]
+/// macro_rules! name {
+///     #[cfg(unix)] {
+///         () => { '/' };
+///     }
+///     #[cfg(windows)] {
+///         () => { '\\' };
+///     }
+/// }
+/// ```
+///
+/// Each group becomes its own `#[cfg(...)] #[macro_pub(...)]` macro, with the
+/// other attributes, so that exactly one is mounted under the name.
+pub(crate) fn cfg_macros(input: TokenStream) -> TokenStream {
+    let mut attrs = TokenStream::new();
+    let mut macro_pub = None;
+    let mut tokens = input.into_iter();
+    let (macro_rules, bang, name, body) = loop {
+        match tokens.next() {
+            Some(TokenTree::Punct(pound)) if pound.as_char() == '#' => {
+                let attr = match tokens.next() {
+                    Some(TokenTree::Group(attr)) if attr.delimiter() == Delimiter::Bracket => attr,
+                    _ => return error(EXPECTED),
+                };
+                if is_macro_pub(&attr) {
+                    if macro_pub.is_some() {
+                        return error("`macro_pub::cfg_macros!` expects one `#[macro_pub]`");
+                    }
+                    macro_pub = Some(attr.stream().into_iter().skip(1).collect::<TokenStream>());
+                } else {
+                    attrs.extend(vec![TokenTree::Punct(pound), TokenTree::Group(attr)]);
+                }
+            }
+            Some(TokenTree::Ident(macro_rules)) if macro_rules == "macro_rules" => {
+                match (tokens.next(), tokens.next(), tokens.next(), tokens.next()) {
+                    (
+                        Some(TokenTree::Punct(bang)),
+                        Some(TokenTree::Ident(name)),
+                        Some(TokenTree::Group(body)),
+                        None,
+                    ) if bang.as_char() == '!' && body.delimiter() == Delimiter::Brace => {
+                        break (macro_rules, bang, name, body);
+                    }
+                    _ => return error(EXPECTED),
+                }
+            }
+            _ => return error(EXPECTED),
+        }
+    };
+
+    let mut macro_pub_attr: TokenStream = "::macro_pub::macro_pub".parse().unwrap();
+    macro_pub_attr.extend(macro_pub);
+
+    let mut output = TokenStream::new();
+    let mut variants = body.stream().into_iter();
+    loop {
+        let cfg = match (variants.next(), variants.next()) {
+            (None, _) => break,
+            (Some(TokenTree::Punct(pound)), Some(TokenTree::Group(cfg)))
+                if pound.as_char() == '#' && is_cfg(&cfg) =>
+            {
+                vec![TokenTree::Punct(pound), TokenTree::Group(cfg)]
+            }
+            _ => return error(EXPECTED),
+        };
+        let arms = match variants.next() {
+            Some(TokenTree::Group(arms)) if arms.delimiter() == Delimiter::Brace => arms,
+            _ => return error(EXPECTED),
+        };
+
+        // The `cfg` comes first, so it removes the other variants before
+        // `#[macro_pub]` sees them.
+        output.extend(cfg);
+        output.extend(vec![
+            TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+            TokenTree::Group(Group::new(Delimiter::Bracket, macro_pub_attr.clone())),
+        ]);
+        output.extend(attrs.clone());
+        output.extend(vec![
+            TokenTree::Ident(macro_rules.clone()),
+            TokenTree::Punct(bang.clone()),
+            TokenTree::Ident(name.clone()),
+            TokenTree::Group(arms),
+        ]);
+    }
+    if output.is_empty() {
+        return error(EXPECTED);
+    }
+    output
+}
+
+const EXPECTED: &str =
+    "`macro_pub::cfg_macros!` expects a `macro_rules!` macro of `#[cfg(...)] { arms }` variants";
+
+/// Checks whether `attr`, the inside of `#[...]`, is `macro_pub` or
+/// `macro_pub(...)`.
+pub(crate) fn is_macro_pub(attr: &Group) -> bool {
+    let mut tokens = attr.stream().into_iter();
+    match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(name)), args, None) if name == "macro_pub" => match args {
+            None => true,
+            Some(TokenTree::Group(args)) => args.delimiter() == Delimiter::Parenthesis,
+            Some(_) => false,
+        },
+        _ => false,
+    }
+}
+
+/// Checks whether `attr` is `[cfg(...)]`.
+fn is_cfg(attr: &Group) -> bool {
+    let mut tokens = attr.stream().into_iter();
+    attr.delimiter() == Delimiter::Bracket
+        && match (tokens.next(), tokens.next(), tokens.next()) {
+            (Some(TokenTree::Ident(name)), Some(TokenTree::Group(_)), None) => name == "cfg",
+            _ => false,
+        }
+}
+
+fn error(message: &str) -> TokenStream {
+    format!("compile_error! {{ {:?} }}", message)
+        .parse()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn assert_expands(input: &str, expected: &str) {
+        assert_eq!(
+            cfg_macros(input.parse().unwrap()).to_string(),
+            expected.parse::<TokenStream>().unwrap().to_string(),
+        );
+    }
+
+    #[test]
+    fn variants() {
+        assert_expands(
+            r#"
+                /// Docs.
+                #[macro_pub(crate)]
+                #[allow(unused_macros)]
+                macro_rules! sep {
+                    #[cfg(unix)] { () => { '/' }; }
+                    #[cfg(not(unix))] { () => { '\\' }; }
+                }
+            "#,
+            r#"
+                #[cfg(unix)]
+                #[::macro_pub::macro_pub(crate)]
+                #[doc = " Docs."]
+                #[allow(unused_macros)]
+                macro_rules! sep { () => { '/' }; }
+                #[cfg(not(unix))]
+                #[::macro_pub::macro_pub(crate)]
+                #[doc = " Docs."]
+                #[allow(unused_macros)]
+                macro_rules! sep { () => { '\\' }; }
+            "#,
+        );
+        assert_expands(
+            "macro_rules! m { #[cfg(all())] {} }",
+            "#[cfg(all())] #[::macro_pub::macro_pub] macro_rules! m {}",
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let expected = format!("compile_error! {{ {:?} }}", EXPECTED);
+        for input in &[
+            "",
+            "fn f() {}",
+            "macro_rules! m {}",
+            "macro_rules! m { () => {}; }",
+            "macro_rules! m { #[cfg(unix)] }",
+            "macro_rules! m { #[test] {} }",
+            "macro_rules! m { #[cfg(unix)] {} } fn f() {}",
+        ] {
+            assert_expands(input, &expected);
+        }
+        assert_expands(
+            "#[macro_pub] #[macro_pub(crate)] macro_rules! m { #[cfg(unix)] {} }",
+            r#"compile_error! { "`macro_pub::cfg_macros!` expects one `#[macro_pub]`" }"#,
+        );
+    }
+}
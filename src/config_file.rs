@@ -0,0 +1,236 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Delimiter, Group, Literal, Punct, Spacing, TokenStream, TokenTree};
+
+use crate::parse::{Level, LINTS};
+
+/// Crate-wide defaults from the file at `MACRO_PUB_CONFIG`.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ConfigFile {
+    /// `doc_required = true`: world-public macros must be documented.
+    pub(crate) doc_required: bool,
+    /// `pin_backend = true`: macros expand the same on every toolchain.
+    pub(crate) pin_backend: bool,
+    /// The `[lints]` table: the levels of `#[macro_pub]`'s lints, in order.
+    pub(crate) lint_levels: Vec<(String, Level)>,
+}
+
+/// Reads the file at `MACRO_PUB_CONFIG`, if it's set, returning its absolute
+/// path along with its settings, or why it can't be used.
+pub(crate) fn from_env() -> Result<Option<(PathBuf, ConfigFile)>, String> {
+    let path = match env::var_os("MACRO_PUB_CONFIG") {
+        Some(path) => PathBuf::from(path),
+        None => return Ok(None),
+    };
+    // Relative to the crate's `Cargo.toml`, like `MACRO_PUB_LOCKFILE`, or the
+    // directory rustc is run in without Cargo.
+    let dir = match env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => env::current_dir().unwrap_or_default(),
+    };
+    let path = dir.join(path);
+    let file = read(&path)?;
+    Ok(Some((path, file)))
+}
+
+/// Reads and parses the file at `path`.
+fn read(path: &Path) -> Result<ConfigFile, String> {
+    let contents = fs::read_to_string(path).map_err(|error| {
+        format!(
+            "`#[macro_pub]` could not read `MACRO_PUB_CONFIG` at {}: {}",
+            path.display(),
+            error,
+        )
+    })?;
+    parse(&contents)
+}
+
+/// Includes the file at `path` in an unused constant, so that rustc records
+/// it as a dependency of the crate, and Cargo rebuilds the crate when it
+/// changes.
+pub(crate) fn track(path: &Path) -> TokenStream {
+    let path = match path.to_str() {
+        Some(path) => path,
+        None => return TokenStream::new(),
+    };
+    let mut output = "const _: &[u8] = include_bytes!"
+        .parse::<TokenStream>()
+        .unwrap();
+    output.extend(vec![
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenTree::Literal(Literal::string(path)).into(),
+        )),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+    output
+}
+
+/// Parses `contents`, the small part of TOML the settings need: `key = value`
+/// lines, `#` comments, and the `[lints]` table.
+fn parse(contents: &str) -> Result<ConfigFile, String> {
+    let mut file = ConfigFile::default();
+    let mut seen = Vec::new();
+    let mut in_lints = false;
+    for (i, line) in contents.lines().enumerate() {
+        let error = |message: String| format!("`MACRO_PUB_CONFIG` line {}: {}", i + 1, message);
+        let line = without_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') {
+            match line {
+                "[lints]" if !in_lints => in_lints = true,
+                _ => {
+                    return Err(error(format!(
+                        "expected `[lints]`, the only table, once, not `{}`",
+                        line,
+                    )))
+                }
+            }
+            continue;
+        }
+        let (key, value) = match line.find('=') {
+            Some(eq) => (line[..eq].trim(), line[eq + 1..].trim()),
+            None => return Err(error(format!("expected `key = value`, not `{}`", line))),
+        };
+        let qualified = if in_lints {
+            format!("lints.{}", key)
+        } else {
+            key.to_string()
+        };
+        if seen.contains(&qualified) {
+            return Err(error(format!("`{}` is repeated", key)));
+        }
+        if in_lints {
+            if !LINTS.contains(&key) {
+                return Err(error(format!(
+                    "`#[macro_pub]` has no lint `{}`; its lints are `{}`",
+                    key,
+                    LINTS.join("`, `"),
+                )));
+            }
+            let level = match value {
+                "\"allow\"" => Level::Allow,
+                "\"warn\"" => Level::Warn,
+                "\"deny\"" => Level::Deny,
+                _ => {
+                    return Err(error(format!(
+                        "lint `{}` expects `\"allow\"`, `\"warn\"`, or `\"deny\"`",
+                        key,
+                    )))
+                }
+            };
+            file.lint_levels.push((key.to_string(), level));
+        } else {
+            let setting = match key {
+                "doc_required" => &mut file.doc_required,
+                "pin_backend" => &mut file.pin_backend,
+                _ => {
+                    return Err(error(format!(
+                        "there's no setting `{}`; the settings are `doc_required`, \
+                         `pin_backend`, and the `[lints]` table",
+                        key,
+                    )))
+                }
+            };
+            *setting = match value {
+                "true" => true,
+                "false" => false,
+                _ => return Err(error(format!("`{}` expects `true` or `false`", key))),
+            };
+        }
+        seen.push(qualified);
+    }
+    Ok(file)
+}
+
+/// `line` up to any `#` comment. None of the values are strings that could
+/// contain a `#`.
+fn without_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(hash) => &line[..hash],
+        None => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings() {
+        assert_eq!(parse(""), Ok(ConfigFile::default()));
+        let file = parse(
+            "# Defaults for every crate.\n\
+             doc_required = true\n\
+             pin_backend = false # for now\n\
+             \n\
+             [lints]\n\
+             pointless_options = \"deny\"\n\
+             std_names = \"warn\"\n",
+        );
+        assert_eq!(
+            file,
+            Ok(ConfigFile {
+                doc_required: true,
+                pin_backend: false,
+                lint_levels: vec![
+                    ("pointless_options".to_string(), Level::Deny),
+                    ("std_names".to_string(), Level::Warn),
+                ],
+            }),
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |contents| parse(contents).unwrap_err();
+        assert_eq!(
+            error("doc_required"),
+            "`MACRO_PUB_CONFIG` line 1: expected `key = value`, not `doc_required`",
+        );
+        assert_eq!(
+            error("doc_required = yes"),
+            "`MACRO_PUB_CONFIG` line 1: `doc_required` expects `true` or `false`",
+        );
+        assert_eq!(
+            error("\ndoc_required = true\ndoc_required = false"),
+            "`MACRO_PUB_CONFIG` line 3: `doc_required` is repeated",
+        );
+        assert_eq!(
+            error("signatures = true"),
+            "`MACRO_PUB_CONFIG` line 1: there's no setting `signatures`; the settings are \
+             `doc_required`, `pin_backend`, and the `[lints]` table",
+        );
+        assert_eq!(
+            error("[package]"),
+            "`MACRO_PUB_CONFIG` line 1: expected `[lints]`, the only table, once, not `[package]`",
+        );
+        assert_eq!(
+            error("[lints]\nstd_names = \"forbid\""),
+            "`MACRO_PUB_CONFIG` line 2: lint `std_names` expects `\"allow\"`, `\"warn\"`, or \
+             `\"deny\"`",
+        );
+        assert!(error("[lints]\nunused = \"allow\"")
+            .starts_with("`MACRO_PUB_CONFIG` line 2: `#[macro_pub]` has no lint `unused`;"));
+
+        let missing = env::temp_dir().join(format!("macro_pub-config-{}", std::process::id()));
+        let error = read(&missing).unwrap_err();
+        assert!(
+            error.starts_with("`#[macro_pub]` could not read `MACRO_PUB_CONFIG` at "),
+            "{}",
+            error,
+        );
+    }
+
+    #[test]
+    fn tracks_file() {
+        assert_eq!(
+            track(Path::new("/crate/macro_pub.toml")).to_string(),
+            "const _ : & [u8] = include_bytes ! (\"/crate/macro_pub.toml\") ;",
+        );
+    }
+}
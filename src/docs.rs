@@ -0,0 +1,347 @@
+use proc_macro2::{Delimiter, Group, Ident, TokenStream, TokenTree};
+
+use crate::arms::Arms;
+use crate::expand::write_tokens;
+use crate::parse::{matchers, transcribers, Args};
+
+/// `attrs`, with the documentation the options in `args` add to the macro's:
+/// its safety, signatures, source, and stability.
+pub(crate) fn macro_docs(
+    attrs: TokenStream,
+    args: &Args,
+    macro_name: &Ident,
+    arms: &Arms,
+    hidden_attr: bool,
+) -> TokenStream {
+    let mut attrs = attrs;
+    if args.unsafe_macro {
+        let has_safety = has_doc_heading(attrs.clone(), "# Safety");
+        let mut docs = doc_lines(&[
+            "<div class=\"warning\">This macro expands to <code>unsafe</code> code.</div>"
+                .to_string(),
+            String::new(),
+        ]);
+        docs.extend(attrs);
+        if !has_safety {
+            docs.extend(doc_lines(&[
+                String::new(),
+                "# Safety".to_string(),
+                String::new(),
+                "This macro expands to `unsafe` code, so uses of it must uphold the \
+                 requirements documented above."
+                    .to_string(),
+            ]));
+        }
+        attrs = docs;
+    }
+    let hidden_arms = arms.hidden_indices();
+    if args.signatures {
+        attrs.extend(signatures_doc(
+            macro_name,
+            arms.arms.clone(),
+            &hidden_arms,
+            &arms.cfgs,
+        ));
+    } else if !arms.cfgs.is_empty() {
+        attrs.extend(arm_cfgs_doc(
+            macro_name,
+            arms.arms.clone(),
+            &hidden_arms,
+            &arms.cfgs,
+        ));
+    }
+    // The definition as written, before any arms are rewritten.
+    if args.source {
+        attrs.extend(source_doc(macro_name, arms.written.clone()));
+    }
+    if let Some(ref since) = args.since {
+        attrs.extend(doc_lines(&[
+            String::new(),
+            format!("*Available since version {}.*", since),
+        ]));
+    }
+    if let Some(ref feature) = args.feature {
+        attrs.extend(doc_lines(&[
+            String::new(),
+            format!("*Requires the `{}` feature.*", feature),
+        ]));
+    }
+    if args.unstable {
+        attrs.extend(doc_lines(&[
+            String::new(),
+            "<div class=\"stab unstable\">This macro is unstable, and may change or be removed \
+             in any release.</div>"
+                .to_string(),
+        ]));
+    }
+    if (args.unstable || args.hidden) && !hidden_attr {
+        attrs.extend("#[doc(hidden)]".parse::<TokenStream>().unwrap());
+    }
+    attrs
+}
+
+/// A "Signatures" documentation section listing how `name!` can be invoked,
+/// except by the `hidden` arms, with the `cfgs` the arms are documented as
+/// needing.
+fn signatures_doc(
+    name: &Ident,
+    arms: TokenStream,
+    hidden: &[usize],
+    cfgs: &[(usize, TokenStream)],
+) -> TokenStream {
+    let mut lines = vec![
+        String::new(),
+        "# Signatures".to_string(),
+        String::new(),
+        "```text".to_string(),
+    ];
+    for (i, matcher) in matchers(arms).into_iter().enumerate() {
+        if hidden.contains(&i) {
+            continue;
+        }
+        let mut line = signature(name, &matcher);
+        for (_, predicate) in cfgs.iter().filter(|&&(arm, _)| arm == i) {
+            line.push_str("  // #[cfg(");
+            write_cfg(&mut line, predicate.clone());
+            line.push_str(")]");
+        }
+        lines.push(line);
+    }
+    lines.push("```".to_string());
+    doc_lines(&lines)
+}
+
+/// A line of documentation for each of the arms of `name!` with `cfgs`,
+/// except the `hidden` ones, noting what they need, like `feature`'s.
+fn arm_cfgs_doc(
+    name: &Ident,
+    arms: TokenStream,
+    hidden: &[usize],
+    cfgs: &[(usize, TokenStream)],
+) -> TokenStream {
+    let matchers = matchers(arms);
+    let mut lines = Vec::new();
+    for (arm, predicate) in cfgs {
+        let matcher = match matchers.get(*arm) {
+            Some(matcher) if !hidden.contains(arm) => matcher,
+            _ => continue,
+        };
+        let needs = match feature_name(predicate.clone()) {
+            Some(feature) => format!("the `{}` feature", feature),
+            None => {
+                let mut cfg = "`cfg(".to_string();
+                write_cfg(&mut cfg, predicate.clone());
+                cfg.push_str(")`");
+                cfg
+            }
+        };
+        lines.push(String::new());
+        lines.push(format!(
+            "*`{}` requires {}.*",
+            signature(name, matcher),
+            needs,
+        ));
+    }
+    doc_lines(&lines)
+}
+
+/// Writes the `cfg` `predicate` to `out` as it's usually written, like
+/// `any(unix, feature = "name")`.
+fn write_cfg(out: &mut String, predicate: TokenStream) {
+    for tt in predicate {
+        match tt {
+            TokenTree::Group(group) => {
+                out.push('(');
+                write_cfg(out, group.stream());
+                out.push(')');
+            }
+            TokenTree::Punct(ref punct) if punct.as_char() == ',' => out.push_str(", "),
+            TokenTree::Punct(ref punct) if punct.as_char() == '=' => out.push_str(" = "),
+            tt => out.push_str(&tt.to_string()),
+        }
+    }
+}
+
+/// The name of the feature `predicate` is, if it's `feature = "name"`.
+fn feature_name(predicate: TokenStream) -> Option<String> {
+    let mut tokens = predicate.into_iter();
+    match (tokens.next(), tokens.next(), tokens.next(), tokens.next()) {
+        (
+            Some(TokenTree::Ident(feature)),
+            Some(TokenTree::Punct(ref eq)),
+            Some(TokenTree::Literal(name)),
+            None,
+        ) if feature == "feature" && eq.as_char() == '=' => {
+            let name = name.to_string();
+            if name.len() >= 2 && name.starts_with('"') && name.ends_with('"') {
+                Some(name[1..name.len() - 1].to_string())
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// An invocation of `name!` with the arm `matcher`, like `name!($e:expr)`.
+pub(crate) fn signature(name: &Ident, matcher: &Group) -> String {
+    let (open, close) = match matcher.delimiter() {
+        Delimiter::Parenthesis => ("(", ")"),
+        Delimiter::Bracket => ("[", "]"),
+        Delimiter::Brace => ("{ ", " }"),
+        Delimiter::None => ("", ""),
+    };
+    let mut line = format!("{}!{}", name, open);
+    write_tokens(&mut line, matcher.stream());
+    line.push_str(close);
+    line
+}
+
+/// A "Definition" documentation section showing `macro_rules! name { arms }`.
+fn source_doc(name: &Ident, arms: TokenStream) -> TokenStream {
+    let mut lines = vec![
+        String::new(),
+        "# Definition".to_string(),
+        String::new(),
+        "```text".to_string(),
+        format!("macro_rules! {} {{", name),
+    ];
+    for (matcher, transcriber) in matchers(arms.clone()).into_iter().zip(transcribers(arms)) {
+        let mut line = "    ".to_string();
+        write_tokens(&mut line, TokenTree::Group(matcher).into());
+        line.push_str(" => ");
+        write_tokens(&mut line, TokenTree::Group(transcriber).into());
+        line.push(';');
+        lines.push(line);
+    }
+    lines.push("}".to_string());
+    lines.push("```".to_string());
+    doc_lines(&lines)
+}
+
+/// `#[doc = "line"]` attributes for each of `lines`.
+fn doc_lines(lines: &[String]) -> TokenStream {
+    lines
+        .iter()
+        .map(|line| format!("#[doc = {:?}]", line))
+        .collect::<String>()
+        .parse()
+        .unwrap()
+}
+
+/// Adds `line` to the start of each Rust code block in the doc attributes in
+/// `attrs`.
+pub(crate) fn doctest_docs(attrs: TokenStream, line: &str) -> TokenStream {
+    let mut docs = TokenStream::new();
+    let mut in_code = false;
+    let mut tokens = attrs.into_iter().peekable();
+    while let Some(tt) = tokens.next() {
+        docs.extend(Some(tt));
+        let group = match tokens.peek() {
+            Some(TokenTree::Group(group)) => group.clone(),
+            _ => continue,
+        };
+        docs.extend(tokens.next());
+        let text = match doc_text(&group) {
+            Some(text) => text,
+            None => continue,
+        };
+        let fence = text.trim_start();
+        if !fence.starts_with("```") || text.contains("\\n") {
+            continue;
+        }
+        in_code = !in_code;
+        if in_code && is_rust_fence(fence.trim_start_matches('`')) {
+            let indent = &text[..text.len() - fence.len()];
+            docs.extend(doc_lines(&[format!("{}{}", indent, line)]));
+        }
+    }
+    docs
+}
+
+/// The text of the doc attribute `[doc = "text"]`, escapes and all, if it is
+/// one.
+fn doc_text(attr: &Group) -> Option<String> {
+    let mut tokens = attr.stream().into_iter();
+    match (tokens.next(), tokens.next(), tokens.next(), tokens.next()) {
+        (
+            Some(TokenTree::Ident(doc)),
+            Some(TokenTree::Punct(eq)),
+            Some(TokenTree::Literal(text)),
+            None,
+        ) if doc == "doc" && eq.as_char() == '=' => {
+            let text = text.to_string();
+            if !text.starts_with('"') {
+                return None;
+            }
+            Some(text[1..text.len() - 1].to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Checks whether a code block with the info string `info` is Rust, which
+/// rustdoc tests.
+fn is_rust_fence(info: &str) -> bool {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .all(|word| {
+            [
+                "rust",
+                "should_panic",
+                "no_run",
+                "compile_fail",
+                "test_harness",
+                "standalone_crate",
+            ]
+            .contains(&word)
+                || word.starts_with("edition")
+        })
+}
+
+/// Checks whether `attrs` has a doc attribute with some text in it, or one
+/// whose text comes from a macro, like `#[doc = include_str!("m.md")]`,
+/// which can't be seen until it's expanded.
+pub(crate) fn is_documented(attrs: TokenStream) -> bool {
+    attrs.into_iter().any(|tt| match tt {
+        TokenTree::Group(group) => match doc_text(&group) {
+            Some(text) => !text.replace("\\n", "").trim().is_empty(),
+            None => {
+                let mut tokens = group.stream().into_iter();
+                match (tokens.next(), tokens.next(), tokens.next()) {
+                    (Some(TokenTree::Ident(doc)), Some(TokenTree::Punct(eq)), Some(_)) => {
+                        doc == "doc" && eq.as_char() == '='
+                    }
+                    _ => false,
+                }
+            }
+        },
+        _ => false,
+    })
+}
+
+/// Checks whether the doc attributes in `attrs` have a line starting with
+/// `heading`.
+fn has_doc_heading(attrs: TokenStream, heading: &str) -> bool {
+    attrs.into_iter().any(|tt| match tt {
+        TokenTree::Group(group) => {
+            let mut tokens = group.stream().into_iter();
+            match (tokens.next(), tokens.next(), tokens.next()) {
+                (
+                    Some(TokenTree::Ident(doc)),
+                    Some(TokenTree::Punct(eq)),
+                    Some(TokenTree::Literal(text)),
+                ) if doc == "doc" && eq.as_char() == '=' => {
+                    // The literal's text, escapes and all, which headings don't need.
+                    text.to_string()
+                        .trim_start_matches(|c| c == 'r' || c == '#' || c == '"')
+                        .replace("\\n", "\n")
+                        .lines()
+                        .any(|line| line.trim_start().starts_with(heading))
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    })
+}
@@ -0,0 +1,84 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use proc_macro2::TokenStream;
+
+use crate::parse::MacroRules;
+
+/// Writes `output`, the expansion of `#[macro_pub] item`, to a file in the
+/// directory at `MACRO_PUB_DEBUG_DIR`, if it's set, or returns an error to
+/// emit if it can't be written.
+pub(crate) fn dump_from_env(item: TokenStream, output: &TokenStream) -> TokenStream {
+    let dir = match env::var_os("MACRO_PUB_DEBUG_DIR") {
+        Some(dir) => dir,
+        None => return TokenStream::new(),
+    };
+    let name = match MacroRules::parse(item) {
+        Some(parsed) => parsed.name.to_string(),
+        None => return TokenStream::new(),
+    };
+    let krate = env::var("CARGO_CRATE_NAME").unwrap_or_default();
+    match dump(dir.as_ref(), &krate, &name, output) {
+        Ok(()) => TokenStream::new(),
+        Err(message) => format!("compile_error! {{ {:?} }}", message)
+            .parse()
+            .unwrap(),
+    }
+}
+
+/// Writes `output` to `dir/krate/name.rs`, and formats it with rustfmt, if
+/// it's installed.
+fn dump(dir: &Path, krate: &str, name: &str, output: &TokenStream) -> Result<(), String> {
+    let dir = dir.join(krate);
+    let path = dir.join(format!("{}.rs", name));
+    fs::create_dir_all(&dir)
+        .and_then(|()| fs::write(&path, format!("{}\n", output)))
+        .map_err(|error| {
+            format!(
+                "`#[macro_pub]` could not write to `MACRO_PUB_DEBUG_DIR` at {}: {}",
+                path.display(),
+                error,
+            )
+        })?;
+    // Unformatted output is still useful, so ignore any failure.
+    let rustfmt = env::var_os("RUSTFMT").unwrap_or_else(|| "rustfmt".into());
+    let _ = Command::new(rustfmt)
+        .args(&["--edition", "2018"])
+        .arg(&path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_files() {
+        let dir = env::temp_dir().join(format!("macro_pub-dump-{}", std::process::id()));
+        let output = "macro_rules! m { () => {}; } pub(crate) use m as m;"
+            .parse()
+            .unwrap();
+        dump(&dir, "krate", "m", &output).unwrap();
+        let written = fs::read_to_string(dir.join("krate").join("m.rs")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(written.contains("macro_rules!"), "{}", written);
+    }
+
+    #[test]
+    fn errors() {
+        let file = env::temp_dir().join(format!("macro_pub-dump-file-{}", std::process::id()));
+        fs::write(&file, "").unwrap();
+        let error = dump(&file, "krate", "m", &TokenStream::new()).unwrap_err();
+        fs::remove_file(&file).unwrap();
+        assert!(
+            error.starts_with("`#[macro_pub]` could not write to `MACRO_PUB_DEBUG_DIR` at "),
+            "{}",
+            error,
+        );
+    }
+}
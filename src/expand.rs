@@ -0,0 +1,3581 @@
+use std::path::PathBuf;
+
+use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use xxhash_rust::xxh3::{xxh3_128, xxh3_128_with_seed, xxh3_64};
+
+use crate::arms::{forward_arms, guard_arms, retarget_self_paths, rewrite_arms, Arms};
+use crate::config_file::{self, ConfigFile};
+use crate::docs::{doctest_docs, is_documented, macro_docs};
+use crate::extend;
+use crate::lints::{lint_level, macro_lints, pointless_options};
+use crate::parse::{lint_levels, matchers, Args, ArgsError, Level, MacroRules, Mangle, Strategy};
+
+/// Toolchain capabilities, and the package being compiled, that change the
+/// expansion.
+pub(crate) struct Config {
+    /// Whether the compiler accepts `pub macro` items.
+    pub(crate) has_simple_decl_macro: bool,
+    /// Whether `#[cfg(doc)]` builds can use a `pub macro` for documentation,
+    /// which `nightly-docs` forces even where the probe failed.
+    pub(crate) doc_decl_macro: bool,
+    /// The spelling of `macro_rules!`-like `#[rustc_macro_transparency]`.
+    pub(crate) transparency: &'static str,
+    /// The `rustc --version` these were detected for.
+    pub(crate) rustc_version: &'static str,
+    /// The package being compiled, which `package` macros are shared with.
+    pub(crate) package: Option<String>,
+    /// The crate being compiled, which `doctest` macros' examples use them
+    /// from.
+    pub(crate) krate: Option<String>,
+    /// Whether the package's world-public macros must be documented, per
+    /// `MACRO_PUB_DOC_REQUIRED`.
+    pub(crate) doc_required: bool,
+    /// Whether the package's macros expand the same on every toolchain, per
+    /// `MACRO_PUB_PIN_BACKEND`.
+    pub(crate) pin_backend: bool,
+    /// The crate-wide levels of `#[macro_pub]`'s lints, per
+    /// `MACRO_PUB_LINTS`, or why they're malformed.
+    pub(crate) lint_levels: Result<Vec<(String, Level)>, String>,
+    /// Whether Cargo is compiling the package as a dependency, rather than
+    /// one it was asked to build, per `CARGO_PRIMARY_PACKAGE`.
+    pub(crate) dependency: bool,
+    /// The file at `MACRO_PUB_CONFIG`, if any, which the expansion tracks, or
+    /// why it can't be used.
+    pub(crate) config_file: Result<Option<PathBuf>, String>,
+}
+
+impl Config {
+    /// The configuration detected by the build script, for the package Cargo
+    /// is compiling.
+    pub(crate) fn detected() -> Self {
+        let package = std::env::var("CARGO_PKG_NAME").ok();
+        // The file's settings are defaults, which the environment variables
+        // add to.
+        let (config_file, file) = match config_file::from_env() {
+            Ok(Some((path, file))) => (Ok(Some(path)), file),
+            Ok(None) => (Ok(None), ConfigFile::default()),
+            Err(error) => (Err(error), ConfigFile::default()),
+        };
+        Config {
+            has_simple_decl_macro: cfg!(has_simple_decl_macro),
+            doc_decl_macro: cfg!(doc_decl_macro),
+            // Only older nightlies, detected as not supporting the current
+            // spelling, need the old one.
+            transparency: if cfg!(has_simple_decl_macro) && !cfg!(has_semiopaque_transparency) {
+                "semitransparent"
+            } else {
+                "semiopaque"
+            },
+            rustc_version: env!("MACRO_PUB_RUSTC_VERSION"),
+            package: package.clone(),
+            krate: std::env::var("CARGO_CRATE_NAME").ok(),
+            doc_required: file.doc_required
+                || lists_package("MACRO_PUB_DOC_REQUIRED", package.as_deref()),
+            pin_backend: file.pin_backend
+                || lists_package("MACRO_PUB_PIN_BACKEND", package.as_deref()),
+            lint_levels: std::env::var("MACRO_PUB_LINTS")
+                .map_or(Ok(Vec::new()), |levels| {
+                    levels
+                        .parse()
+                        .map_err(|_| "`MACRO_PUB_LINTS` isn't valid Rust tokens".to_string())
+                        .and_then(lint_levels)
+                })
+                .map(|levels| file.lint_levels.into_iter().chain(levels).collect()),
+            dependency: package.is_some() && std::env::var_os("CARGO_PRIMARY_PACKAGE").is_none(),
+            config_file,
+        }
+    }
+
+    /// `level`, with denied lints only warning in dependencies, as
+    /// `--cap-lints` would have them, since it can't cap the errors they are.
+    pub(crate) fn cap(&self, level: Level) -> Level {
+        match level {
+            Level::Deny if self.dependency => Level::Warn,
+            level => level,
+        }
+    }
+}
+
+/// Whether the environment variable `var` lists `package`. These are lists of
+/// packages, since `[env]` sets them for dependencies too.
+fn lists_package(var: &str, package: Option<&str>) -> bool {
+    std::env::var(var).map_or(false, |packages| {
+        packages
+            .split(',')
+            .any(|listed| Some(listed.trim()) == package)
+    })
+}
+
+/// `item`, with the macro renamed as `args` asks with `rename = "name"`:
+/// its name in `macro_rules!`, and each `name!` invoking it, by itself or by
+/// path, in its arms. Any other mention of the old name is left as written.
+pub(crate) fn renamed(args: Option<&Args>, item: TokenStream) -> TokenStream {
+    let new = match args {
+        Some(Args {
+            rename: Some(new), ..
+        }) => new,
+        _ => return item,
+    };
+    let mut tokens = item.into_iter().collect::<Vec<_>>();
+    let position = tokens.windows(3).position(|window| match window {
+        [TokenTree::Ident(macro_rules), TokenTree::Punct(bang), TokenTree::Ident(_)] => {
+            macro_rules == "macro_rules" && bang.as_char() == '!'
+        }
+        _ => false,
+    });
+    let i = match position {
+        Some(i) => i + 2,
+        None => return tokens.into_iter().collect(),
+    };
+    let old = match tokens[i] {
+        TokenTree::Ident(ref old) => old.clone(),
+        _ => unreachable!(),
+    };
+    tokens[i] = TokenTree::Ident(Ident::new(&new.to_string(), old.span()));
+    if let Some(TokenTree::Group(body)) = tokens.get(i + 1) {
+        let mut body_renamed =
+            Group::new(body.delimiter(), renamed_calls(body.stream(), &old, new));
+        body_renamed.set_span(body.span());
+        tokens[i + 1] = TokenTree::Group(body_renamed);
+    }
+    tokens.into_iter().collect()
+}
+
+/// `tokens` with each `old!` replaced by `new!`.
+fn renamed_calls(tokens: TokenStream, old: &Ident, new: &Ident) -> TokenStream {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut renamed = TokenStream::new();
+    for (i, tt) in tokens.iter().enumerate() {
+        let tt = match *tt {
+            TokenTree::Group(ref group) => {
+                let mut rewritten =
+                    Group::new(group.delimiter(), renamed_calls(group.stream(), old, new));
+                rewritten.set_span(group.span());
+                TokenTree::Group(rewritten)
+            }
+            TokenTree::Ident(ref ident) if ident == old => {
+                let is_call = match tokens.get(i + 1) {
+                    Some(TokenTree::Punct(bang)) => bang.as_char() == '!',
+                    _ => false,
+                };
+                let is_metavar = match i.checked_sub(1).map(|i| &tokens[i]) {
+                    Some(TokenTree::Punct(dollar)) => dollar.as_char() == '$',
+                    _ => false,
+                };
+                if is_call && !is_metavar {
+                    TokenTree::Ident(Ident::new(&new.to_string(), ident.span()))
+                } else {
+                    tt.clone()
+                }
+            }
+            ref tt => tt.clone(),
+        };
+        renamed.extend(Some(tt));
+    }
+    renamed
+}
+
+/// Expands `#[macro_pub(attr)] item`, with `args` parsed from `attr`.
+pub(crate) fn macro_pub(
+    attr: TokenStream,
+    args: &Result<Args, ArgsError>,
+    item: TokenStream,
+    config: &Config,
+) -> TokenStream {
+    let parsed = match MacroRules::parse(item.clone()) {
+        Some(parsed) => parsed,
+        None => {
+            let mut output = item;
+            output.extend(
+                format!(
+                    "compile_error! {{ {:?} }}",
+                    "`#[macro_pub]` must be used on a `macro_rules!` macro",
+                )
+                .parse::<TokenStream>()
+                .unwrap(),
+            );
+            return output;
+        }
+    };
+
+    let args = match *args {
+        Ok(ref args) => {
+            check_args(args, config, &parsed.attrs, parsed.hidden, &parsed.name).map(|()| args)
+        }
+        Err(ref error) => Err(error.clone()),
+    };
+
+    // Parts of a macro, and macros mounting them, are only defined once the
+    // parts are put together.
+    match args {
+        Ok(args) if args.extend => {
+            let hash = macro_hash(&item, None, false);
+            return extend::part(hash, &parsed.name, args.restriction.clone(), item);
+        }
+        Ok(args) if !args.extend_from.is_empty() => {
+            return extend::mount(attr, item, &args.extend_from);
+        }
+        _ => {}
+    }
+
+    let args = match args {
+        Ok(args) => args,
+        Err(error) => return unexpanded(parsed, &error),
+    };
+
+    let hash = macro_hash(&item, args.salt.as_deref(), args.hash_docs);
+    let mangled = mangled_name(
+        hash,
+        &parsed.name.to_string(),
+        &args.restriction,
+        args.strategy,
+        args.mangle.as_ref(),
+    );
+    // Only restricted macros need a hidden copy for their doctests.
+    let package_guard = if args.package || (args.doctest && !args.restriction.is_empty()) {
+        let package = config.package.as_ref().unwrap().replace('-', "_");
+        Some(format!("__macro_pub_package_{}", package))
+    } else {
+        None
+    };
+    let cross_crate = args.restriction.is_empty()
+        || args.export_as.is_some()
+        || !args.friends.is_empty()
+        || package_guard.is_some();
+
+    // Code in the arms that does something else in the crates using the macro,
+    // reported where it's written, with the lint it's for.
+    let mut lints = Vec::new();
+    let arms = rewrite_arms(
+        &parsed,
+        args,
+        config,
+        mangled.as_deref(),
+        cross_crate,
+        &mut lints,
+    );
+    lints.extend(macro_lints(&arms, &parsed.name, args, config, cross_crate));
+    let pointless = pointless_options(
+        args,
+        &parsed.name,
+        &arms,
+        mangled.as_deref(),
+        cross_crate,
+        parsed.hidden,
+    );
+
+    let expansion = Expansion::new(args, config, &parsed, hash, mangled, package_guard, &arms);
+    let attrs = expansion.docs(parsed.attrs, &arms, parsed.hidden);
+
+    let mut output = arms.helper;
+    let (doc_copy, shadowed) = expansion.doc_copy(&attrs);
+    output.extend(doc_copy);
+    output.extend(expansion.definition(attrs, shadowed));
+    output.extend(expansion.textual_copy(arms.textual));
+    output.extend(expansion.feature_stub());
+    output.extend(expansion.legacy_name());
+    if let Some(ref alias) = args.export_as {
+        output.extend(expansion.export_as(alias.clone()));
+    }
+    if let Some(ref old_name) = args.deprecate_as {
+        output.extend(expansion.deprecate_as(old_name.clone()));
+    }
+    output.extend(expansion.aliases(args.aliases.clone()));
+    if let Some(ref hygienic_name) = args.hygienic_as {
+        output.extend(expansion.hygienic_as(hygienic_name.clone()));
+    }
+    output.extend(expansion.friends());
+    if args.metadata {
+        output.extend(expansion.metadata());
+    }
+    if let Some(ref path) = args.expect_path {
+        output.extend(path_check(path.clone()));
+    }
+
+    output.extend(parsed.rest);
+
+    output.extend(expansion.warnings(
+        args.deprecated.clone(),
+        pointless,
+        lints,
+        parsed.item_attrs,
+        parsed.arm_attrs,
+    ));
+
+    if args.debug {
+        let debug = expansion.debug(output.clone());
+        output.extend(debug);
+    }
+
+    output
+}
+
+/// `item` as written, without the arm attributes it can't have, and `error`.
+/// The macro is still defined, so that uses of it don't cause more errors.
+fn unexpanded(item: MacroRules, error: &ArgsError) -> TokenStream {
+    let mut output = item.attrs;
+    output.extend(item.internal_attrs);
+    output.extend(vec![
+        TokenTree::Ident(item.macro_rules),
+        TokenTree::Punct(item.bang),
+        TokenTree::Ident(item.name),
+        TokenTree::Group(Group::new(Delimiter::Brace, item.arms)),
+    ]);
+    output.extend(item.rest);
+    output.extend(spanned_error(&error.message, error.span));
+    output
+}
+
+/// The macro being expanded, as the items generated for it see it.
+struct Expansion<'a> {
+    args: &'a Args,
+    config: &'a Config,
+    macro_name: Ident,
+    hash: u128,
+    /// The name the macro is exported under, if it's mangled.
+    mangled: Option<String>,
+    /// The guard of the package's hidden copy of the macro, if it has one.
+    package_guard: Option<String>,
+    internal_attrs: TokenStream,
+    macro_rules: Ident,
+    bang: Punct,
+    /// The name the macro is defined under when it's exported or kept out of
+    /// textual scope.
+    macro_rules_name: TokenTree,
+    /// The arms the macro is defined with.
+    arms: TokenStream,
+    /// The arms as copies of the macro expand them, which never forward.
+    alias_arms: TokenStream,
+    /// The arms left out of the documented macro.
+    hidden_arms: Vec<usize>,
+    /// The macro's visibility, which copies of it share.
+    vis: TokenStream,
+    need_macro_export: bool,
+    /// The `cfg` predicate the macro is enabled under with `feature`.
+    enabled: Option<String>,
+    /// Whether the macro is hidden, by an option or by `#[doc(hidden)]`.
+    hidden: bool,
+    /// Whether the macro is documented as a `pub macro`.
+    documented: bool,
+    /// Whether the documented macro forwards to the exported one.
+    forwards: bool,
+    /// The hidden module-level alias of the exported macro, which forwarding
+    /// macros go through.
+    impl_alias: Ident,
+}
+
+impl<'a> Expansion<'a> {
+    /// The expansion of `item`, whose hash is `hash`, with `args`, and its
+    /// `arms` rewritten for them.
+    fn new(
+        args: &'a Args,
+        config: &'a Config,
+        item: &MacroRules,
+        hash: u128,
+        mangled: Option<String>,
+        package_guard: Option<String>,
+        arms: &Arms,
+    ) -> Self {
+        let macro_name = item.name.clone();
+        let need_macro_export = args.restriction.is_empty();
+        let mut vis = TokenStream::from(TokenTree::Ident(Ident::new("pub", Span::call_site())));
+        if !need_macro_export {
+            vis.extend(Some(TokenTree::Group(Group::new(
+                Delimiter::Parenthesis,
+                args.restriction.clone(),
+            ))));
+        }
+        let hidden_arms = arms.hidden_indices();
+
+        // Without an explicit strategy, document the macro as a `pub macro` if
+        // the toolchain supports it, and the expansion isn't pinned to the one
+        // every toolchain supports.
+        let documented = config.doc_decl_macro
+            && !config.pin_backend
+            && args.strategy.is_none()
+            && !args.no_alias;
+
+        // Collapsed, or without its hidden arms, the documented macro forwards
+        // to the exported one, which is kept for it, and which calls itself
+        // directly rather than through the documented macro. Macro-expanded
+        // exported macros can't be named by path in their own crate, so both
+        // go through a hidden module-level alias.
+        let forwards =
+            documented && need_macro_export && (args.hide_arms || !hidden_arms.is_empty());
+        let impl_alias = Ident::new(
+            &format!("__macro_pub_impl_{}", unraw(&macro_name.to_string())),
+            Span::call_site().located_at(macro_name.span()),
+        );
+        let definition_arms = if forwards {
+            retarget_self_paths(arms.arms.clone(), &macro_name, &impl_alias)
+        } else {
+            arms.arms.clone()
+        };
+
+        Expansion {
+            args,
+            config,
+            macro_rules_name: TokenTree::Ident(impl_ident(hash, &macro_name, args.mangle.as_ref())),
+            macro_name,
+            hash,
+            mangled,
+            package_guard,
+            internal_attrs: item.internal_attrs.clone(),
+            macro_rules: item.macro_rules.clone(),
+            bang: item.bang.clone(),
+            arms: definition_arms,
+            alias_arms: arms.arms.clone(),
+            hidden_arms,
+            vis,
+            need_macro_export,
+            // Documentation always shows the macro, whether or not it's
+            // enabled.
+            enabled: args
+                .feature
+                .as_ref()
+                .map(|feature| format!("any(feature = {:?}, doc)", feature)),
+            hidden: args.hidden || item.hidden,
+            documented,
+            forwards,
+            impl_alias,
+        }
+    }
+
+    /// `#[cfg(...)]` for the macro's feature, if it has one.
+    fn enabled_cfg(&self) -> TokenStream {
+        match self.enabled {
+            Some(ref enabled) => format!("#[cfg({})]", enabled).parse().unwrap(),
+            None => TokenStream::new(),
+        }
+    }
+
+    /// The macro's documentation: `attrs`, the documentation its options add
+    /// for `arms`, and, for `doctest`, a hidden definition at the start of
+    /// each example forwarding to the copy guarded for the package, since
+    /// examples are compiled as other crates.
+    fn docs(&self, attrs: TokenStream, arms: &Arms, hidden_attr: bool) -> TokenStream {
+        let attrs = macro_docs(attrs, self.args, &self.macro_name, arms, hidden_attr);
+        let guard = match self.package_guard {
+            Some(ref guard) if self.args.doctest => guard,
+            _ => return attrs,
+        };
+        let forward = format!(
+            "# macro_rules! {name} {{ ($($tokens:tt)*) => {{ \
+             ::{krate}::{friends}! {{ {guard} $($tokens)* }} \
+             }}; }}",
+            name = self.macro_name,
+            friends = impl_name(
+                self.hash,
+                &format!(
+                    "__macro_pub_friends_{}",
+                    unraw(&self.macro_name.to_string())
+                ),
+                self.args.mangle.as_ref(),
+            ),
+            krate = self.config.krate.as_ref().unwrap(),
+            guard = guard,
+        );
+        doctest_docs(attrs, &forward)
+    }
+
+    /// The visibility of the items named after the macro in the `shadowable`
+    /// module, as visible as the crate allows, which the macro's visibility
+    /// then narrows where they're re-exported.
+    fn shadowed_vis(&self) -> TokenStream {
+        if self.need_macro_export {
+            "pub".parse().unwrap()
+        } else {
+            "pub(crate)".parse().unwrap()
+        }
+    }
+
+    /// The `pub macro` copy of the macro that rustdoc documents with `attrs`,
+    /// if it's documented as one, and its `selfcheck`. Returns those, and what
+    /// goes in the `shadowable` module instead.
+    fn doc_copy(&self, attrs: &TokenStream) -> (TokenStream, TokenStream) {
+        let mut output = TokenStream::new();
+        let mut shadowed = TokenStream::new();
+        if !self.documented {
+            return (output, shadowed);
+        }
+        let mut doc_copy = attrs.clone();
+        doc_copy.extend(r##"#[cfg(doc)]"##.parse::<TokenStream>().unwrap());
+        // A forwarding macro stays opaque, so it finds the alias where it's
+        // defined.
+        if !self.forwards {
+            doc_copy.extend(
+                format!(
+                    r##"#[rustc_macro_transparency = "{}"]"##,
+                    self.config.transparency
+                )
+                .parse::<TokenStream>()
+                .unwrap(),
+            );
+        }
+        let doc_arms = if self.args.hide_arms && self.need_macro_export {
+            format!(
+                "($($args:tt)*) => {{ {}! {{ $($args)* }} }};",
+                self.impl_alias
+            )
+            .parse()
+            .unwrap()
+        } else if self.forwards {
+            forward_arms(self.arms.clone(), &self.hidden_arms, &self.impl_alias)
+        } else {
+            self.arms.clone()
+        };
+        if self.args.shadowable {
+            if self.forwards {
+                shadowed.extend("#[cfg(doc)] use super::".parse::<TokenStream>().unwrap());
+                shadowed.extend(vec![
+                    TokenTree::Ident(self.impl_alias.clone()),
+                    TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+                ]);
+            }
+            doc_copy.extend(decl_macro(self.shadowed_vis(), &self.macro_name, doc_arms));
+            shadowed.extend(doc_copy);
+        } else {
+            doc_copy.extend(decl_macro(self.vis.clone(), &self.macro_name, doc_arms));
+            output.extend(doc_copy);
+        }
+        if self.args.selfcheck {
+            output.extend(selfcheck_block(self.arms.clone(), self.config.transparency));
+        }
+        (output, shadowed)
+    }
+
+    /// The macro's definition, with its documentation `attrs`, and its
+    /// re-export under its name, with `shadowed` in the `shadowable` module.
+    fn definition(&self, attrs: TokenStream, shadowed: TokenStream) -> TokenStream {
+        let mut output = attrs;
+        if self.documented {
+            // Restricted macros can still be used textually, so only hide them.
+            output.extend(if self.forwards {
+                TokenStream::new()
+            } else if self.need_macro_export || self.args.no_textual {
+                r##"#[cfg(not(doc))]"##.parse::<TokenStream>().unwrap()
+            } else {
+                r##"#[cfg_attr(doc, doc(hidden))]"##
+                    .parse::<TokenStream>()
+                    .unwrap()
+            });
+        }
+
+        match self.args.strategy {
+            Some(Strategy::DeclMacro) => {
+                output.extend(
+                    format!(
+                        r##"#[rustc_macro_transparency = "{}"]"##,
+                        self.config.transparency
+                    )
+                    .parse::<TokenStream>()
+                    .unwrap(),
+                );
+                output.extend(self.internal_attrs.clone());
+                output.extend(decl_macro(
+                    self.vis.clone(),
+                    &self.macro_name,
+                    self.arms.clone(),
+                ));
+                return output;
+            }
+            Some(Strategy::LegacyExport) => {
+                output.extend("#[macro_export]".parse::<TokenStream>().unwrap());
+                output.extend(self.internal_attrs.clone());
+                output.extend(vec![
+                    TokenTree::Ident(self.macro_rules.clone()),
+                    TokenTree::Punct(self.bang.clone()),
+                    TokenTree::Ident(self.macro_name.clone()),
+                    TokenTree::Group(Group::new(Delimiter::Brace, self.arms.clone())),
+                ]);
+                return output;
+            }
+            Some(Strategy::UseAlias) | None => {}
+        }
+
+        if self.enabled.is_some() && !self.need_macro_export {
+            // It's only used textually without the feature.
+            output.extend("#[allow(unused_macros)]".parse::<TokenStream>().unwrap());
+        }
+        if self.need_macro_export {
+            output.extend(
+                "#[macro_export] #[doc(hidden)]"
+                    .parse::<TokenStream>()
+                    .unwrap(),
+            );
+        }
+        output.extend(self.internal_attrs.clone());
+        output.extend(vec![
+            TokenTree::Ident(self.macro_rules.clone()),
+            TokenTree::Punct(self.bang.clone()),
+            if self.need_macro_export || self.args.no_textual {
+                self.macro_rules_name.clone()
+            } else {
+                TokenTree::Ident(self.macro_name.clone())
+            },
+            TokenTree::Group(Group::new(Delimiter::Brace, self.arms.clone())),
+        ]);
+
+        if self.forwards {
+            let mut use_path = self.macro_rules_name.clone();
+            use_path.set_span(Span::call_site().located_at(use_path.span()));
+            output.extend("#[doc(hidden)] pub".parse::<TokenStream>().unwrap());
+            output.extend(vec![
+                TokenTree::Ident(Ident::new("use", mixed_site())),
+                use_path,
+                TokenTree::Ident(Ident::new("as", mixed_site())),
+                TokenTree::Ident(self.impl_alias.clone()),
+                TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+            ]);
+        }
+
+        // Without the alias, users mount the exported macro themselves.
+        if !self.args.no_alias {
+            output.extend(self.reexport(shadowed));
+        }
+        output
+    }
+
+    /// The re-export of the macro under its name, which rustdoc documents.
+    fn reexport(&self, shadowed: TokenStream) -> TokenStream {
+        let macro_name = &self.macro_name;
+        let mut output = TokenStream::new();
+        let mut shadowed = shadowed;
+        let mut cfgs = TokenStream::new();
+        if self.documented {
+            cfgs.extend(r##"#[cfg(not(doc))]"##.parse::<TokenStream>().unwrap());
+        }
+
+        // Imports are resolved per the edition of the path's span, and 2015
+        // edition imports can't name `macro_rules!` macros. `call_site` hygiene
+        // resolves the same as the user's span, but carries this crate's edition.
+        let mut use_path = if self.need_macro_export || self.args.no_textual {
+            self.macro_rules_name.clone()
+        } else {
+            TokenTree::Ident(macro_name.clone())
+        };
+        use_path.set_span(Span::call_site().located_at(use_path.span()));
+
+        cfgs.extend(self.enabled_cfg());
+
+        // Recent rustdoc documents an explicitly inlined re-export of the hidden
+        // implementation as a macro page under the public name. A `cfg(doc)`
+        // `macro_rules!` copy can't stand in for it on older rustdoc, since it
+        // would have to be `#[macro_export]`ed, and documented at the crate
+        // root. The re-export has to follow the definition, since `use` only
+        // finds `macro_rules!` macros defined before it.
+        let doc = if self.args.unstable || self.hidden {
+            r##"#[doc(hidden)]"##.parse::<TokenStream>().unwrap()
+        } else if self.args.no_inline {
+            r##"#[doc(no_inline)]"##.parse::<TokenStream>().unwrap()
+        } else if self.args.inline || self.need_macro_export {
+            r##"#[doc(inline)]"##.parse::<TokenStream>().unwrap()
+        } else {
+            TokenStream::new()
+        };
+        let alias = vec![
+            TokenTree::Ident(Ident::new("use", mixed_site())),
+            use_path,
+            TokenTree::Ident(Ident::new("as", mixed_site())),
+            TokenTree::Ident(macro_name.clone()),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ];
+
+        if self.args.shadowable {
+            // Names imported by a glob give way to any other item of the
+            // same name in the module. The module is private, so rustdoc
+            // only documents the macro where it's re-exported.
+            let module = Ident::new(
+                &format!("__macro_pub_shadowable_{}", unraw(&macro_name.to_string())),
+                Span::call_site().located_at(macro_name.span()),
+            );
+            shadowed.extend(cfgs);
+            shadowed.extend(doc.clone());
+            shadowed.extend(self.shadowed_vis());
+            shadowed.extend(alias);
+            output.extend(vec![
+                TokenTree::Ident(Ident::new("mod", mixed_site())),
+                TokenTree::Ident(module.clone()),
+                TokenTree::Group(Group::new(Delimiter::Brace, shadowed)),
+            ]);
+            output.extend(doc);
+            output.extend("#[allow(unused_imports)]".parse::<TokenStream>().unwrap());
+            output.extend(self.vis.clone());
+            output.extend(vec![
+                TokenTree::Ident(Ident::new("use", mixed_site())),
+                TokenTree::Ident(Ident::new("self", Span::call_site())),
+                TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+                TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+                TokenTree::Ident(module),
+            ]);
+            output.extend("::*;".parse::<TokenStream>().unwrap());
+        } else {
+            output.extend(cfgs);
+            output.extend(doc);
+            // Restricted macros may only be used textually, leaving the
+            // alias unused.
+            if !self.need_macro_export {
+                output.extend("#[allow(unused_imports)]".parse::<TokenStream>().unwrap());
+            }
+            output.extend(self.vis.clone());
+            output.extend(alias);
+        }
+        output
+    }
+
+    /// A `macro_rules!` copy of the macro under its own name, after `attrs`.
+    fn own_name_copy(&self, attrs: TokenStream) -> TokenStream {
+        let mut output = attrs;
+        output.extend(self.internal_attrs.clone());
+        let mut bang = Punct::new('!', Spacing::Alone);
+        bang.set_span(self.macro_rules.span());
+        output.extend(vec![
+            TokenTree::Ident(Ident::new("macro_rules", self.macro_rules.span())),
+            TokenTree::Punct(bang),
+            TokenTree::Ident(self.macro_name.clone()),
+            TokenTree::Group(Group::new(Delimiter::Brace, self.alias_arms.clone())),
+        ]);
+        output
+    }
+
+    /// A copy under the macro's own name, for uses below the definition and in
+    /// `#[macro_use]` modules, like a plain `macro_rules!` macro, with
+    /// `textual`, or if it invokes itself by that name in `self_calls`.
+    fn textual_copy(&self, self_calls: bool) -> TokenStream {
+        let strategy = self.args.strategy;
+        if !(self.args.textual || self_calls)
+            || strategy == Some(Strategy::LegacyExport)
+            || self.args.also_export_legacy_name
+            || !(self.need_macro_export || strategy == Some(Strategy::DeclMacro))
+        {
+            return TokenStream::new();
+        }
+        let mut attrs = self.enabled_cfg();
+        attrs.extend("#[allow(unused_macros)]".parse::<TokenStream>().unwrap());
+        self.own_name_copy(attrs)
+    }
+
+    /// The name `#[macro_use] extern crate` and `crate_name::name!` used before
+    /// the macro had a path, with `also_export_legacy_name`, hidden so it's
+    /// only documented once.
+    fn legacy_name(&self) -> TokenStream {
+        if !self.args.also_export_legacy_name || self.args.strategy == Some(Strategy::LegacyExport)
+        {
+            return TokenStream::new();
+        }
+        self.own_name_copy(
+            "#[macro_export] #[doc(hidden)]"
+                .parse::<TokenStream>()
+                .unwrap(),
+        )
+    }
+
+    /// Without the feature, the macro is replaced by one explaining how to
+    /// enable it.
+    fn feature_stub(&self) -> TokenStream {
+        let (feature, enabled) = match (&self.args.feature, &self.enabled) {
+            (Some(feature), Some(enabled)) => (feature, enabled),
+            _ => return TokenStream::new(),
+        };
+        let renamed = self.macro_name.to_string();
+        let cfg = format!("#[cfg(not({}))]", enabled)
+            .parse::<TokenStream>()
+            .unwrap();
+        let mut attrs = cfg.clone();
+        if self.need_macro_export {
+            attrs.extend(
+                "#[macro_export] #[doc(hidden)]"
+                    .parse::<TokenStream>()
+                    .unwrap(),
+            );
+        }
+        let message = format!("`{}!` requires the `{}` feature", renamed, feature);
+        alias_macro(
+            attrs,
+            cfg,
+            Ident::new(
+                &format!("macro_pub_feature_{}_{}", self.hash, unraw(&renamed)),
+                self.macro_name.span(),
+            ),
+            self.vis.clone(),
+            self.macro_name.clone(),
+            self.macro_rules.span(),
+            format!(
+                "($($tokens:tt)*) => {{ compile_error! {{ {:?} }} }};",
+                message,
+            )
+            .parse()
+            .unwrap(),
+        )
+    }
+
+    /// A hidden world-public copy, so the macro's own visibility and
+    /// documentation are unaffected.
+    fn export_as(&self, alias: Ident) -> TokenStream {
+        let mut attrs = "#[macro_export] #[doc(hidden)]"
+            .parse::<TokenStream>()
+            .unwrap();
+        attrs.extend(self.internal_attrs.clone());
+        alias_macro(
+            attrs,
+            TokenStream::new(),
+            impl_ident(self.hash, &alias, self.args.mangle.as_ref()),
+            "pub".parse().unwrap(),
+            alias,
+            self.macro_rules.span(),
+            self.alias_arms.clone(),
+        )
+    }
+
+    /// A hidden, deprecated copy under the old name, as visible as the macro.
+    fn deprecate_as(&self, old_name: Ident) -> TokenStream {
+        let mut attrs = format!(
+            "#[doc(hidden)] #[deprecated(note = \"renamed to `{}`\")]",
+            self.macro_name,
+        )
+        .parse::<TokenStream>()
+        .unwrap();
+        if self.need_macro_export {
+            attrs.extend("#[macro_export]".parse::<TokenStream>().unwrap());
+        }
+        let old_impl = if self.need_macro_export || self.args.no_textual {
+            impl_ident(self.hash, &old_name, self.args.mangle.as_ref())
+        } else {
+            old_name.clone()
+        };
+        attrs.extend(self.internal_attrs.clone());
+        alias_macro(
+            attrs,
+            TokenStream::new(),
+            old_impl,
+            self.vis.clone(),
+            old_name,
+            self.macro_rules.span(),
+            self.alias_arms.clone(),
+        )
+    }
+
+    /// Hidden re-exports under other names, as visible as the macro. Paths
+    /// in the module are written `self::name`, since a glob import there
+    /// could make the name ambiguous, and other macros are re-exported from
+    /// their definition, like the macro itself, with the feature if it has
+    /// one.
+    fn aliases(&self, aliases: Vec<Ident>) -> TokenStream {
+        let strategy = self.args.strategy;
+        let legacy_export = strategy == Some(Strategy::LegacyExport);
+        let in_module = strategy == Some(Strategy::DeclMacro)
+            || (self.need_macro_export && !self.args.no_alias && !legacy_export);
+        let mut alias_path = if in_module {
+            "self::".parse::<TokenStream>().unwrap()
+        } else {
+            TokenStream::new()
+        };
+        let mut target =
+            if in_module || legacy_export || (!self.need_macro_export && !self.args.no_textual) {
+                TokenTree::Ident(self.macro_name.clone())
+            } else {
+                self.macro_rules_name.clone()
+            };
+        target.set_span(Span::call_site().located_at(target.span()));
+        alias_path.extend(Some(target));
+        let mut output = TokenStream::new();
+        for alias in aliases {
+            if !in_module {
+                output.extend(self.enabled_cfg());
+            }
+            output.extend("#[doc(hidden)]".parse::<TokenStream>().unwrap());
+            if !self.need_macro_export {
+                output.extend("#[allow(unused_imports)]".parse::<TokenStream>().unwrap());
+            }
+            output.extend(self.vis.clone());
+            output.extend(vec![TokenTree::Ident(Ident::new("use", mixed_site()))]);
+            output.extend(alias_path.clone());
+            output.extend(vec![
+                TokenTree::Ident(Ident::new("as", mixed_site())),
+                TokenTree::Ident(alias),
+                TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+            ]);
+        }
+        output
+    }
+
+    /// A copy with the definition-site hygiene of a `macro`, as visible as the
+    /// macro, where the toolchain has them. Elsewhere it's left out, rather
+    /// than failing the crate's build on stable.
+    fn hygienic_as(&self, hygienic_name: Ident) -> TokenStream {
+        let mut output = TokenStream::new();
+        if !self.config.has_simple_decl_macro || self.config.pin_backend {
+            return output;
+        }
+        output.extend(self.enabled_cfg());
+        let doc = format!(
+            "`{}!`, with the hygiene of a `macro`: names its expansion defines \
+             aren't visible to the caller, and names it uses are resolved where \
+             it's defined.",
+            self.macro_name,
+        );
+        output.extend(vec![
+            TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+            TokenTree::Group(Group::new(
+                Delimiter::Bracket,
+                format!("doc = {:?}", doc).parse().unwrap(),
+            )),
+        ]);
+        if self.hidden {
+            output.extend("#[doc(hidden)]".parse::<TokenStream>().unwrap());
+        }
+        output.extend(self.internal_attrs.clone());
+        output.extend(decl_macro(
+            self.vis.clone(),
+            &hygienic_name,
+            self.alias_arms.clone(),
+        ));
+        output
+    }
+
+    /// A hidden world-public copy that only accepts invocations carrying a
+    /// friend's guard, which `macro_pub::friend!` supplies in that crate, or
+    /// the package's, which `macro_pub::package!` supplies in its other
+    /// targets and `doctest` in the macro's examples.
+    fn friends(&self) -> TokenStream {
+        let mut guards = self
+            .args
+            .friends
+            .iter()
+            .map(|friend| format!("__macro_pub_friend_{}", friend))
+            .collect::<Vec<_>>();
+        guards.extend(self.package_guard.clone());
+        if guards.is_empty() {
+            return TokenStream::new();
+        }
+        let mut arms = TokenStream::new();
+        for guard in guards {
+            let guard = Ident::new(&guard, Span::call_site());
+            arms.extend(guard_arms(self.alias_arms.clone(), &guard));
+        }
+        let alias = Ident::new(
+            &format!(
+                "__macro_pub_friends_{}",
+                unraw(&self.macro_name.to_string())
+            ),
+            Span::call_site().located_at(self.macro_name.span()),
+        );
+        let mut attrs = "#[macro_export] #[doc(hidden)]"
+            .parse::<TokenStream>()
+            .unwrap();
+        attrs.extend(self.internal_attrs.clone());
+        alias_macro(
+            attrs,
+            TokenStream::new(),
+            impl_ident(self.hash, &alias, self.args.mangle.as_ref()),
+            "pub".parse().unwrap(),
+            alias,
+            self.macro_rules.span(),
+            arms,
+        )
+    }
+
+    /// A hidden module of constants describing the macro, for tests to check,
+    /// as visible as the macro.
+    fn metadata(&self) -> TokenStream {
+        let constants = format!(
+            "pub const NAME: &str = {:?}; \
+             pub const ARMS: usize = {}; \
+             pub const VISIBILITY: &str = {:?}; \
+             pub const MANGLED: Option<&str> = {};",
+            self.macro_name.to_string(),
+            matchers(self.alias_arms.clone()).len(),
+            visibility(self.args.restriction.clone()),
+            match self.mangled {
+                Some(ref mangled) => format!("Some({:?})", mangled),
+                None => "None".to_string(),
+            },
+        );
+        let module = Ident::new(
+            &format!(
+                "__macro_pub_metadata_{}",
+                unraw(&self.macro_name.to_string())
+            ),
+            Span::call_site().located_at(self.macro_name.span()),
+        );
+        let mut output = "#[doc(hidden)]".parse::<TokenStream>().unwrap();
+        output.extend(self.vis.clone());
+        output.extend(vec![
+            TokenTree::Ident(Ident::new("mod", Span::call_site())),
+            TokenTree::Ident(module),
+            TokenTree::Group(Group::new(Delimiter::Brace, constants.parse().unwrap())),
+        ]);
+        output
+    }
+
+    /// The warnings about the macro and its options, each at its lint's level.
+    fn warnings(
+        &self,
+        deprecated: Vec<(String, Span)>,
+        pointless: Vec<String>,
+        lints: Vec<(&'static str, String, Span)>,
+        item_attrs: Vec<TokenStream>,
+        arm_attrs: Vec<TokenStream>,
+    ) -> TokenStream {
+        let level = |name: &str| lint_level(self.args, self.config, name);
+        let mut output = TokenStream::new();
+
+        for (message, span) in deprecated {
+            output.extend(warning(&message, span));
+        }
+
+        for message in pointless {
+            output.extend(lint_warning(
+                level("pointless_options"),
+                &message,
+                self.macro_name.span(),
+            ));
+        }
+
+        for (name, message, span) in lints {
+            output.extend(lint_warning(level(name), &message, span));
+        }
+
+        for attr in item_attrs {
+            let (name, span) = match attr.into_iter().last() {
+                Some(TokenTree::Group(attr)) => {
+                    (attr.stream().into_iter().next().unwrap(), attr.span())
+                }
+                _ => unreachable!(),
+            };
+            output.extend(lint_warning(
+                level("item_attrs"),
+                &format!(
+                    "`#[macro_pub]` removed `#[{}]`, which doesn't apply to macros",
+                    name,
+                ),
+                span,
+            ));
+        }
+
+        for attr in arm_attrs {
+            let span = attr.into_iter().last().unwrap().span();
+            output.extend(lint_warning(
+                level("ignored_arm_attrs"),
+                "`#[macro_pub]` removed this attribute; attributes are not allowed on macro arms",
+                span,
+            ));
+        }
+        output
+    }
+
+    /// A warning showing `output`, the macro's expansion, for `debug`.
+    fn debug(&self, output: TokenStream) -> TokenStream {
+        let mut expansion = String::new();
+        write_tokens(&mut expansion, output);
+        warning(
+            &format!(
+                "`#[macro_pub]` expanded `{}!` for {}, {} nightly documentation, to: {}",
+                self.macro_name,
+                self.config.rustc_version,
+                if self.documented { "with" } else { "without" },
+                expansion,
+            ),
+            self.macro_name.span(),
+        )
+    }
+}
+
+/// Only compiles if the macro, or something else, is at `path`.
+fn path_check(path: TokenStream) -> TokenStream {
+    let mut check = "#[allow(unused_imports)] use"
+        .parse::<TokenStream>()
+        .unwrap();
+    check.extend(path);
+    check.extend("as _;".parse::<TokenStream>().unwrap());
+    let mut output = "const _: () =".parse::<TokenStream>().unwrap();
+    output.extend(vec![
+        TokenTree::Group(Group::new(Delimiter::Brace, check)),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+    output
+}
+
+/// Checks `args` against what was detected, and the macro they're for, which
+/// parsing them alone can't.
+fn check_args(
+    args: &Args,
+    config: &Config,
+    attrs: &TokenStream,
+    hidden_attr: bool,
+    macro_name: &Ident,
+) -> Result<(), ArgsError> {
+    // The `decl_macro` strategy can't work without the feature, so report
+    // what was detected rather than leaving rustc to complain about it.
+    if args.strategy == Some(Strategy::DeclMacro) && !config.has_simple_decl_macro {
+        return Err(ArgsError {
+            message: format!(
+                "`#[macro_pub]` strategy `decl_macro` needs \
+                 `#![feature(decl_macro, rustc_attrs)]`, which isn't available on the \
+                 detected {}",
+                config.rustc_version,
+            ),
+            span: Span::call_site(),
+        });
+    }
+    // Hidden macros aren't part of the documented API.
+    if (args.doc_required || config.doc_required)
+        && args.restriction.is_empty()
+        && !args.extend
+        && !(args.hidden || args.unstable || hidden_attr)
+        && !is_documented(attrs.clone())
+    {
+        return Err(ArgsError {
+            message: format!(
+                "`#[macro_pub]` macro `{}!` is world-public, so it needs documentation",
+                macro_name,
+            ),
+            span: macro_name.span(),
+        });
+    }
+    if let Err(ref message) = config.config_file {
+        return Err(ArgsError {
+            message: message.clone(),
+            span: Span::call_site(),
+        });
+    }
+    if let Err(ref message) = config.lint_levels {
+        return Err(ArgsError {
+            message: message.clone(),
+            span: Span::call_site(),
+        });
+    }
+    if args.package && config.package.is_none() {
+        return Err(ArgsError {
+            message: "`#[macro_pub]` option `package` needs Cargo to set `CARGO_PKG_NAME`"
+                .to_string(),
+            span: Span::call_site(),
+        });
+    }
+    if args.doctest
+        && !args.restriction.is_empty()
+        && (config.package.is_none() || config.krate.is_none())
+    {
+        return Err(ArgsError {
+            message: "`#[macro_pub]` option `doctest` needs Cargo to set `CARGO_PKG_NAME` \
+                      and `CARGO_CRATE_NAME`"
+                .to_string(),
+            span: Span::call_site(),
+        });
+    }
+    Ok(())
+}
+
+/// Defines `macro_rules! name { arms }` with `attrs`, and re-exports it with
+/// `use_attrs` and `vis` as `#[doc(hidden)] alias`, even if `attrs` deprecate
+/// it.
+fn alias_macro(
+    attrs: TokenStream,
+    use_attrs: TokenStream,
+    name: Ident,
+    vis: TokenStream,
+    alias: Ident,
+    macro_rules_span: Span,
+    arms: TokenStream,
+) -> TokenStream {
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(macro_rules_span);
+    let mut output = attrs;
+    output.extend(vec![
+        TokenTree::Ident(Ident::new("macro_rules", macro_rules_span)),
+        TokenTree::Punct(bang),
+        TokenTree::Ident(name.clone()),
+        TokenTree::Group(Group::new(Delimiter::Brace, arms)),
+    ]);
+    let mut name = name;
+    name.set_span(Span::call_site().located_at(name.span()));
+    output.extend(use_attrs);
+    output.extend(
+        "#[doc(hidden)] #[allow(deprecated)]"
+            .parse::<TokenStream>()
+            .unwrap(),
+    );
+    output.extend(vis);
+    output.extend(vec![
+        TokenTree::Ident(Ident::new("use", mixed_site())),
+        TokenTree::Ident(name),
+        TokenTree::Ident(Ident::new("as", mixed_site())),
+        TokenTree::Ident(alias),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+    output
+}
+
+/// `vis macro name { arms }`, with the arms separated by `,` instead of `;`.
+fn decl_macro(vis: TokenStream, name: &Ident, arms: TokenStream) -> TokenStream {
+    let mut output = vis;
+    output.extend(vec![
+        TokenTree::Ident(Ident::new("macro", mixed_site())),
+        TokenTree::Ident(name.clone()),
+        TokenTree::Group(Group::new(
+            Delimiter::Brace,
+            arms.into_iter()
+                .map(|tt| match tt {
+                    TokenTree::Punct(punct) if punct.as_char() == ';' => {
+                        TokenTree::Punct(Punct::new(',', punct.spacing()))
+                    }
+                    tt => tt,
+                })
+                .collect(),
+        )),
+    ]);
+    output
+}
+
+/// A `#[cfg(doc)]` check that the `macro` defined from `arms` matches a
+/// sample invocation of each arm with the same arm as the `macro_rules!`
+/// macro, failing to compile if not.
+///
+/// For each sample, the `macro_rules!` copy defines a macro only accepting
+/// the index of the arm it matched, which the `macro` copy invokes with the
+/// index of the arm it matched. Both fall back to matching anything, for
+/// samples that neither matches as written.
+fn selfcheck_block(arms: TokenStream, transparency: &str) -> TokenStream {
+    let matchers = matchers(arms);
+    let mut rules_arms = TokenStream::new();
+    let mut decl_arms = TokenStream::new();
+    let fallback = "($($tokens:tt)*)".parse::<TokenStream>().unwrap();
+    let indices = (0..matchers.len()).map(|i| i.to_string());
+    let all = matchers
+        .iter()
+        .map(|matcher| TokenTree::Group(matcher.clone()).into())
+        .zip(indices)
+        .chain(Some((fallback, "none".to_string())));
+    for (matcher, index) in all {
+        rules_arms.extend(matcher.clone());
+        rules_arms.extend(
+            format!(
+                "=> {{ macro_rules! __macro_pub_selfcheck_arm {{ ({}) => {{}}; ($other:tt) => {{ \
+                 compile_error! {{ {:?} }} }}; }} }};",
+                index, SELFCHECK_ERROR,
+            )
+            .parse::<TokenStream>()
+            .unwrap(),
+        );
+        decl_arms.extend(matcher);
+        decl_arms.extend(
+            format!("=> {{ __macro_pub_selfcheck_arm! {{ {} }} }};", index)
+                .parse::<TokenStream>()
+                .unwrap(),
+        );
+    }
+
+    let mut checks: TokenStream = "macro_rules! __macro_pub_selfcheck_rules".parse().unwrap();
+    checks.extend(Some(TokenTree::Group(Group::new(
+        Delimiter::Brace,
+        rules_arms,
+    ))));
+    checks.extend(
+        format!(r#"#[rustc_macro_transparency = "{}"]"#, transparency)
+            .parse::<TokenStream>()
+            .unwrap(),
+    );
+    checks.extend(decl_macro(
+        TokenStream::new(),
+        &Ident::new("__macro_pub_selfcheck_decl", Span::call_site()),
+        decl_arms,
+    ));
+    for matcher in matchers {
+        let sample = sample(matcher.stream());
+        let mut check: TokenStream = "__macro_pub_selfcheck_rules!".parse().unwrap();
+        check.extend(Some(TokenTree::Group(Group::new(
+            Delimiter::Brace,
+            sample.clone(),
+        ))));
+        check.extend(
+            "__macro_pub_selfcheck_decl!"
+                .parse::<TokenStream>()
+                .unwrap(),
+        );
+        check.extend(Some(TokenTree::Group(Group::new(Delimiter::Brace, sample))));
+        checks.extend(Some(TokenTree::Group(Group::new(Delimiter::Brace, check))));
+    }
+
+    let mut output: TokenStream = "#[cfg(doc)] const _: () =".parse().unwrap();
+    output.extend(vec![
+        TokenTree::Group(Group::new(Delimiter::Brace, checks)),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+    output
+}
+
+const SELFCHECK_ERROR: &str = "`#[macro_pub(selfcheck)]` found an invocation that the \
+     documented `pub macro` matches with a different arm than the `macro_rules!` macro";
+
+/// An invocation that `matcher` matches, with each fragment replaced by a
+/// simple example, and each repetition repeated as few times as it can be.
+fn sample(matcher: TokenStream) -> TokenStream {
+    let mut sample = TokenStream::new();
+    let mut tokens = matcher.into_iter();
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Punct(ref dollar) if dollar.as_char() == '$' => match tokens.next() {
+                Some(TokenTree::Ident(name)) => {
+                    let mut lookahead = tokens.clone();
+                    match (lookahead.next(), lookahead.next()) {
+                        (Some(TokenTree::Punct(colon)), Some(TokenTree::Ident(fragment)))
+                            if colon.as_char() == ':' =>
+                        {
+                            tokens = lookahead;
+                            sample.extend(
+                                fragment_sample(&fragment.to_string())
+                                    .parse::<TokenStream>()
+                                    .unwrap(),
+                            );
+                        }
+                        // `$crate`, which matches itself.
+                        _ => sample.extend(vec![tt, TokenTree::Ident(name)]),
+                    }
+                }
+                Some(TokenTree::Group(repeated)) => {
+                    // An optional separator, then the operator.
+                    let mut operator = tokens.next();
+                    match operator {
+                        Some(TokenTree::Punct(ref op)) if "*+?".contains(op.as_char()) => {}
+                        _ => operator = tokens.next(),
+                    }
+                    match operator {
+                        Some(TokenTree::Punct(ref op)) if op.as_char() == '+' => {
+                            sample.extend(self::sample(repeated.stream()))
+                        }
+                        _ => {}
+                    }
+                }
+                next => sample.extend(Some(tt).into_iter().chain(next)),
+            },
+            TokenTree::Group(group) => {
+                let mut sampled = Group::new(group.delimiter(), self::sample(group.stream()));
+                sampled.set_span(group.span());
+                sample.extend(Some(TokenTree::Group(sampled)));
+            }
+            tt => sample.extend(Some(tt)),
+        }
+    }
+    sample
+}
+
+/// A simple example of the `macro_rules!` fragment specifier `fragment`.
+fn fragment_sample(fragment: &str) -> &'static str {
+    match fragment {
+        "ident" | "path" | "tt" | "meta" => "x",
+        "ty" => "()",
+        "pat" | "pat_param" => "_",
+        "lifetime" => "'a",
+        "block" => "{}",
+        "item" => "fn f() {}",
+        "vis" => "",
+        // `expr`, `expr_2021`, `literal`, and `stmt`.
+        _ => "0",
+    }
+}
+
+/// The visibility `pub` or `pub(restriction)`, as it's written.
+pub(crate) fn visibility(restriction: TokenStream) -> String {
+    if restriction.is_empty() {
+        return "pub".to_string();
+    }
+    let mut visibility = "pub(".to_string();
+    write_tokens(&mut visibility, restriction);
+    visibility.push(')');
+    visibility
+}
+
+/// The hash of the `macro_rules!` macro `item`, with the `salt` option, that
+/// the names it's exported under are mangled with.
+///
+/// Doc comments are left out, unless `hash_docs`, so that editing them
+/// doesn't rename the macro. Whitespace and formatting outside literals always
+/// are, since they aren't part of the tokens' string form.
+pub(crate) fn macro_hash(item: &TokenStream, salt: Option<&str>, hash_docs: bool) -> u128 {
+    let item = if hash_docs {
+        item.to_string()
+    } else {
+        without_doc_comments(item.clone()).to_string()
+    };
+    match salt {
+        Some(salt) => xxh3_128_with_seed(item.as_bytes(), xxh3_64(salt.as_bytes())),
+        None => xxh3_128(item.as_bytes()),
+    }
+}
+
+/// `tokens` without `#[doc = "..."]` and `#![doc = "..."]` attributes,
+/// which doc comments are, at any depth.
+fn without_doc_comments(tokens: TokenStream) -> TokenStream {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut output = TokenStream::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let pound = match tokens[i] {
+            TokenTree::Punct(ref punct) => punct.as_char() == '#',
+            _ => false,
+        };
+        let bang = match tokens.get(i + 1) {
+            Some(TokenTree::Punct(punct)) => punct.as_char() == '!',
+            _ => false,
+        };
+        let attr = if bang { i + 2 } else { i + 1 };
+        match tokens.get(attr) {
+            Some(TokenTree::Group(group)) if pound && is_doc_comment(group) => {
+                i = attr + 1;
+                continue;
+            }
+            _ => {}
+        }
+        output.extend(Some(match tokens[i] {
+            TokenTree::Group(ref group) => {
+                let mut stripped =
+                    Group::new(group.delimiter(), without_doc_comments(group.stream()));
+                stripped.set_span(group.span());
+                TokenTree::Group(stripped)
+            }
+            ref tt => tt.clone(),
+        }));
+        i += 1;
+    }
+    output
+}
+
+/// Whether `attr`, the `[...]` of an attribute, is `[doc = ...]`.
+fn is_doc_comment(attr: &Group) -> bool {
+    let mut tokens = attr.stream().into_iter();
+    attr.delimiter() == Delimiter::Bracket
+        && match (tokens.next(), tokens.next()) {
+            (Some(TokenTree::Ident(doc)), Some(TokenTree::Punct(eq))) => {
+                doc == "doc" && eq.as_char() == '='
+            }
+            _ => false,
+        }
+}
+
+/// The name `macro_rules! name`, hashed to `hash`, is `#[macro_export]`ed
+/// under, if it's exported under a mangled name.
+pub(crate) fn mangled_name(
+    hash: u128,
+    name: &str,
+    restriction: &TokenStream,
+    strategy: Option<Strategy>,
+    mangle: Option<&Mangle>,
+) -> Option<String> {
+    match strategy {
+        _ if !restriction.is_empty() => None,
+        Some(Strategy::DeclMacro) | Some(Strategy::LegacyExport) => None,
+        _ => Some(impl_name(hash, name, mangle)),
+    }
+}
+
+/// The name `macro_rules! name`, hashed to `hash`, is defined under when it
+/// isn't defined under its own, per the `mangle` option. A prefix keeps the
+/// whole hash, in hex, so that macros of the same name in different modules
+/// don't collide at the crate root.
+fn impl_name(hash: u128, name: &str, mangle: Option<&Mangle>) -> String {
+    match mangle {
+        None => format!("macro_impl_{}_{}", hash, unraw(name)),
+        Some(Mangle::Prefix(prefix)) => format!("{}_{:032x}_{}", prefix, hash, unraw(name)),
+        Some(Mangle::Off) => name.to_string(),
+    }
+}
+
+/// [`impl_name`] as an identifier, spanned at `name`.
+fn impl_ident(hash: u128, name: &Ident, mangle: Option<&Mangle>) -> Ident {
+    let impl_name = impl_name(hash, &name.to_string(), mangle);
+    if impl_name.starts_with("r#") {
+        Ident::new_raw(&impl_name[2..], name.span())
+    } else {
+        Ident::new(&impl_name, name.span())
+    }
+}
+
+/// `name` without the `r#` of a raw identifier, to build other identifiers
+/// from.
+pub(crate) fn unraw(name: &str) -> &str {
+    if name.starts_with("r#") {
+        &name[2..]
+    } else {
+        name
+    }
+}
+
+/// Writes `tokens` roughly as they would be formatted in source code.
+pub(crate) fn write_tokens(out: &mut String, tokens: TokenStream) {
+    let mut space = false;
+    let mut after_dollar = false;
+    // Between a `$(...)` repetition and its `*`, `+`, or `?`.
+    let mut in_repetition = false;
+    for tt in tokens {
+        match tt {
+            TokenTree::Group(group) => {
+                if space && !after_dollar {
+                    out.push(' ');
+                }
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace if group.stream().is_empty() => ("{", "}"),
+                    Delimiter::Brace => ("{ ", " }"),
+                    Delimiter::None => ("", ""),
+                };
+                out.push_str(open);
+                write_tokens(out, group.stream());
+                out.push_str(close);
+                in_repetition = after_dollar;
+                after_dollar = false;
+                space = true;
+            }
+            TokenTree::Punct(punct) => {
+                let c = punct.as_char();
+                if space && !in_repetition && c != ',' && c != ';' && c != ':' {
+                    out.push(' ');
+                }
+                out.push(c);
+                if in_repetition {
+                    in_repetition = c != '*' && c != '+' && c != '?';
+                    space = !in_repetition;
+                } else {
+                    space = punct.spacing() == Spacing::Alone && !"$:@#&".contains(c);
+                }
+                after_dollar = c == '$';
+            }
+            tt => {
+                if space && !after_dollar {
+                    out.push(' ');
+                }
+                out.push_str(&tt.to_string());
+                in_repetition = false;
+                after_dollar = false;
+                space = true;
+            }
+        }
+    }
+}
+
+/// Emits a warning pointing at `span`.
+///
+/// Stable proc macros can't emit diagnostics other than errors, so this uses
+/// a deprecated item instead, which warns wherever it's used.
+pub(crate) fn warning(message: &str, span: Span) -> TokenStream {
+    let mut output: TokenStream = format!(
+        "#[deprecated(note = {:?})] #[allow(non_upper_case_globals)] const macro_pub_warning: () = ();",
+        message,
+    )
+    .parse()
+    .unwrap();
+    output.extend(vec![
+        TokenTree::Ident(Ident::new("let", span)),
+        TokenTree::Ident(Ident::new("_", span)),
+        TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("macro_pub_warning", span)),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+    let mut block = "const _: () =".parse::<TokenStream>().unwrap();
+    block.extend(vec![
+        TokenTree::Group(Group::new(Delimiter::Brace, output)),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+    block
+}
+
+/// [`warning`] for one of `#[macro_pub]`'s lints at `level`, which is a
+/// [`spanned_error`] at `Level::Deny`, so that allowing rustc's `deprecated`
+/// lint doesn't let it through.
+pub(crate) fn lint_warning(level: Level, message: &str, span: Span) -> TokenStream {
+    match level {
+        Level::Allow => TokenStream::new(),
+        Level::Warn => warning(message, span),
+        Level::Deny => spanned_error(message, span),
+    }
+}
+
+/// The level `levels` set last for the lint `name`, if any.
+pub(crate) fn last_level(levels: &[(String, Level)], name: &str) -> Option<Level> {
+    levels
+        .iter()
+        .rev()
+        .find(|(lint, _)| lint == name)
+        .map(|&(_, level)| level)
+}
+
+/// `compile_error!`, reported at `span`.
+pub(crate) fn spanned_error(message: &str, span: Span) -> TokenStream {
+    let mut message = Literal::string(message);
+    message.set_span(span);
+    let mut body = Group::new(Delimiter::Brace, TokenTree::Literal(message).into());
+    body.set_span(span);
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+    vec![
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(bang),
+        TokenTree::Group(body),
+    ]
+    .into_iter()
+    .collect()
+}
+
+/// `Span::mixed_site`, or `Span::call_site` on compilers without it.
+#[cfg(has_span_mixed_site)]
+fn mixed_site() -> Span {
+    Span::mixed_site()
+}
+
+#[cfg(not(has_span_mixed_site))]
+fn mixed_site() -> Span {
+    Span::call_site()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STABLE: Config = Config {
+        has_simple_decl_macro: false,
+        doc_decl_macro: false,
+        transparency: "semiopaque",
+        rustc_version: "rustc 1.41.0 (5e1a79984 2020-01-27)",
+        package: None,
+        krate: None,
+        doc_required: false,
+        pin_backend: false,
+        lint_levels: Ok(Vec::new()),
+        dependency: false,
+        config_file: Ok(None),
+    };
+
+    const NIGHTLY: Config = Config {
+        has_simple_decl_macro: true,
+        doc_decl_macro: true,
+        transparency: "semiopaque",
+        rustc_version: "rustc 1.93.0-nightly (1d60f9e07 2025-12-01)",
+        package: None,
+        krate: None,
+        doc_required: false,
+        pin_backend: false,
+        lint_levels: Ok(Vec::new()),
+        dependency: false,
+        config_file: Ok(None),
+    };
+
+    /// [`super::macro_pub`], parsing `attr` as the attribute does.
+    fn macro_pub(attr: TokenStream, item: TokenStream, config: &Config) -> TokenStream {
+        super::macro_pub(attr.clone(), &Args::parse(attr), item, config)
+    }
+
+    #[track_caller]
+    fn assert_expands(config: &Config, attr: &str, item: &str, expected: &str) {
+        let actual = macro_pub(attr.parse().unwrap(), item.parse().unwrap(), config);
+        let hash = macro_hash(&item.parse().unwrap(), None, false);
+        let expected = expected.replace("HASH", &hash.to_string());
+        assert_eq!(
+            actual.to_string(),
+            expected.parse::<TokenStream>().unwrap().to_string(),
+        );
+    }
+
+    #[test]
+    fn restricted() {
+        assert_expands(
+            &STABLE,
+            "crate",
+            "/// docs\nmacro_rules! m { () => {}; }",
+            r#"
+                #[doc = " docs"]
+                macro_rules! m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn world_public() {
+        assert_expands(
+            &STABLE,
+            "",
+            "/// docs\nmacro_rules! m { () => {}; }",
+            r#"
+                #[doc = " docs"]
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn world_public_nightly() {
+        assert_expands(
+            &NIGHTLY,
+            "",
+            "/// docs\nmacro_rules! m { () => {}; ($e:expr) => { $e }; }",
+            r#"
+                #[doc = " docs"]
+                #[cfg(doc)]
+                #[rustc_macro_transparency = "semiopaque"]
+                pub macro m { () => {}, ($e:expr) => { $e }, }
+                #[doc = " docs"]
+                #[cfg(not(doc))]
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; ($e:expr) => { $e }; }
+                #[cfg(not(doc))]
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn internal_rules() {
+        assert_expands(
+            &STABLE,
+            "crate, internal_rules",
+            "/// docs\nmacro_rules! m { ($e:expr) => { m!(@twice $e) }; (@twice $e:expr) => { $crate::inner::m!(@once $e) + $e }; (@once $e:expr) => { $e }; }",
+            r#"
+                #[doc(hidden)]
+                macro_rules! __macro_pub_internal_m {
+                    (@twice $e:expr) => { $crate::inner::__macro_pub_internal_m!(@once $e) + $e };
+                    (@once $e:expr) => { $e };
+                }
+                #[doc(hidden)]
+                #[allow(unused_imports)]
+                pub(crate) use __macro_pub_internal_m as __macro_pub_internal_m;
+                #[doc = " docs"]
+                macro_rules! m { ($e:expr) => { __macro_pub_internal_m!(@twice $e) }; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn hide_arms() {
+        assert_expands(
+            &NIGHTLY,
+            "hide_arms",
+            "/// docs\nmacro_rules! m { () => {}; (@internal $e:expr) => { $e }; }",
+            r#"
+                #[doc = " docs"]
+                #[cfg(doc)]
+                pub macro m { ($($args:tt)*) => { __macro_pub_impl_m! { $($args)* } }, }
+                #[doc = " docs"]
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; (@internal $e:expr) => { $e }; }
+                #[doc(hidden)]
+                pub use macro_impl_HASH_m as __macro_pub_impl_m;
+                #[cfg(not(doc))]
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn hidden_arms() {
+        let item = "macro_rules! m { ($($e:expr),*) => { $crate::m!(@sum $($e)*) }; #[doc(hidden)] (@sum $($e:expr)*) => { 0 $(+ $e)* }; }";
+        assert_expands(
+            &NIGHTLY,
+            "",
+            item,
+            r#"
+                #[cfg(doc)]
+                pub macro m { ($($e:expr),*) => { __macro_pub_impl_m! { $($e),* } }, }
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { ($($e:expr),*) => { $crate::__macro_pub_impl_m!(@sum $($e)*) }; (@sum $($e:expr)*) => { 0 $(+ $e)* }; }
+                #[doc(hidden)]
+                pub use macro_impl_HASH_m as __macro_pub_impl_m;
+                #[cfg(not(doc))]
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+            "#,
+        );
+        let expanded = macro_pub("crate".parse().unwrap(), item.parse().unwrap(), &NIGHTLY);
+        assert!(expanded
+            .to_string()
+            .contains("ignored `#[doc(hidden)]` on this arm"));
+    }
+
+    #[test]
+    fn restricted_nightly() {
+        assert_expands(
+            &NIGHTLY,
+            "in super::module",
+            "/// docs\nmacro_rules! m { () => {}; }",
+            r#"
+                #[doc = " docs"]
+                #[cfg(doc)]
+                #[rustc_macro_transparency = "semiopaque"]
+                pub(in super::module) macro m { () => {}, }
+                #[doc = " docs"]
+                #[cfg_attr(doc, doc(hidden))]
+                macro_rules! m { () => {}; }
+                #[cfg(not(doc))]
+                #[allow(unused_imports)]
+                pub(in super::module) use m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn internal_attrs() {
+        assert_expands(
+            &NIGHTLY,
+            "export_as = n",
+            "/// docs\n#[allow_internal_unstable(core_intrinsics)]\nmacro_rules! m { () => {}; }",
+            r#"
+                #[doc = " docs"]
+                #[cfg(doc)]
+                #[rustc_macro_transparency = "semiopaque"]
+                pub macro m { () => {}, }
+                #[doc = " docs"]
+                #[cfg(not(doc))]
+                #[macro_export]
+                #[doc(hidden)]
+                #[allow_internal_unstable(core_intrinsics)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[cfg(not(doc))]
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+                #[macro_export]
+                #[doc(hidden)]
+                #[allow_internal_unstable(core_intrinsics)]
+                macro_rules! macro_impl_HASH_n { () => {}; }
+                #[doc(hidden)]
+                #[allow(deprecated)]
+                pub use macro_impl_HASH_n as n;
+            "#,
+        );
+    }
+
+    #[test]
+    fn arm_attrs() {
+        assert_expands(
+            &STABLE,
+            "crate",
+            "macro_rules! m { /// docs\n () => {}; }",
+            r#"
+                macro_rules! m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+                const _: () = {
+                    #[deprecated(
+                        note = "`#[macro_pub]` removed this attribute; attributes are not allowed on macro arms"
+                    )]
+                    #[allow(non_upper_case_globals)]
+                    const macro_pub_warning: () = ();
+                    let _ = macro_pub_warning;
+                };
+            "#,
+        );
+    }
+
+    #[test]
+    fn rename() {
+        let attr = r#"crate, rename = "new""#.parse::<TokenStream>().unwrap();
+        let item = "/// `old!`\nmacro_rules! old { ($old:ident) => { old!(); $old!(); $crate::a::old!() }; }";
+        let args = Args::parse(attr.clone()).ok();
+        let item = renamed(args.as_ref(), item.parse().unwrap());
+        assert_eq!(
+            item.to_string(),
+            "/// `old!`\nmacro_rules! new { ($old:ident) => { new!(); $old!(); $crate::a::new!() }; }"
+                .parse::<TokenStream>()
+                .unwrap()
+                .to_string(),
+        );
+        assert_eq!(
+            macro_pub(attr, item, &STABLE).to_string(),
+            r#"
+                #[doc = " `old!`"]
+                macro_rules! new { ($old:ident) => { new!(); $old!(); $crate::a::new!() }; }
+                #[allow(unused_imports)]
+                pub(crate) use new as new;
+            "#
+            .parse::<TokenStream>()
+            .unwrap()
+            .to_string(),
+        );
+    }
+
+    #[test]
+    fn raw_names() {
+        assert_expands(
+            &STABLE,
+            "crate",
+            "macro_rules! r#match { () => {}; }",
+            r#"
+                macro_rules! r#match { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use r#match as r#match;
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            "export_as = r#try, metadata, allow(self_calls)",
+            "macro_rules! r#match { () => { r#match!() }; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_match { () => { r#match!() }; }
+                #[doc(inline)]
+                pub use macro_impl_HASH_match as r#match;
+                #[allow(unused_macros)]
+                macro_rules! r#match { () => { r#match!() }; }
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_try { () => { r#match!() }; }
+                #[doc(hidden)]
+                #[allow(deprecated)]
+                pub use macro_impl_HASH_try as r#try;
+                #[doc(hidden)]
+                pub mod __macro_pub_metadata_match {
+                    pub const NAME: &str = "r#match";
+                    pub const ARMS: usize = 1;
+                    pub const VISIBILITY: &str = "pub";
+                    pub const MANGLED: Option<&str> = Some("macro_impl_HASH_match");
+                }
+            "#,
+        );
+    }
+
+    #[test]
+    fn delimiters() {
+        for item in &[
+            "macro_rules! m ( () => {}; );",
+            "macro_rules! m [ () => {}; ];",
+        ] {
+            assert_expands(
+                &STABLE,
+                "",
+                item,
+                r#"
+                    #[macro_export]
+                    #[doc(hidden)]
+                    macro_rules! macro_impl_HASH_m { () => {}; }
+                    #[doc(inline)]
+                    pub use macro_impl_HASH_m as m;
+                "#,
+            );
+        }
+    }
+
+    #[test]
+    fn item_attrs() {
+        assert_expands(
+            &STABLE,
+            "crate",
+            "#[inline] #[must_use] macro_rules! m { () => {}; }",
+            r#"
+                macro_rules! m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+                const _: () = {
+                    #[deprecated(
+                        note = "`#[macro_pub]` removed `#[inline]`, which doesn't apply to macros"
+                    )]
+                    #[allow(non_upper_case_globals)]
+                    const macro_pub_warning: () = ();
+                    let _ = macro_pub_warning;
+                };
+                const _: () = {
+                    #[deprecated(
+                        note = "`#[macro_pub]` removed `#[must_use]`, which doesn't apply to macros"
+                    )]
+                    #[allow(non_upper_case_globals)]
+                    const macro_pub_warning: () = ();
+                    let _ = macro_pub_warning;
+                };
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            "crate, allow(item_attrs)",
+            "#[inline] macro_rules! m { () => {}; }",
+            r#"
+                macro_rules! m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn signatures() {
+        assert_expands(
+            &STABLE,
+            "crate, signatures",
+            "/// docs\nmacro_rules! m { () => {}; (@inner $a:ident::$b:ident) => {}; [$($e:expr),+ $(,)?] => {}; }",
+            r##"
+                #[doc = " docs"]
+                #[doc = ""]
+                #[doc = "# Signatures"]
+                #[doc = ""]
+                #[doc = "```text"]
+                #[doc = "m!()"]
+                #[doc = "m!(@inner $a:ident::$b:ident)"]
+                #[doc = "m![$($e:expr),+ $(,)?]"]
+                #[doc = "```"]
+                macro_rules! m { () => {}; (@inner $a:ident::$b:ident) => {}; [$($e:expr),+ $(,)?] => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+            "##,
+        );
+    }
+
+    #[test]
+    fn arm_cfgs() {
+        let item = r#"/// docs
+            macro_rules! m {
+                () => {};
+                #[doc(cfg(feature = "json"))]
+                (json $e:expr) => {};
+                #[doc(cfg(unix))]
+                #[doc(hidden)]
+                (@unix) => {};
+                #[doc(cfg(any(unix, windows)))]
+                (native) => {};
+            }"#;
+        let arms = "() => {}; (json $e:expr) => {}; (@unix) => {}; (native) => {};";
+        assert_expands(
+            &STABLE,
+            "crate",
+            item,
+            &format!(
+                r#"
+                    #[doc = " docs"]
+                    #[doc = ""]
+                    #[doc = "*`m!(json $e:expr)` requires the `json` feature.*"]
+                    #[doc = ""]
+                    #[doc = "*`m!(native)` requires `cfg(any(unix, windows))`.*"]
+                    macro_rules! m {{ {} }}
+                    #[allow(unused_imports)]
+                    pub(crate) use m as m;
+                    const _: () = {{
+                        #[deprecated(
+                            note = "`#[macro_pub]` ignored `#[doc(hidden)]` on this arm, since only world-public macros documented as a `pub macro` can hide arms"
+                        )]
+                        #[allow(non_upper_case_globals)]
+                        const macro_pub_warning: () = ();
+                        let _ = macro_pub_warning;
+                    }};
+                "#,
+                arms,
+            ),
+        );
+        let expanded = macro_pub(
+            "crate, signatures".parse().unwrap(),
+            item.parse().unwrap(),
+            &STABLE,
+        );
+        assert!(
+            expanded.to_string().contains(&format!(
+                "{:?}",
+                r#"m!(json $e:expr)  // #[cfg(feature = "json")]"#,
+            )),
+            "{}",
+            expanded,
+        );
+        assert!(!expanded.to_string().contains("requires"), "{}", expanded);
+    }
+
+    #[test]
+    fn no_inline() {
+        assert_expands(
+            &STABLE,
+            "no_inline",
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[doc(no_inline)]
+                pub use macro_impl_HASH_m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn hidden() {
+        assert_expands(
+            &STABLE,
+            "hidden",
+            "/// Docs.\nmacro_rules! m { () => {}; }",
+            r#"
+                #[doc = " Docs."]
+                #[doc(hidden)]
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[doc(hidden)]
+                pub use macro_impl_HASH_m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn doc_hidden() {
+        let item = "/// Docs.\n#[doc(hidden)]\nmacro_rules! m { () => {}; }";
+        assert_expands(
+            &STABLE,
+            "",
+            item,
+            r#"
+                #[doc = " Docs."]
+                #[doc(hidden)]
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[doc(hidden)]
+                pub use macro_impl_HASH_m as m;
+            "#,
+        );
+        let expanded = macro_pub("hidden".parse().unwrap(), item.parse().unwrap(), &STABLE);
+        assert!(expanded.to_string().contains(
+            "option `hidden` has no effect, since the macro is already `#[doc(hidden)]`"
+        ));
+    }
+
+    #[test]
+    fn textual() {
+        assert_expands(
+            &STABLE,
+            "textual",
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+                #[allow(unused_macros)]
+                macro_rules! m { () => {}; }
+            "#,
+        );
+    }
+
+    #[test]
+    fn shadowable() {
+        assert_expands(
+            &STABLE,
+            "shadowable",
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                mod __macro_pub_shadowable_m {
+                    #[doc(inline)]
+                    pub use macro_impl_HASH_m as m;
+                }
+                #[doc(inline)]
+                #[allow(unused_imports)]
+                pub use self::__macro_pub_shadowable_m::*;
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            "super, shadowable",
+            "macro_rules! m { () => {}; }",
+            r#"
+                macro_rules! m { () => {}; }
+                mod __macro_pub_shadowable_m {
+                    pub(crate) use m as m;
+                }
+                #[allow(unused_imports)]
+                pub(super) use self::__macro_pub_shadowable_m::*;
+            "#,
+        );
+        assert_expands(
+            &NIGHTLY,
+            "hide_arms, shadowable",
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[doc(hidden)]
+                pub use macro_impl_HASH_m as __macro_pub_impl_m;
+                mod __macro_pub_shadowable_m {
+                    #[cfg(doc)]
+                    use super::__macro_pub_impl_m;
+                    #[cfg(doc)]
+                    pub macro m { ($($args:tt)*) => { __macro_pub_impl_m! { $($args)* } }, }
+                    #[cfg(not(doc))]
+                    #[doc(inline)]
+                    pub use macro_impl_HASH_m as m;
+                }
+                #[doc(inline)]
+                #[allow(unused_imports)]
+                pub use self::__macro_pub_shadowable_m::*;
+            "#,
+        );
+    }
+
+    #[test]
+    fn doc_required() {
+        let config = Config {
+            doc_required: true,
+            ..STABLE
+        };
+        for (attr, item) in &[
+            ("doc_required", "/// docs\nmacro_rules! m { () => {}; }"),
+            ("crate", "macro_rules! m { () => {}; }"),
+            ("hidden", "macro_rules! m { () => {}; }"),
+            ("", "#[doc(hidden)] macro_rules! m { () => {}; }"),
+            (
+                "",
+                r#"#[doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/docs/m.md"))]
+                macro_rules! m { () => {}; }"#,
+            ),
+        ] {
+            assert!(
+                !macro_pub(attr.parse().unwrap(), item.parse().unwrap(), &config)
+                    .to_string()
+                    .contains("compile_error")
+            );
+        }
+        for (attr, config) in &[("doc_required", &STABLE), ("", &config)] {
+            for item in &[
+                "macro_rules! m { () => {}; }",
+                "///\nmacro_rules! m { () => {}; }",
+            ] {
+                assert!(
+                    macro_pub(attr.parse().unwrap(), item.parse().unwrap(), config)
+                        .to_string()
+                        .contains(
+                            "`#[macro_pub]` macro `m!` is world-public, so it needs documentation"
+                        )
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn salt() {
+        let item = "macro_rules! m { () => {}; }"
+            .parse::<TokenStream>()
+            .unwrap();
+        let mangled = |attr: &str| {
+            let output = macro_pub(attr.parse().unwrap(), item.clone(), &STABLE).to_string();
+            let start = output.find("macro_impl_").unwrap();
+            output[start..].split(' ').next().unwrap().to_string()
+        };
+        let hash = |salt| macro_hash(&item, salt, false);
+        assert_eq!(mangled(""), format!("macro_impl_{}_m", hash(None)));
+        assert_eq!(
+            mangled(r#"salt = "v2""#),
+            format!("macro_impl_{}_m", hash(Some("v2"))),
+        );
+        assert_ne!(hash(Some("v2")), hash(None));
+        assert_ne!(hash(Some("v2")), hash(Some("v3")));
+    }
+
+    #[test]
+    fn hash_docs() {
+        let hash = |item: &str| macro_hash(&item.parse().unwrap(), None, false);
+        let item = "macro_rules! m { () => { 1 }; }";
+        assert_eq!(
+            hash("/// Docs.\nmacro_rules! m {\n    () => { 1 };\n}"),
+            hash(item),
+        );
+        assert_eq!(
+            hash("#[doc = \"Docs.\"] macro_rules! m { /// An arm.\n () => { 1 }; }"),
+            hash(item),
+        );
+        assert_ne!(
+            hash("#[doc(hidden)] macro_rules! m { () => { 1 }; }"),
+            hash(item)
+        );
+        assert_ne!(hash("macro_rules! m { () => { 2 }; }"), hash(item));
+
+        // Macros of the same name, differing only in their docs, are only
+        // told apart with `hash_docs`, or a `salt`.
+        let mangled = |attr: &str, docs: &str| {
+            let item = format!("#[doc = {:?}] macro_rules! m {{ () => {{ 1 }}; }}", docs);
+            let output = macro_pub(attr.parse().unwrap(), item.parse().unwrap(), &STABLE);
+            let output = output.to_string();
+            let start = output.find("macro_impl_").unwrap();
+            output[start..].split(' ').next().unwrap().to_string()
+        };
+        assert_eq!(mangled("", "One."), mangled("", "Two."));
+        assert_ne!(mangled("hash_docs", "One."), mangled("hash_docs", "Two."));
+        assert_ne!(mangled(r#"salt = "a""#, "One."), mangled("", "Two."));
+    }
+
+    #[test]
+    fn mangle() {
+        let item = "macro_rules! m { () => {}; }";
+        let hex = format!("{:032x}", macro_hash(&item.parse().unwrap(), None, false));
+        assert_expands(
+            &STABLE,
+            r#"mangle = "my_crate""#,
+            item,
+            &r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! my_crate_HEX_m { () => {}; }
+                #[doc(inline)]
+                pub use my_crate_HEX_m as m;
+            "#
+            .replace("HEX", &hex),
+        );
+        assert_expands(
+            &STABLE,
+            "mangle = false",
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! m { () => {}; }
+                #[doc(inline)]
+                pub use m as m;
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            r#"crate, no_textual, mangle = "my_crate""#,
+            item,
+            &r#"
+                macro_rules! my_crate_HEX_m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use my_crate_HEX_m as m;
+            "#
+            .replace("HEX", &hex),
+        );
+
+        // Every name generated for the macro is mangled the same way.
+        let expanded = macro_pub(
+            r#"mangle = "my_crate", export_as = other, deprecate_as = "old", friends("a")"#
+                .parse()
+                .unwrap(),
+            item.parse().unwrap(),
+            &STABLE,
+        )
+        .to_string();
+        for name in &["m", "other", "old", "__macro_pub_friends_m"] {
+            let mangled = format!("macro_rules ! my_crate_{}_{} ", hex, name);
+            assert!(expanded.contains(&mangled), "{} in {}", mangled, expanded);
+        }
+        assert!(!expanded.contains("macro_impl_"), "{}", expanded);
+
+        // Macros of the same name in different modules still differ.
+        let other = "macro_rules! m { ($e:expr) => { $e }; }";
+        let expanded = macro_pub(
+            r#"mangle = "my_crate""#.parse().unwrap(),
+            other.parse().unwrap(),
+            &STABLE,
+        );
+        assert!(!expanded
+            .to_string()
+            .contains(&format!("my_crate_{}_m", hex)));
+    }
+
+    #[test]
+    fn expect_path() {
+        assert_expands(
+            &STABLE,
+            r#"crate, expect_path = "crate::a::m""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                macro_rules! m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+                const _: () = {
+                    #[allow(unused_imports)]
+                    use crate::a::m as _;
+                };
+            "#,
+        );
+    }
+
+    #[test]
+    fn lint_levels() {
+        let expand = |config: &Config, attr: &str, item: &str| {
+            macro_pub(attr.parse().unwrap(), item.parse().unwrap(), config).to_string()
+        };
+        let pointless = "has no effect, since only world-public macros need documentation";
+        let item = "macro_rules! m { () => {}; }";
+        assert!(!expand(
+            &STABLE,
+            "crate, doc_required, allow(pointless_options)",
+            item
+        )
+        .contains(pointless));
+        let denied = expand(
+            &STABLE,
+            "crate, doc_required, deny(pointless_options)",
+            item,
+        );
+        assert!(denied.contains("compile_error") && denied.contains(pointless));
+        assert!(!denied.contains("deprecated"));
+
+        // Levels set for the crate give way to the macro's own.
+        let config = Config {
+            lint_levels: Ok(vec![("pointless_options".to_string(), Level::Deny)]),
+            ..STABLE
+        };
+        assert!(expand(&config, "crate, doc_required", item).contains("compile_error"));
+        assert!(!expand(
+            &config,
+            "crate, doc_required, warn(pointless_options)",
+            item
+        )
+        .contains("compile_error"));
+
+        // Dependencies only warn about denied lints, as `--cap-lints` has
+        // rustc's.
+        let dependency = Config {
+            dependency: true,
+            ..config
+        };
+        let capped = expand(
+            &dependency,
+            "crate, doc_required, deny(pointless_options)",
+            item,
+        );
+        assert!(!capped.contains("compile_error") && capped.contains(pointless));
+
+        // Lints checked by an option are allowed without it, unless they're
+        // given a level, and the option overrides the crate's level.
+        let env = r#"macro_rules! m { () => { env!("X") }; }"#;
+        let foreign_env = "which reads the environment compiling the crate using the macro";
+        assert!(!expand(&STABLE, "", env).contains(foreign_env));
+        assert!(expand(&STABLE, "warn(foreign_env)", env).contains(foreign_env));
+        let config = Config {
+            lint_levels: Ok(vec![("foreign_env".to_string(), Level::Allow)]),
+            ..STABLE
+        };
+        assert!(expand(&config, "lint", env).contains(foreign_env));
+
+        let config = Config {
+            lint_levels: Err(
+                "`MACRO_PUB_LINTS` expects lint levels, like `deny(foreign_env)`".to_string(),
+            ),
+            ..STABLE
+        };
+        assert!(expand(&config, "", item).contains("compile_error"));
+    }
+
+    #[test]
+    fn pin_backend() {
+        let pinned = Config {
+            pin_backend: true,
+            ..NIGHTLY
+        };
+        for attr in &["", "crate", "hide_arms", "shadowable"] {
+            let expand = |config| {
+                macro_pub(
+                    attr.parse().unwrap(),
+                    "/// docs\nmacro_rules! m { () => {}; }".parse().unwrap(),
+                    config,
+                )
+                .to_string()
+            };
+            assert_eq!(expand(&pinned), expand(&STABLE), "{}", attr);
+        }
+        // An explicit strategy still uses what the toolchain supports.
+        assert!(macro_pub(
+            r#"strategy = "decl_macro""#.parse().unwrap(),
+            "macro_rules! m { () => {}; }".parse().unwrap(),
+            &pinned,
+        )
+        .to_string()
+        .contains("pub macro m"));
+    }
+
+    #[test]
+    fn catch_all() {
+        assert_expands(
+            &STABLE,
+            "catch_all",
+            "macro_rules! m { () => {}; (@inner) => {}; #[doc(hidden)] (secret) => {}; ($e:expr) => { $e }; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m {
+                    () => {};
+                    (@inner) => {};
+                    (secret) => {};
+                    ($e:expr) => { $e };
+                    ($($tokens:tt)*) => {
+                        compile_error! {
+                            concat!("`m!` doesn't accept `", stringify!($($tokens)*), "`; expected one of:\n    m!()\n    m!($e:expr)")
+                        }
+                    };
+                }
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn extend() {
+        assert_expands(
+            &STABLE,
+            "extend",
+            "macro_rules! m { ($e:expr) => { $e }; }",
+            r#"
+                #[doc(hidden)]
+                macro_rules! __macro_pub_extend_HASH_m {
+                    ($__macro_pub_dollar:tt [($($next:tt)*) $($rest:tt)*] ($($mount:tt)*) $($arms:tt)*) => {
+                        $($next)*! { $__macro_pub_dollar [$($rest)*] ($($mount)*) $($arms)* ($__macro_pub_dollar e:expr) => { $__macro_pub_dollar e }; }
+                    };
+                    ($__macro_pub_dollar:tt [] ($($mount:tt)*) $($arms:tt)*) => {
+                        $($mount)* { $($arms)* ($__macro_pub_dollar e:expr) => { $__macro_pub_dollar e }; }
+                    };
+                }
+                #[doc(hidden)]
+                pub(crate) use __macro_pub_extend_HASH_m as m;
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            "crate, extend_from(a::m, b::m), signatures",
+            "/// docs\nmacro_rules! m { () => {}; }",
+            r#"
+                a::m! {
+                    $ [(b::m)]
+                    (#[::macro_pub::macro_pub(crate, signatures)] #[doc = " docs"] macro_rules! m)
+                    () => {};
+                }
+            "#,
+        );
+    }
+
+    #[test]
+    fn self_calls() {
+        assert_expands(
+            &STABLE,
+            "",
+            "macro_rules! m { () => { m!(1) }; ($e:expr) => { $crate::a::m!($e, $e) }; ($a:expr, $b:expr) => { $a + $b }; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m {
+                    () => { $crate::a::m!(1) };
+                    ($e:expr) => { $crate::a::m!($e, $e) };
+                    ($a:expr, $b:expr) => { $a + $b };
+                }
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            "allow(self_calls)",
+            "macro_rules! m { () => { m!(1) }; ($e:expr) => { $e }; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => { m!(1) }; ($e:expr) => { $e }; }
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+                #[allow(unused_macros)]
+                macro_rules! m { () => { m!(1) }; ($e:expr) => { $e }; }
+            "#,
+        );
+        let expanded = macro_pub(
+            TokenStream::new(),
+            "macro_rules! m { () => { m!(1) }; ($e:expr) => { $e }; }"
+                .parse()
+                .unwrap(),
+            &STABLE,
+        );
+        assert!(expanded.to_string().contains(
+            "`#[macro_pub]` macro `m!` invokes itself as `m!`, which only finds it where it's in \
+             textual scope"
+        ));
+        // Restricted macros keep their name, so find themselves as before.
+        let expanded = macro_pub(
+            "crate".parse().unwrap(),
+            "macro_rules! m { () => { m!(1) }; ($e:expr) => { $e }; }"
+                .parse()
+                .unwrap(),
+            &STABLE,
+        );
+        assert!(!expanded.to_string().contains("invokes itself"));
+    }
+
+    #[test]
+    fn self_calls_expect_path() {
+        assert_expands(
+            &STABLE,
+            r#"expect_path = "crate::a::m""#,
+            "macro_rules! m { () => { m!(1) }; ($e:expr) => { $crate::m!($e, $e) }; ($a:expr, $b:expr) => { $a + $b }; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m {
+                    () => { $crate::a::m!(1) };
+                    ($e:expr) => { $crate::a::m!($e, $e) };
+                    ($a:expr, $b:expr) => { $a + $b };
+                }
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+                const _: () = {
+                    #[allow(unused_imports)]
+                    use crate::a::m as _;
+                };
+            "#,
+        );
+        // Only a path into the crate, to the macro, tells where it is.
+        for path in &["super::m", "crate::a::n"] {
+            let expanded = macro_pub(
+                format!("expect_path = {:?}", path).parse().unwrap(),
+                "macro_rules! m { () => { m!(1) }; ($e:expr) => { $e }; }"
+                    .parse()
+                    .unwrap(),
+                &STABLE,
+            );
+            assert!(expanded
+                .to_string()
+                .contains("macro_rules ! m { () => { m ! (1) } ;"));
+        }
+    }
+
+    #[test]
+    fn local_inner_macros() {
+        assert_expands(
+            &STABLE,
+            "crate",
+            "#[macro_export(local_inner_macros)] macro_rules! m { \
+                (helper!($e:expr)) => { helper!($e); ::std::println!(); $name!(); a != b; }; \
+                () => { macro_rules! inner { () => { vec![] }; } }; \
+            }",
+            r#"
+                macro_rules! m {
+                    (helper!($e:expr)) => { $crate::helper!($e); ::std::println!(); $name!(); a != b; };
+                    () => { macro_rules! inner { () => { $crate::vec![] }; } };
+                }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn root_self_paths() {
+        assert_expands(
+            &STABLE,
+            "crate",
+            "macro_rules! m { () => { $crate::m!(1) }; ($e:expr) => { $crate::a::m!($e, $e) }; ($a:expr, $b:expr) => { $a + $b }; }",
+            r#"
+                macro_rules! m {
+                    () => { $crate::a::m!(1) };
+                    ($e:expr) => { $crate::a::m!($e, $e) };
+                    ($a:expr, $b:expr) => { $a + $b };
+                }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+            "#,
+        );
+        let expanded = macro_pub(
+            "lint".parse().unwrap(),
+            "macro_rules! m { () => { $crate::m!(1) }; ($e:expr) => { $e }; }"
+                .parse()
+                .unwrap(),
+            &STABLE,
+        );
+        assert!(expanded.to_string().contains(
+            "`#[macro_pub]` macro `m!` uses `$crate::m!`, which only finds it if it's defined at the crate root"
+        ));
+    }
+
+    #[test]
+    fn no_textual() {
+        assert_expands(
+            &STABLE,
+            "crate, no_textual, deprecate_as = \"old\"",
+            "macro_rules! m { () => {}; }",
+            r#"
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use macro_impl_HASH_m as m;
+                #[doc(hidden)]
+                #[deprecated(note = "renamed to `m`")]
+                macro_rules! macro_impl_HASH_old { () => {}; }
+                #[doc(hidden)]
+                #[allow(deprecated)]
+                pub(crate) use macro_impl_HASH_old as old;
+            "#,
+        );
+    }
+
+    #[test]
+    fn export_as() {
+        assert_expands(
+            &STABLE,
+            "crate, export_as = other",
+            "macro_rules! m { () => {}; }",
+            r#"
+                macro_rules! m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_other { () => {}; }
+                #[doc(hidden)]
+                #[allow(deprecated)]
+                pub use macro_impl_HASH_other as other;
+            "#,
+        );
+    }
+
+    #[test]
+    fn deprecate_as() {
+        assert_expands(
+            &STABLE,
+            r#"crate, deprecate_as = "old""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                macro_rules! m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+                #[doc(hidden)]
+                #[deprecated(note = "renamed to `m`")]
+                macro_rules! old { () => {}; }
+                #[doc(hidden)]
+                #[allow(deprecated)]
+                pub(crate) use old as old;
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            r#"deprecate_as = "old""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+                #[doc(hidden)]
+                #[deprecated(note = "renamed to `m`")]
+                #[macro_export]
+                macro_rules! macro_impl_HASH_old { () => {}; }
+                #[doc(hidden)]
+                #[allow(deprecated)]
+                pub use macro_impl_HASH_old as old;
+            "#,
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        assert_expands(
+            &STABLE,
+            r#"alias = "old", alias = "older""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+                #[doc(hidden)]
+                pub use self::m as old;
+                #[doc(hidden)]
+                pub use self::m as older;
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            r#"crate, alias = "old""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                macro_rules! m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+                #[doc(hidden)]
+                #[allow(unused_imports)]
+                pub(crate) use m as old;
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            r#"no_alias, alias = "old""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[doc(hidden)]
+                pub use macro_impl_HASH_m as old;
+            "#,
+        );
+    }
+
+    #[test]
+    fn hygienic_as() {
+        assert_expands(
+            &NIGHTLY,
+            r#"crate, hygienic_as = "m2""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[cfg(doc)]
+                #[rustc_macro_transparency = "semiopaque"]
+                pub(crate) macro m { () => {}, }
+                #[cfg_attr(doc, doc(hidden))]
+                macro_rules! m { () => {}; }
+                #[cfg(not(doc))]
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+                #[doc = "`m!`, with the hygiene of a `macro`: names its expansion defines aren't visible to the caller, and names it uses are resolved where it's defined."]
+                pub(crate) macro m2 { () => {}, }
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            r#"crate, hygienic_as = "m2""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                macro_rules! m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+            "#,
+        );
+        // `nightly-docs` only forces the `cfg(doc)` copy.
+        assert_expands(
+            &Config {
+                has_simple_decl_macro: false,
+                ..NIGHTLY
+            },
+            r#"crate, hygienic_as = "m2""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[cfg(doc)]
+                #[rustc_macro_transparency = "semiopaque"]
+                pub(crate) macro m { () => {}, }
+                #[cfg_attr(doc, doc(hidden))]
+                macro_rules! m { () => {}; }
+                #[cfg(not(doc))]
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn friends() {
+        assert_expands(
+            &STABLE,
+            r#"crate, friends("a", "b")"#,
+            "macro_rules! m { () => {}; ($e:expr) => { $e }; }",
+            r#"
+                macro_rules! m { () => {}; ($e:expr) => { $e }; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH___macro_pub_friends_m {
+                    (__macro_pub_friend_a) => {};
+                    (__macro_pub_friend_a $e:expr) => { $e };
+                    (__macro_pub_friend_b) => {};
+                    (__macro_pub_friend_b $e:expr) => { $e };
+                }
+                #[doc(hidden)]
+                #[allow(deprecated)]
+                pub use macro_impl_HASH___macro_pub_friends_m as __macro_pub_friends_m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn package() {
+        let config = Config {
+            package: Some("my-package".to_string()),
+            ..STABLE
+        };
+        assert_expands(
+            &config,
+            r#"package, friends("a")"#,
+            "macro_rules! m { ($e:expr) => { $e }; }",
+            r#"
+                macro_rules! m { ($e:expr) => { $e }; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH___macro_pub_friends_m {
+                    (__macro_pub_friend_a $e:expr) => { $e };
+                    (__macro_pub_package_my_package $e:expr) => { $e };
+                }
+                #[doc(hidden)]
+                #[allow(deprecated)]
+                pub use macro_impl_HASH___macro_pub_friends_m as __macro_pub_friends_m;
+            "#,
+        );
+        assert!(macro_pub(
+            "package".parse().unwrap(),
+            "macro_rules! m { () => {}; }".parse().unwrap(),
+            &STABLE,
+        )
+        .to_string()
+        .contains("needs Cargo to set `CARGO_PKG_NAME`"));
+    }
+
+    #[test]
+    fn doctest() {
+        let config = Config {
+            package: Some("my-package".to_string()),
+            krate: Some("my_crate".to_string()),
+            ..STABLE
+        };
+        assert_expands(
+            &config,
+            "crate, doctest",
+            "/// ```\n/// m!();\n/// ```\n///\n/// ```text\n/// m!()\n/// ```\nmacro_rules! m { () => {}; }",
+            r#"
+                #[doc = " ```"]
+                #[doc = " # macro_rules! m { ($($tokens:tt)*) => { ::my_crate::macro_impl_HASH___macro_pub_friends_m! { __macro_pub_package_my_package $($tokens)* } }; }"]
+                #[doc = " m!();"]
+                #[doc = " ```"]
+                #[doc = ""]
+                #[doc = " ```text"]
+                #[doc = " m!()"]
+                #[doc = " ```"]
+                macro_rules! m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH___macro_pub_friends_m {
+                    (__macro_pub_package_my_package) => {};
+                }
+                #[doc(hidden)]
+                #[allow(deprecated)]
+                pub use macro_impl_HASH___macro_pub_friends_m as __macro_pub_friends_m;
+            "#,
+        );
+        assert_expands(
+            &config,
+            "crate, doctest",
+            "/// ```\n/// r#match!();\n/// ```\nmacro_rules! r#match { () => {}; }",
+            r#"
+                #[doc = " ```"]
+                #[doc = " # macro_rules! r#match { ($($tokens:tt)*) => { ::my_crate::macro_impl_HASH___macro_pub_friends_match! { __macro_pub_package_my_package $($tokens)* } }; }"]
+                #[doc = " r#match!();"]
+                #[doc = " ```"]
+                macro_rules! r#match { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use r#match as r#match;
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH___macro_pub_friends_match {
+                    (__macro_pub_package_my_package) => {};
+                }
+                #[doc(hidden)]
+                #[allow(deprecated)]
+                pub use macro_impl_HASH___macro_pub_friends_match as __macro_pub_friends_match;
+            "#,
+        );
+    }
+
+    #[test]
+    fn source() {
+        assert_expands(
+            &STABLE,
+            "crate, source",
+            "/// docs\nmacro_rules! m { () => {}; ($($e:expr),+ $(,)?) => { [$($e),+] }; }",
+            r##"
+                #[doc = " docs"]
+                #[doc = ""]
+                #[doc = "# Definition"]
+                #[doc = ""]
+                #[doc = "```text"]
+                #[doc = "macro_rules! m {"]
+                #[doc = "    () => {};"]
+                #[doc = "    ($($e:expr),+ $(,)?) => { [$($e),+] };"]
+                #[doc = "}"]
+                #[doc = "```"]
+                macro_rules! m { () => {}; ($($e:expr),+ $(,)?) => { [$($e),+] }; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+            "##,
+        );
+    }
+
+    #[test]
+    fn samples() {
+        for (matcher, expected) in &[
+            ("() $(x)*", "()"),
+            ("$a:expr, $b:ident", "0, x"),
+            ("$($t:ty),* ; $($p:pat)|+ ; $(,)?", "; _ ;"),
+            ("[$v:vis $l:lifetime] $crate", "['a] $crate"),
+        ] {
+            assert_eq!(
+                sample(matcher.parse().unwrap()).to_string(),
+                expected.parse::<TokenStream>().unwrap().to_string(),
+            );
+        }
+    }
+
+    #[test]
+    fn stability() {
+        assert_expands(
+            &STABLE,
+            r#"crate, since = "1.4.0""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[doc = ""]
+                #[doc = "*Available since version 1.4.0.*"]
+                macro_rules! m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            "unstable",
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[doc = ""]
+                #[doc = "<div class=\"stab unstable\">This macro is unstable, and may change or be removed in any release.</div>"]
+                #[doc(hidden)]
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[doc(hidden)]
+                pub use macro_impl_HASH_m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn feature() {
+        assert_expands(
+            &STABLE,
+            r#"crate, feature = "extra""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[doc = ""]
+                #[doc = "*Requires the `extra` feature.*"]
+                #[allow(unused_macros)]
+                macro_rules! m { () => {}; }
+                #[cfg(any(feature = "extra", doc))]
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+                #[cfg(not(any(feature = "extra", doc)))]
+                macro_rules! macro_pub_feature_HASH_m {
+                    ($($tokens:tt)*) => { compile_error! { "`m!` requires the `extra` feature" } };
+                }
+                #[cfg(not(any(feature = "extra", doc)))]
+                #[doc(hidden)]
+                #[allow(deprecated)]
+                pub(crate) use macro_pub_feature_HASH_m as m;
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            r#"feature = "extra""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[doc = ""]
+                #[doc = "*Requires the `extra` feature.*"]
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[cfg(any(feature = "extra", doc))]
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+                #[cfg(not(any(feature = "extra", doc)))]
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_pub_feature_HASH_m {
+                    ($($tokens:tt)*) => { compile_error! { "`m!` requires the `extra` feature" } };
+                }
+                #[cfg(not(any(feature = "extra", doc)))]
+                #[doc(hidden)]
+                #[allow(deprecated)]
+                pub use macro_pub_feature_HASH_m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn also_export_legacy_name() {
+        assert_expands(
+            &STABLE,
+            "crate, also_export_legacy_name",
+            "macro_rules! m { () => {}; }",
+            r#"
+                macro_rules! m { () => {}; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! m { () => {}; }
+            "#,
+        );
+    }
+
+    #[test]
+    fn strategies() {
+        assert_expands(
+            &NIGHTLY,
+            r#"strategy = "use_alias""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+            "#,
+        );
+        assert_expands(
+            &NIGHTLY,
+            r#"crate, strategy = "decl_macro""#,
+            "/// Docs.\nmacro_rules! m { () => {}; ($e:expr) => { $e }; }",
+            r#"
+                #[doc = " Docs."]
+                #[rustc_macro_transparency = "semiopaque"]
+                pub(crate) macro m { () => {}, ($e:expr) => { $e }, }
+            "#,
+        );
+        assert_expands(
+            &NIGHTLY,
+            r#"strategy = "legacy_export""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[macro_export]
+                macro_rules! m { () => {}; }
+            "#,
+        );
+    }
+
+    #[test]
+    fn metadata() {
+        assert_expands(
+            &STABLE,
+            "in crate::a, metadata",
+            "macro_rules! m { () => {}; ($e:expr) => { $e }; }",
+            r#"
+                macro_rules! m { () => {}; ($e:expr) => { $e }; }
+                #[allow(unused_imports)]
+                pub(in crate::a) use m as m;
+                #[doc(hidden)]
+                pub(in crate::a) mod __macro_pub_metadata_m {
+                    pub const NAME: &str = "m";
+                    pub const ARMS: usize = 2;
+                    pub const VISIBILITY: &str = "pub(in crate::a)";
+                    pub const MANGLED: Option<&str> = None;
+                }
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            "metadata",
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+                #[doc(hidden)]
+                pub mod __macro_pub_metadata_m {
+                    pub const NAME: &str = "m";
+                    pub const ARMS: usize = 1;
+                    pub const VISIBILITY: &str = "pub";
+                    pub const MANGLED: Option<&str> = Some("macro_impl_HASH_m");
+                }
+            "#,
+        );
+    }
+
+    #[test]
+    fn debug() {
+        assert_expands(
+            &STABLE,
+            "crate, debug",
+            "macro_rules! m { ($e:expr) => { $e }; }",
+            r#"
+                macro_rules! m { ($e:expr) => { $e }; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+                const _: () = {
+                    #[deprecated(
+                        note = "`#[macro_pub]` expanded `m!` for rustc 1.41.0 (5e1a79984 2020-01-27), without nightly documentation, to: macro_rules ! m { ($e:expr) => { $e }; } #[allow (unused_imports)] pub (crate) use m as m;"
+                    )]
+                    #[allow(non_upper_case_globals)]
+                    const macro_pub_warning: () = ();
+                    let _ = macro_pub_warning;
+                };
+            "#,
+        );
+    }
+
+    #[test]
+    fn std_names() {
+        let expand = |attr: &str, item: &str| {
+            macro_pub(attr.parse().unwrap(), item.parse().unwrap(), &STABLE).to_string()
+        };
+        let message = "`#[macro_pub]` macro `vec!` has the same name as the standard library's \
+                       `vec!`, which glob imports of it shadow; consider renaming it, or telling \
+                       users to import it by name";
+        let message = format!("{:?}", message);
+        assert!(expand("warn(std_names)", "macro_rules! vec { () => {}; }").contains(&message));
+        assert!(expand(
+            "crate, warn(std_names), export_as = vec",
+            "macro_rules! v { () => {}; }"
+        )
+        .contains(&message));
+        assert!(!expand("", "macro_rules! vec { () => {}; }").contains(&message));
+        assert!(
+            !expand("warn(std_names)", "macro_rules! vec2 { () => {}; }").contains("deprecated")
+        );
+    }
+
+    #[test]
+    fn deprecated() {
+        // Parsing records the warnings for deprecated spellings, which are
+        // emitted with the others.
+        let attr: TokenStream = "warn(std_names)".parse().unwrap();
+        let mut args = Args::parse(attr.clone()).ok().unwrap();
+        args.deprecated.push((
+            "`#[macro_pub]` option `old` is deprecated; write `warn(std_names)` instead"
+                .to_string(),
+            Span::call_site(),
+        ));
+        let output = super::macro_pub(
+            attr,
+            &Ok(args),
+            "macro_rules! vec { () => {}; }".parse().unwrap(),
+            &STABLE,
+        )
+        .to_string();
+        assert!(
+            output.contains("same name as the standard library"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("option `old` is deprecated; write `warn(std_names)` instead"),
+            "{}",
+            output,
+        );
+    }
+
+    #[test]
+    fn no_alias() {
+        assert_expands(
+            &NIGHTLY,
+            "no_alias",
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m { () => {}; }
+            "#,
+        );
+        assert_expands(
+            &STABLE,
+            "crate, no_alias",
+            "macro_rules! m { () => {}; }",
+            "macro_rules! m { () => {}; }",
+        );
+    }
+
+    #[test]
+    fn track_uses() {
+        assert_expands(
+            &STABLE,
+            "track_uses",
+            "macro_rules! m { () => { 1 }; ($x:expr) => ($x); }",
+            r#"
+                #[macro_export]
+                #[doc(hidden)]
+                macro_rules! macro_impl_HASH_m {
+                    () => { $crate::__macro_pub_track_use! { "macro_impl_HASH_m" 1 } };
+                    ($x:expr) => ($crate::__macro_pub_track_use! { "macro_impl_HASH_m" $x });
+                }
+                #[doc(inline)]
+                pub use macro_impl_HASH_m as m;
+            "#,
+        );
+    }
+
+    #[test]
+    fn nested_macros() {
+        let item = "macro_rules! m { ($name:ident) => { \
+            #[doc = \"Inner.\"] #[macro_export] macro_rules! $name { () => {}; } \
+            mod inner { #[macro_export(local_inner_macros)] macro_rules! other { () => {}; } } \
+            macro_rules! local { () => {}; } \
+        }; }";
+        let output = macro_pub(TokenStream::new(), item.parse().unwrap(), &STABLE).to_string();
+        for nested in &["$name", "other"] {
+            let message = format!(
+                "`#[macro_pub]` macro `m!` defines `#[macro_export]` macro `{}!`, which is \
+                 exported from the crates using `m!`, where it can't be used by path; consider \
+                 defining it with `#[macro_pub]` here, and using it through `$crate`",
+                nested,
+            );
+            assert!(output.contains(&format!("{:?}", message)), "{}", output);
+        }
+        assert!(!output.contains("`local!`"), "{}", output);
+
+        let output = macro_pub("crate".parse().unwrap(), item.parse().unwrap(), &STABLE);
+        assert!(!output.to_string().contains("deprecated"), "{}", output);
+    }
+
+    #[test]
+    fn lint() {
+        let item = r#"macro_rules! m {
+            () => { env!("CARGO_PKG_NAME") };
+            ($x:ident) => { ::core::option_env!(stringify!($x)) };
+        }"#;
+        let output = macro_pub("lint".parse().unwrap(), item.parse().unwrap(), &STABLE).to_string();
+        for env in &["env", "option_env"] {
+            let message = format!(
+                "`#[macro_pub]` macro `m!` uses `{}!`, which reads the environment compiling the \
+                 crate using the macro, not this one; to read this crate's, use it in a `pub \
+                 const` here, and use that through `$crate`",
+                env,
+            );
+            assert!(output.contains(&format!("{:?}", message)), "{}", output);
+        }
+
+        let output = macro_pub(TokenStream::new(), item.parse().unwrap(), &STABLE);
+        assert!(!output.to_string().contains("deprecated"), "{}", output);
+
+        let item = r##"macro_rules! m {
+            () => { include_str!("data.txt") };
+            (bytes) => { include_bytes!(r#"../data.bin"#) };
+            (absolute) => { include!("/usr/share/data.rs") };
+            (expr) => { include!(concat!("/", "data.rs")) };
+        }"##;
+        let output = macro_pub("lint".parse().unwrap(), item.parse().unwrap(), &STABLE).to_string();
+        for include in &["include_str", "include_bytes"] {
+            let message = format!(
+                "`#[macro_pub]` macro `m!` uses `{0}!` with a relative path, which is relative \
+                 to the file using the macro, not this one; `{0}!` it in a `pub const` here \
+                 instead, and use that through `$crate`",
+                include,
+            );
+            assert!(output.contains(&format!("{:?}", message)), "{}", output);
+        }
+        assert!(!output.contains("`include!`"), "{}", output);
+    }
+
+    #[test]
+    fn no_std() {
+        let item = r#"macro_rules! m {
+            () => { ::std::mem::drop(std::vec::Vec::<u8>::new()) };
+            ($x:expr) => { let _: &dyn std::io::Write = $x; $crate::std::fs::read(<std::string::String>::new()) };
+        }"#;
+        let output =
+            macro_pub("no_std".parse().unwrap(), item.parse().unwrap(), &STABLE).to_string();
+        for (path, instead) in &[
+            ("std::mem", "use `::core::mem` instead"),
+            (
+                "std::vec",
+                "use `::alloc::vec` instead, which needs `extern crate alloc;`",
+            ),
+            ("std::io", "it has no equivalent in `core` or `alloc`"),
+            (
+                "std::string",
+                "use `::alloc::string` instead, which needs `extern crate alloc;`",
+            ),
+        ] {
+            let message = format!(
+                "`#[macro_pub]` macro `m!` uses `{}`, which `#![no_std]` crates using the macro \
+                 don't have; {}",
+                path, instead,
+            );
+            assert!(output.contains(&format!("{:?}", message)), "{}", output);
+        }
+        assert!(!output.contains("`std::fs`"), "{}", output);
+    }
+
+    #[test]
+    fn unsupported_strategy() {
+        assert_expands(
+            &STABLE,
+            r#"strategy = "decl_macro""#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                macro_rules! m { () => {}; }
+                compile_error! {
+                    "`#[macro_pub]` strategy `decl_macro` needs `#![feature(decl_macro, rustc_attrs)]`, which isn't available on the detected rustc 1.41.0 (5e1a79984 2020-01-27)"
+                }
+            "#,
+        );
+    }
+
+    #[test]
+    fn unsafe_macro() {
+        assert_expands(
+            &STABLE,
+            "crate, unsafe_macro",
+            "/// Reads `$p`.\nmacro_rules! m { ($p:expr) => { unsafe { *$p } }; }",
+            r##"
+                #[doc = "<div class=\"warning\">This macro expands to <code>unsafe</code> code.</div>"]
+                #[doc = ""]
+                #[doc = " Reads `$p`."]
+                #[doc = ""]
+                #[doc = "# Safety"]
+                #[doc = ""]
+                #[doc = "This macro expands to `unsafe` code, so uses of it must uphold the requirements documented above."]
+                macro_rules! m { ($p:expr) => { unsafe { *$p } }; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+            "##,
+        );
+        assert_expands(
+            &STABLE,
+            "crate, unsafe_macro",
+            "/// Reads `$p`.\n///\n/// # Safety\n///\n/// `$p` must be valid.\n\
+             macro_rules! m { ($p:expr) => { unsafe { *$p } }; }",
+            r##"
+                #[doc = "<div class=\"warning\">This macro expands to <code>unsafe</code> code.</div>"]
+                #[doc = ""]
+                #[doc = " Reads `$p`."]
+                #[doc = ""]
+                #[doc = " # Safety"]
+                #[doc = ""]
+                #[doc = " `$p` must be valid."]
+                macro_rules! m { ($p:expr) => { unsafe { *$p } }; }
+                #[allow(unused_imports)]
+                pub(crate) use m as m;
+            "##,
+        );
+        let output = macro_pub(
+            "crate, unsafe_macro".parse().unwrap(),
+            "macro_rules! m { (unsafe) => {}; }".parse().unwrap(),
+            &STABLE,
+        );
+        assert!(output
+            .to_string()
+            .contains("no arm of the macro expands to `unsafe` code"));
+    }
+
+    #[test]
+    fn pointless_options() {
+        assert_expands(
+            &STABLE,
+            r#"strategy = "legacy_export", also_export_legacy_name"#,
+            "macro_rules! m { () => {}; }",
+            r#"
+                #[macro_export]
+                macro_rules! m { () => {}; }
+                const _: () = {
+                    #[deprecated(
+                        note = "`#[macro_pub]` option `also_export_legacy_name` has no effect, since strategy `legacy_export` already exports `m` at the crate root"
+                    )]
+                    #[allow(non_upper_case_globals)]
+                    const macro_pub_warning: () = ();
+                    let _ = macro_pub_warning;
+                };
+            "#,
+        );
+        for (attr, message) in &[
+            (
+                r#"friends("other")"#,
+                "`#[macro_pub]` option `friends` has no effect, since the macro is already `pub`",
+            ),
+            (
+                r#"strategy = "use_alias", selfcheck"#,
+                "`#[macro_pub]` option `selfcheck` has no effect, since the macro is never documented as a `pub macro`",
+            ),
+            (
+                "crate, textual",
+                "`#[macro_pub]` option `textual` has no effect, since restricted macros are already defined under their own name",
+            ),
+            (
+                "no_textual",
+                "`#[macro_pub]` option `no_textual` has no effect, since only restricted `macro_rules!` macros are defined under their own name",
+            ),
+            (
+                "crate, doc_required",
+                "`#[macro_pub]` option `doc_required` has no effect, since only world-public macros need documentation",
+            ),
+            (
+                r#"crate, salt = "v2""#,
+                "`#[macro_pub]` option `salt` has no effect, since the macro isn't exported under a mangled name",
+            ),
+            (
+                "crate, hash_docs",
+                "`#[macro_pub]` option `hash_docs` has no effect, since the macro isn't exported under a mangled name",
+            ),
+            (
+                "crate, mangle = false",
+                "`#[macro_pub]` option `mangle` has no effect, since the macro isn't exported under a mangled name",
+            ),
+            (
+                r#"strategy = "legacy_export", shadowable"#,
+                "`#[macro_pub]` option `shadowable` has no effect, since the macro isn't re-exported under its name",
+            ),
+            (
+                "crate, internal_rules",
+                "`#[macro_pub]` option `internal_rules` has no effect, since no arm's matcher starts with `@`",
+            ),
+            (
+                "crate, hide_arms",
+                "`#[macro_pub]` option `hide_arms` has no effect, since only world-public macros documented as a `pub macro` can hide their arms",
+            ),
+            (
+                "doctest",
+                "`#[macro_pub]` option `doctest` has no effect, since the macro is already `pub`",
+            ),
+            (
+                "crate, hidden, unstable",
+                "`#[macro_pub]` option `hidden` has no effect, since `unstable` already hides the macro",
+            ),
+            (
+                "crate, lint",
+                "`#[macro_pub]` option `lint` has no effect, since the macro can't be used in other crates",
+            ),
+            (
+                "crate, no_std",
+                "`#[macro_pub]` option `no_std` has no effect, since the macro can't be used in other crates",
+            ),
+            (
+                "crate, track_uses",
+                "`#[macro_pub]` option `track_uses` has no effect, since only world-public macros exported under a mangled name are tracked",
+            ),
+        ] {
+            let output = macro_pub(
+                attr.parse().unwrap(),
+                "macro_rules! m { () => {}; }".parse().unwrap(),
+                &STABLE,
+            );
+            assert!(output.to_string().contains(&format!("{:?}", message)), "{}", output);
+        }
+    }
+
+    #[test]
+    fn not_macro_rules() {
+        assert_expands(
+            &STABLE,
+            "crate",
+            "fn f() {}",
+            r#"
+                fn f() {}
+                compile_error! { "`#[macro_pub]` must be used on a `macro_rules!` macro" }
+            "#,
+        );
+    }
+
+    #[test]
+    fn not_visibility() {
+        assert_expands(
+            &STABLE,
+            "pub",
+            "macro_rules! m { () => {}; }",
+            r#"
+                macro_rules! m { () => {}; }
+                compile_error! {
+                    "`#[macro_pub]` expects a visibility restriction like `crate`, `super`, or `in path`"
+                }
+            "#,
+        );
+    }
+}
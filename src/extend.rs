@@ -0,0 +1,207 @@
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use crate::expand::unraw;
+
+/// Expands `#[macro_pub(extend)] item`, a part of a macro named `name` whose
+/// arms are mounted elsewhere.
+///
+/// The part is a hidden macro, re-exported under `name` as visible as
+/// `restriction`, which takes `$` and the rest of the parts to visit, and the
+/// `#[macro_pub] macro_rules! name` the arms are for, followed by the arms so
+/// far. It adds its arms to them, and passes them on to the next part, or
+/// completes the definition if it's the last:
+///
+/// ```text
+/// a::name! { $ [(b::name)] (#[macro_pub] macro_rules! name) arms }
+/// ```
+///
+/// Its own arms have every `$` replaced by the one passed in, so they're
+/// copied, rather than interpreted, by the part.
+pub(crate) fn part(
+    hash: u128,
+    name: &Ident,
+    restriction: TokenStream,
+    item: TokenStream,
+) -> TokenStream {
+    let (_, arms, rest) = match split(item) {
+        Some(split) => split,
+        None => return TokenStream::new(),
+    };
+    let part = Ident::new(
+        &format!("__macro_pub_extend_{}_{}", hash, unraw(&name.to_string())),
+        Span::call_site().located_at(name.span()),
+    );
+    let dollar = Ident::new("__macro_pub_dollar", Span::call_site());
+    let own = escape_dollars(arms.stream(), &dollar);
+    let restriction = if restriction.is_empty() {
+        "crate".parse().unwrap()
+    } else {
+        restriction
+    };
+    let body = format!(
+        "(${d}:tt [($($next:tt)*) $($rest:tt)*] ($($mount:tt)*) $($arms:tt)*) => {{ \
+             $($next)*! {{ ${d} [$($rest)*] ($($mount)*) $($arms)* __macro_pub_own }} \
+         }}; \
+         (${d}:tt [] ($($mount:tt)*) $($arms:tt)*) => {{ \
+             $($mount)* {{ $($arms)* __macro_pub_own }} \
+         }};",
+        d = dollar,
+    )
+    .parse::<TokenStream>()
+    .unwrap();
+
+    let mut output = "#[doc(hidden)] macro_rules!"
+        .parse::<TokenStream>()
+        .unwrap();
+    output.extend(vec![
+        TokenTree::Ident(part.clone()),
+        TokenTree::Group(Group::new(Delimiter::Brace, insert_own(body, &own))),
+    ]);
+    output.extend("#[doc(hidden)] pub".parse::<TokenStream>().unwrap());
+    output.extend(vec![
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, restriction)),
+        TokenTree::Ident(Ident::new("use", Span::call_site())),
+        TokenTree::Ident(part),
+        TokenTree::Ident(Ident::new("as", Span::call_site())),
+        TokenTree::Ident(name.clone()),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+    output.extend(rest);
+    output
+}
+
+/// Expands `#[macro_pub(attr)] item`, where `attr` has `extend_from` with
+/// the `parts`, by passing `item` with the `extend_from` option removed to the
+/// first part, which passes it through the rest.
+pub(crate) fn mount(attr: TokenStream, item: TokenStream, parts: &[TokenStream]) -> TokenStream {
+    let (definition, arms, rest) = match split(item) {
+        Some(split) => split,
+        None => return TokenStream::new(),
+    };
+
+    let mut macro_pub = "::macro_pub::macro_pub".parse::<TokenStream>().unwrap();
+    macro_pub.extend(vec![TokenTree::Group(Group::new(
+        Delimiter::Parenthesis,
+        without_extend_from(attr),
+    ))]);
+    let mut mount = vec![
+        TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Bracket, macro_pub)),
+    ]
+    .into_iter()
+    .collect::<TokenStream>();
+    mount.extend(definition);
+
+    let mut next = TokenStream::new();
+    for part in &parts[1..] {
+        next.extend(vec![TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            part.clone(),
+        ))]);
+    }
+    let mut input = vec![
+        TokenTree::Punct(Punct::new('$', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Bracket, next)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, mount)),
+    ]
+    .into_iter()
+    .collect::<TokenStream>();
+    input.extend(arms.stream());
+
+    let mut output = parts[0].clone();
+    output.extend(vec![
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Brace, input)),
+    ]);
+    output.extend(rest);
+    output
+}
+
+/// Splits the `macro_rules!` macro `item` into everything before its arms,
+/// the arms, and anything after it.
+fn split(item: TokenStream) -> Option<(TokenStream, Group, TokenStream)> {
+    let mut definition = TokenStream::new();
+    let mut tokens = item.into_iter();
+    // Tokens since the `!` of `macro_rules!`, which is followed by the name.
+    let mut after_bang = None;
+    loop {
+        let tt = tokens.next()?;
+        match tt {
+            TokenTree::Group(ref arms)
+                if after_bang == Some(1) && arms.delimiter() == Delimiter::Brace =>
+            {
+                return Some((definition, arms.clone(), tokens.collect()));
+            }
+            TokenTree::Punct(ref punct) if punct.as_char() == '!' => after_bang = Some(0),
+            _ => after_bang = after_bang.map(|after| after + 1),
+        }
+        definition.extend(Some(tt));
+    }
+}
+
+/// `tokens` with each `__macro_pub_own` replaced by `own`.
+fn insert_own(tokens: TokenStream, own: &TokenStream) -> TokenStream {
+    let mut inserted = TokenStream::new();
+    for tt in tokens {
+        match tt {
+            TokenTree::Ident(ref ident) if ident == "__macro_pub_own" => {
+                inserted.extend(own.clone())
+            }
+            TokenTree::Group(group) => inserted.extend(Some(TokenTree::Group(Group::new(
+                group.delimiter(),
+                insert_own(group.stream(), own),
+            )))),
+            tt => inserted.extend(Some(tt)),
+        }
+    }
+    inserted
+}
+
+/// `tokens` with each `$` followed by `dollar`, so that a macro with the
+/// metavariable `$dollar` bound to `$` transcribes them as they were.
+fn escape_dollars(tokens: TokenStream, dollar: &Ident) -> TokenStream {
+    let mut escaped = TokenStream::new();
+    for tt in tokens {
+        match tt {
+            TokenTree::Punct(ref punct) if punct.as_char() == '$' => {
+                escaped.extend(vec![tt.clone(), TokenTree::Ident(dollar.clone())]);
+            }
+            TokenTree::Group(group) => {
+                let mut escaped_group =
+                    Group::new(group.delimiter(), escape_dollars(group.stream(), dollar));
+                escaped_group.set_span(group.span());
+                escaped.extend(Some(TokenTree::Group(escaped_group)));
+            }
+            tt => escaped.extend(Some(tt)),
+        }
+    }
+    escaped
+}
+
+/// `attr` without its `extend_from(...)` option.
+fn without_extend_from(attr: TokenStream) -> TokenStream {
+    let mut segments = vec![TokenStream::new()];
+    for tt in attr {
+        match tt {
+            TokenTree::Punct(ref punct) if punct.as_char() == ',' => {
+                segments.push(TokenStream::new())
+            }
+            tt => segments.last_mut().unwrap().extend(Some(tt)),
+        }
+    }
+    let mut kept = TokenStream::new();
+    for segment in segments {
+        let is_extend_from = match segment.clone().into_iter().next() {
+            Some(TokenTree::Ident(ref option)) => option == "extend_from",
+            _ => false,
+        };
+        if segment.is_empty() || is_extend_from {
+            continue;
+        }
+        if !kept.is_empty() {
+            kept.extend(",".parse::<TokenStream>().unwrap());
+        }
+        kept.extend(segment);
+    }
+    kept
+}
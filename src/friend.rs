@@ -0,0 +1,163 @@
+use proc_macro2::{Ident, TokenStream, TokenTree};
+
+use crate::expand::unraw;
+
+/// Expands `friend! { path::to::name }`, in the crate being compiled, named
+/// `krate`.
+///
+/// The path names a macro defined with `#[macro_pub(friends(...))]` listing
+/// this crate. It's imported as a `pub(crate)` macro `name`, which forwards
+/// to the hidden export with this crate's guard:
+///
+/// ```text
+/// macro_rules! name {
+///     ($($tokens:tt)*) => {
+///         path::to::__macro_pub_friends_name! { __macro_pub_friend_krate $($tokens)* }
+///     };
+/// }
+/// pub(crate) use name as name;
+/// ```
+pub(crate) fn friend(input: TokenStream, krate: Option<String>) -> TokenStream {
+    match krate {
+        Some(krate) => forward(input, "friend", &format!("friend_{}", krate)),
+        None => error("`macro_pub::friend!` needs Cargo to set `CARGO_CRATE_NAME`"),
+    }
+}
+
+/// Expands `package! { path::to::name }`, in a target of the package named
+/// `package`.
+///
+/// The path names a macro defined with `#[macro_pub(package)]` in the
+/// package's library, and it's imported like with [`friend`], with the
+/// package's guard, `__macro_pub_package_package`.
+pub(crate) fn package(input: TokenStream, package: Option<String>) -> TokenStream {
+    match package {
+        Some(package) => forward(input, "package", &format!("package_{}", package)),
+        None => error("`macro_pub::package!` needs Cargo to set `CARGO_PKG_NAME`"),
+    }
+}
+
+/// Imports the macro at the path `input` as a `pub(crate)` macro forwarding
+/// to its hidden export with the guard `__macro_pub_{guard}`, reporting
+/// errors as `macro_pub::{importer}!`.
+fn forward(input: TokenStream, importer: &str, guard: &str) -> TokenStream {
+    let guard = guard.replace('-', "_");
+    let expected = format!("`macro_pub::{}!` expects a path to a macro", importer);
+
+    let mut path = input.into_iter().collect::<Vec<_>>();
+    if let Some(TokenTree::Punct(punct)) = path.last() {
+        if punct.as_char() == ';' {
+            path.pop();
+        }
+    }
+    let name = match path.pop() {
+        Some(TokenTree::Ident(name)) => name,
+        _ => return error(&expected),
+    };
+    let is_prefix = match path.last() {
+        Some(TokenTree::Punct(punct)) => punct.as_char() == ':',
+        _ => false,
+    };
+    if !is_prefix {
+        return error(&expected);
+    }
+
+    let mut forwarded = path.into_iter().collect::<TokenStream>();
+    forwarded.extend(Some(TokenTree::Ident(Ident::new(
+        &format!("__macro_pub_friends_{}", unraw(&name.to_string())),
+        name.span(),
+    ))));
+    format!(
+        "macro_rules! {name} {{
+            ($($tokens:tt)*) => {{
+                {forwarded}! {{ __macro_pub_{guard} $($tokens)* }}
+            }};
+        }}
+        pub(crate) use {name} as {name};",
+        name = name,
+        forwarded = forwarded,
+        guard = guard,
+    )
+    .parse()
+    .unwrap()
+}
+
+fn error(message: &str) -> TokenStream {
+    format!("compile_error! {{ {:?} }}", message)
+        .parse()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn assert_expands(input: &str, expected: &str) {
+        assert_eq!(
+            friend(input.parse().unwrap(), Some("my-crate".to_string())).to_string(),
+            expected.parse::<TokenStream>().unwrap().to_string(),
+        );
+    }
+
+    #[test]
+    fn imports() {
+        assert_expands(
+            "definer::shared::shared;",
+            r#"
+                macro_rules! shared {
+                    ($($tokens:tt)*) => {
+                        definer::shared::__macro_pub_friends_shared! {
+                            __macro_pub_friend_my_crate $($tokens)*
+                        }
+                    };
+                }
+                pub(crate) use shared as shared;
+            "#,
+        );
+    }
+
+    #[test]
+    fn packages() {
+        assert_eq!(
+            package("crate::m".parse().unwrap(), Some("my-package".to_string())).to_string(),
+            r#"
+                macro_rules! m {
+                    ($($tokens:tt)*) => {
+                        crate::__macro_pub_friends_m! {
+                            __macro_pub_package_my_package $($tokens)*
+                        }
+                    };
+                }
+                pub(crate) use m as m;
+            "#
+            .parse::<TokenStream>()
+            .unwrap()
+            .to_string(),
+        );
+        assert_eq!(
+            package("m".parse().unwrap(), Some("my-package".to_string())).to_string(),
+            r#"compile_error! { "`macro_pub::package!` expects a path to a macro" }"#
+                .parse::<TokenStream>()
+                .unwrap()
+                .to_string(),
+        );
+    }
+
+    #[test]
+    fn errors() {
+        for input in &["", "shared", "definer::", "definer::shared!"] {
+            assert_expands(
+                input,
+                r#"compile_error! { "`macro_pub::friend!` expects a path to a macro" }"#,
+            );
+        }
+        assert_eq!(
+            friend("definer::shared".parse().unwrap(), None).to_string(),
+            r#"compile_error! { "`macro_pub::friend!` needs Cargo to set `CARGO_CRATE_NAME`" }"#
+                .parse::<TokenStream>()
+                .unwrap()
+                .to_string(),
+        );
+    }
+}
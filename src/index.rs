@@ -0,0 +1,252 @@
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Expands `index! { input }`.
+///
+/// The input is a module whose body lists paths to macros, each followed by
+/// `;`, and nested `mod`s grouping more of them:
+///
+/// ```text
+/// /// All of the macros.
+/// pub mod macros {
+///     crate::a::first;
+///     /// Some related macros.
+///     mod related {
+///         crate::b::{second, third};
+///     }
+/// }
+/// ```
+///
+/// Each path becomes an inlined `pub use`, and each group a `pub mod`.
+pub(crate) fn index(input: TokenStream) -> TokenStream {
+    let mut tokens = input.into_iter().peekable();
+    let mut output = attrs(&mut tokens);
+    loop {
+        match tokens.next() {
+            Some(TokenTree::Ident(ident)) if ident == "mod" => {
+                output.extend(Some(TokenTree::Ident(ident)));
+                break;
+            }
+            Some(tt) => output.extend(Some(tt)),
+            None => {
+                return error("`macro_pub::index!` expects a module like `pub mod macros { ... }`")
+            }
+        }
+    }
+    match module(&mut tokens, "index") {
+        Ok(module) => output.extend(module),
+        Err(message) => return error(&message),
+    }
+    if tokens.next().is_some() {
+        return error("`macro_pub::index!` expects a single module");
+    }
+    output
+}
+
+/// Expands `reexport! { entries }`.
+///
+/// The entries are those of an `index!` module, usually paths to macros in
+/// another crate:
+///
+/// ```text
+/// other_crate::first;
+/// /// Some related macros.
+/// mod related {
+///     other_crate::b::{second, third};
+/// }
+/// ```
+///
+/// Each path becomes an inlined `pub use` in the current module, and each
+/// group a `pub mod`.
+pub(crate) fn reexport(input: TokenStream) -> TokenStream {
+    match entries(&mut input.into_iter().peekable(), "reexport") {
+        Ok(entries) => entries,
+        Err(message) => error(&message),
+    }
+}
+
+pub(crate) type Tokens = std::iter::Peekable<proc_macro2::token_stream::IntoIter>;
+
+/// Takes leading `#[attribute]`s from `tokens`.
+pub(crate) fn attrs(tokens: &mut Tokens) -> TokenStream {
+    let mut attrs = TokenStream::new();
+    loop {
+        match tokens.peek() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '#' => {}
+            _ => return attrs,
+        }
+        attrs.extend(tokens.next());
+        attrs.extend(tokens.next());
+    }
+}
+
+/// Expands `name { entries }`, after the `mod` keyword.
+fn module(tokens: &mut Tokens, macro_name: &str) -> Result<TokenStream, String> {
+    let (name, body) = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(name)), Some(TokenTree::Group(body)))
+            if body.delimiter() == Delimiter::Brace =>
+        {
+            (name, body)
+        }
+        _ => {
+            return Err(format!(
+                "`macro_pub::{}!` expects a module like `pub mod macros {{ ... }}`",
+                macro_name,
+            ))
+        }
+    };
+
+    let contents = entries(&mut body.stream().into_iter().peekable(), macro_name)?;
+    let body = Group::new(Delimiter::Brace, contents);
+    Ok(vec![TokenTree::Ident(name), TokenTree::Group(body)]
+        .into_iter()
+        .collect())
+}
+
+/// Expands the paths and `mod`s of `entries`.
+fn entries(entries: &mut Tokens, macro_name: &str) -> Result<TokenStream, String> {
+    let mut contents = TokenStream::new();
+    while entries.peek().is_some() {
+        contents.extend(attrs(entries));
+        match entries.peek() {
+            Some(TokenTree::Ident(ident)) if ident == "mod" => {
+                contents.extend(vec![
+                    TokenTree::Ident(Ident::new("pub", Span::call_site())),
+                    entries.next().unwrap(),
+                ]);
+                contents.extend(module(entries, macro_name)?);
+                continue;
+            }
+            Some(_) => {}
+            None => {
+                return Err(format!(
+                    "`macro_pub::{}!` expects a path after attributes",
+                    macro_name,
+                ))
+            }
+        }
+
+        let mut path = TokenStream::new();
+        loop {
+            match entries.next() {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == ';' => break,
+                Some(tt) => path.extend(Some(tt)),
+                None => {
+                    return Err(format!(
+                        "`macro_pub::{}!` expects `;` after each path",
+                        macro_name,
+                    ))
+                }
+            }
+        }
+        contents.extend("#[doc(inline)] pub use".parse::<TokenStream>().unwrap());
+        contents.extend(path);
+        contents.extend(Some(TokenTree::Punct(Punct::new(';', Spacing::Alone))));
+    }
+    Ok(contents)
+}
+
+fn error(message: &str) -> TokenStream {
+    format!("compile_error! {{ {:?} }}", message)
+        .parse()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn assert_expands(input: &str, expected: &str) {
+        assert_eq!(
+            index(input.parse().unwrap()).to_string(),
+            expected.parse::<TokenStream>().unwrap().to_string(),
+        );
+    }
+
+    #[test]
+    fn groups() {
+        assert_expands(
+            r#"
+                /// Macros.
+                pub mod macros {
+                    crate::a::first;
+                    /// Related.
+                    mod related {
+                        #[cfg(all())]
+                        crate::b::{second, third as fourth};
+                    }
+                }
+            "#,
+            r#"
+                #[doc = " Macros."]
+                pub mod macros {
+                    #[doc(inline)]
+                    pub use crate::a::first;
+                    #[doc = " Related."]
+                    pub mod related {
+                        #[cfg(all())]
+                        #[doc(inline)]
+                        pub use crate::b::{second, third as fourth};
+                    }
+                }
+            "#,
+        );
+    }
+
+    #[test]
+    fn reexports() {
+        assert_eq!(
+            reexport(
+                r#"
+                    other::first;
+                    /// Related.
+                    mod related {
+                        other::b::{second, third};
+                    }
+                "#
+                .parse()
+                .unwrap(),
+            )
+            .to_string(),
+            r#"
+                #[doc(inline)]
+                pub use other::first;
+                #[doc = " Related."]
+                pub mod related {
+                    #[doc(inline)]
+                    pub use other::b::{second, third};
+                }
+            "#
+            .parse::<TokenStream>()
+            .unwrap()
+            .to_string(),
+        );
+        assert_eq!(
+            reexport("other::first".parse().unwrap()).to_string(),
+            r#"compile_error! { "`macro_pub::reexport!` expects `;` after each path" }"#
+                .parse::<TokenStream>()
+                .unwrap()
+                .to_string(),
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_expands(
+            "macros {}",
+            r#"compile_error! { "`macro_pub::index!` expects a module like `pub mod macros { ... }`" }"#,
+        );
+        assert_expands(
+            "mod macros {} mod more {}",
+            r#"compile_error! { "`macro_pub::index!` expects a single module" }"#,
+        );
+        assert_expands(
+            "mod macros { crate::a }",
+            r#"compile_error! { "`macro_pub::index!` expects `;` after each path" }"#,
+        );
+        assert_expands(
+            "mod macros { mod group; }",
+            r#"compile_error! { "`macro_pub::index!` expects a module like `pub mod macros { ... }`" }"#,
+        );
+    }
+}
@@ -28,7 +28,9 @@
 //! ```
 //!
 //! The hash is the XXH3 hash of the annotated item's `TokenStream`, and is
-//! included to prevent name conflicts in the macro namespace.
+//! included to prevent name conflicts in the macro namespace. Doc comments are
+//! left out of it, and whitespace isn't part of the tokens, so editing the
+//! documentation or reformatting the macro doesn't change the name.
 //!
 //! If you do not specify a `pub(in path)` restriction, you instead get a
 //! world-visible macro:
@@ -39,28 +41,59 @@
 //! macro_rules! macro_impl_279137529572831871236407390024221977230_my_macro {
 //!     () => {};
 //! }
+//! #[doc(inline)]
 //! pub use macro_impl_279137529572831871236407390024221977230_my_macro
 //!     as my_macro;
 //! ```
 //!
+//! A world-public macro that invokes itself by name, as `my_macro!(...)`, would
+//! no longer find itself where it used to under the mangled name. If one of its
+//! arms names it by path, as `$crate::path::to::my_macro!(...)`, or the
+//! `expect_path` option below gives the path as `crate::path::to::my_macro`,
+//! those invocations are rewritten to that path, which works anywhere.
+//! Otherwise, it also gets the copy the `textual` option below defines, so
+//! those invocations resolve within your crate as they did before, but not in
+//! other crates. Invocations by `$crate::my_macro!`, which assumed
+//! `#[macro_export]` put the macro at the crate root, are also rewritten to the
+//! path of the module it's defined in, if it's known.
+//!
+//! The mangled name is hidden from documentation, but can't be marked
+//! `#[deprecated]` to steer anyone who finds it to the public path: only
+//! `#[macro_export]` macros can be re-exported to other crates, so the re-export
+//! is of the same macro, and would be deprecated along with it. Use the
+//! `MACRO_PUB_LOCKFILE` described below to keep the name from changing by
+//! accident, or the `salt` option to change it on purpose.
+//!
+//! Other crates can still invoke the mangled name directly, and it can't be
+//! sealed with a guard token that only a wrapper macro in your crate supplies.
+//! The wrapper would have to name the mangled macro by its path from the crate
+//! root, and within the crate defining it, rustc rejects such paths to
+//! `#[macro_export]` macros that a macro expanded to, with a lint an expansion
+//! can't allow. The wrapper would be unusable in the one crate it was for.
+//!
 //! # Documenting public macros
 //!
-//! Unfortunately, `#[doc(hidden)]` on the actual macro implementation hides
-//! any documentation attatched to it, `#[doc(inline)]`ing the `use` juts makes
-//! it hidden as well, and you can't attach documentation. Thus, on stable, your
-//! macro will be included in the documentation just as a re-export:
+//! `#[doc(hidden)]` keeps the mangled implementation out of the documentation,
+//! and the `#[doc(inline)]` re-export lets rustdoc document it as a macro named
+//! `my_macro`, with its documentation, where it is re-exported. That needs a
+//! rustdoc that inlines re-exports of hidden items, as Rust 1.95's does; this
+//! crate's tests check for it on the toolchain running them. Older versions
+//! refuse, and show nothing more than the re-export line.
 //!
-//! ```
-//! # pub struct macro_impl_279137529572831871236407390024221977230_my_macro;
-//! pub use macro_impl_279137529572831871236407390024221977230_my_macro
-//!     as my_macro;
-//! ```
+//! The macro isn't documented through a `#[cfg(doc)]` `macro_rules!` copy under
+//! its public name instead, which any rustdoc would show: only
+//! `#[macro_export]` gives a `macro_rules!` macro a path, and always at the
+//! crate root. The copy would be documented there, rather than where the macro
+//! is re-exported, and copies of macros of the same name in different modules
+//! would collide.
 //!
-//! and `macro_impl_279137529572831871236407390024221977230_my_macro` will not
-//! be documented.
+//! The re-export directly follows the definition, since `use` only finds
+//! `macro_rules!` macros defined before it, and rustdoc lists re-exports in
+//! source order, so move the macro to move where its re-export is listed. The
+//! `inline`, `no_inline`, and `hidden` options below choose its doc attributes.
 //!
-//! If you are on nightly, however, we can take advantage of nightly features
-//! in order to document the macro. In order to document your crate on nightly,
+//! On nightly, we can instead take advantage of nightly features to document
+//! the macro directly. In order to document your crate on nightly,
 //! `#[macro_pub]` requires `#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]`
 //! and instead emits
 //!
@@ -77,7 +110,17 @@
 //!
 //! This uses the unstable "macros 2.0" to define a macro with the legacy
 //! `macro_rules!` hygeine rules that obeys normal scoping rules and is
-//! documented cleanly by rustdoc.
+//! documented cleanly by rustdoc. It expands exactly like the `macro_rules!`
+//! macro, so doctests behave the same whichever definition they are run against.
+//!
+//! Restricted macros get the same treatment, with a `pub(restriction) macro`
+//! alongside a hidden `macro_rules!` for textual scoping, so they are documented
+//! cleanly with `--document-private-items`, too.
+//!
+//! Attributes on the macro are copied onto both definitions, except for the
+//! nightly `#[allow_internal_unstable]` and `#[allow_internal_unsafe]`, which are
+//! only put on the definitions that can be used, including the copies that
+//! options like `export_as` define.
 //!
 //! `macro_pub` automatically sniffs the rustc you're using to compile and
 //! determines if it can use decl_macro and rustc_attrs in this way. When these
@@ -87,6 +130,497 @@
 //! almost exactly what this crate does), `macro_pub` will be updated to take
 //! advantage of that on compatible rustc versions.
 //!
+//! When building on [docs.rs], which always uses a nightly toolchain, the
+//! documented expansion is used automatically, so the crate attribute above is
+//! all you need for your macros to be documented there.
+//!
+//! To get the same output deterministically, without relying on detection,
+//! enable the `nightly-docs` feature when documenting, e.g. in your docs.rs
+//! metadata:
+//!
+//! ```toml
+//! [package.metadata.docs.rs]
+//! features = ["macro_pub/nightly-docs"]
+//! ```
+//!
+//! This only changes `cfg(doc)` builds, which then require a nightly toolchain.
+//! Other toolchains ignore it, unless `RUSTC_BOOTSTRAP` is set, since even the
+//! `cfg(doc)` copy of a macro would warn there that `macro` is experimental.
+//!
+//! Rustdoc's JSON output, which also needs a nightly toolchain, likewise lists
+//! macros under their public paths only.
+//!
+//! [docs.rs]: https://docs.rs
+//!
+//! # Options
+//!
+//! After the optional restriction, `#[macro_pub]` accepts comma-separated
+//! options, e.g. `#[macro_pub(crate, signatures)]`:
+//!
+//! - `vis = pub(...)`: give the visibility restriction as an option, anywhere in
+//!   the list, e.g. `#[macro_pub(signatures, vis = pub(crate))]`. `vis = pub` is
+//!   world-public, like giving no restriction.
+//! - `rename = "name"`: define the macro as `name`, as if it were written
+//!   `macro_rules! name`. Its invocations of itself in its arms, like `old!(...)`
+//!   or `$crate::path::old!(...)`, are renamed too, but its documentation isn't.
+//! - `signatures`: add a "Signatures" section to the macro's documentation,
+//!   listing how each arm is invoked.
+//! - `source`: add a "Definition" section to the macro's documentation, showing
+//!   its `macro_rules!` definition as written, so readers can see the full arms
+//!   even where rustdoc only shows the re-export.
+//! - `hide_arms`: document a world-public macro on nightly as taking
+//!   `($($args:tt)*)`, rather than listing its arms, for macros whose arms are
+//!   mostly internal bookkeeping. The documented macro forwards to the real one,
+//!   so doctests still run against the same arms. To leave out only some arms,
+//!   such as `(@internal ...)` helpers, put `#[doc(hidden)]` on each of them
+//!   instead; they're also left out of `signatures`.
+//! - `internal_rules`: move the arms whose matchers start with `@`, the usual
+//!   convention for internal rules, to a hidden helper macro next to the macro,
+//!   exported wherever the macro is. Invocations of the macro starting with `@`,
+//!   by name or through `$crate::path::to::name!`, become invocations of the
+//!   helper, so the macro's documentation and `signatures` only show its public
+//!   arms, and the internal ones can't be invoked through the macro's name.
+//! - `inline`/`no_inline`: put `#[doc(inline)]` or `#[doc(no_inline)]` on the
+//!   re-export. World-public macros are inlined by default, which recent versions
+//!   of rustdoc need to document them under their public name.
+//! - `hidden`: put `#[doc(hidden)]` on the macro and its re-export, leaving it
+//!   out of documentation. Writing `#[doc(hidden)]` on the macro does the same.
+//! - `export_as = name`: also export the macro as world-public `name`, hidden
+//!   from documentation, e.g. to expose a `pub(crate)` macro to companion crates.
+//! - `deprecate_as = "old_name"`: also define the macro as `old_name`, hidden from
+//!   documentation and deprecated in favor of the new name, to ease a rename.
+//! - `alias = "name"`: also re-export the macro as `name`, hidden from
+//!   documentation and as visible as the macro, e.g. to keep an old name working
+//!   after a rename. It can be given more than once. rustc ignores `#[deprecated]`
+//!   on re-exports, so to deprecate an old name, use `deprecate_as` instead.
+//! - `hygienic_as = "name"`: on toolchains with `decl_macro`, also define the
+//!   macro as `name`, a `macro` with the same visibility and its default hygiene:
+//!   names the expansion defines, like local variables and items, aren't visible
+//!   to the caller, and the names it uses are resolved where it's defined. This
+//!   lets nightly users opt into better hygiene without a second definition to
+//!   maintain. Elsewhere, and in packages with a pinned backend (see `strategy`),
+//!   `name` isn't defined, so the crate needs `#![feature(decl_macro)]` exactly
+//!   where it's detected, e.g. from a build script probing for it like
+//!   `#[macro_pub]`'s own.
+//! - `friends("crate-name", ...)`: experimentally, also export the macro, hidden,
+//!   for only the named crates, which import it with
+//!   `macro_pub::friend!(path::to::name)`. This lets tightly-coupled crates in a
+//!   workspace share macros without making them public API.
+//! - `package`: also export the macro, hidden, for your package's integration
+//!   tests, benches, and examples, which are separate crates that `pub(crate)`
+//!   macros are invisible to. They import it with
+//!   `macro_pub::package!(your_crate::path::to::name)`. On its own,
+//!   `#[macro_pub(package)]` makes the macro `pub(crate)`.
+//! - `doctest`: make a restricted macro usable by name in the examples in its
+//!   documentation, which rustdoc compiles as separate crates. Each Rust code block
+//!   gets a hidden line defining the macro there, forwarding to a hidden export
+//!   guarded like `package`'s, so the macro's visibility doesn't change.
+//! - `selfcheck`: when documenting the macro as a `pub macro` on nightly, also
+//!   check that it matches a sample invocation of each arm, made up from the arm's
+//!   matcher, with the same arm as the `macro_rules!` macro, failing the
+//!   documentation build if not.
+//! - `since = "1.4.0"`: note in the documentation which version added the macro.
+//! - `unstable`: hide the macro from documentation, and mark it as unstable where
+//!   hidden items are documented, for macros not covered by semver.
+//! - `feature = "name"`: without your crate's cargo feature `name`, replace the
+//!   macro with one that fails to compile, asking for the feature to be enabled.
+//!   Documentation always shows the macro, noting the feature it requires. To
+//!   note that only some of its arms need a feature, put
+//!   `#[doc(cfg(feature = "name"))]`, or any other `doc(cfg)`, on each of them
+//!   instead: the documentation then notes what each of those invocations
+//!   requires, or `signatures` does next to them. Like the `feature` note, this
+//!   only documents the arms; they still match without the feature.
+//! - `also_export_legacy_name`: also `#[macro_export]` the macro under its own
+//!   name, hidden from documentation, so `#[macro_use] extern crate` and
+//!   `crate_name::name!` keep working while users migrate. The legacy name is
+//!   at the crate root, so a `use super::*` importing it into the module defining
+//!   the macro makes the two ambiguous, and it can't be used on macros defined
+//!   at the crate root.
+//! - `textual`: also define a world-public macro with `macro_rules!` under its own
+//!   name, unexported, so that uses below the definition and in modules after a
+//!   `#[macro_use] mod` keep working within the crate, as they do for a plain
+//!   `macro_rules!` macro. Restricted macros are already defined this way.
+//! - `no_textual`: define a restricted macro under a mangled name too, like a
+//!   world-public one, so it can only be used through its path, and a
+//!   `#[macro_use]` on a module containing it can't leak it past its visibility.
+//!   Invoking it by name below its definition then needs it to be imported.
+//! - `shadowable`: re-export the macro under its name through a glob import of a
+//!   private module, so that another item or import of the same name in the
+//!   module, like a `#[cfg(test)]` test double, shadows it rather than clashing
+//!   with it. Users' glob imports of the module bring it along as usual, though
+//!   a `macro_rules!` macro of the same name from outside their module still
+//!   makes uses of it ambiguous.
+//! - `doc_required`: make it an error for the macro to be world-public without
+//!   documentation, unless it's hidden. To require it of every macro in some
+//!   packages, set the `MACRO_PUB_DOC_REQUIRED` environment variable to a
+//!   comma-separated list of their names, e.g. in `.cargo/config.toml`.
+//! - `catch_all`: add a last arm, hidden from documentation, matching anything
+//!   the others don't, which fails with a list of the macro's arms rather than
+//!   rustc's "no rules expected this token". Hidden arms and internal rules
+//!   starting with `@` aren't listed.
+//! - `salt = "..."`: mix the string into the hash of the macro's tokens that the
+//!   name it's exported under is mangled with, to change that name without
+//!   changing the macro, e.g. so that crates that named it directly anyway stop
+//!   compiling.
+//! - `hash_docs`: keep doc comments in the hash the exported name is mangled
+//!   with. They're left out by default, so two world-public macros of the same
+//!   name that differ only in their documentation get the same name and
+//!   conflict; this, or a `salt`, tells them apart.
+//! - `mangle = "prefix"`: name the `#[macro_export]`ed macros behind a
+//!   world-public macro `prefix_<hash>_name`, with the hash in hex, rather than
+//!   `macro_impl_<hash>_name`, for readable expansions and errors; this covers
+//!   the names made for `export_as`, `deprecate_as` and `friends` too.
+//!   `mangle = false` names them `name`, which collides with macros of the same
+//!   name elsewhere in the crate, and with the macro's own re-export at the
+//!   crate root, where `strategy = "legacy_export"` is the way to go. That name
+//!   no longer changes with the macro.
+//! - `allow(lint, ...)`, `warn(...)`, and `deny(...)`: set the levels of
+//!   `#[macro_pub]`'s lints for the macro, as described under "Lints" below.
+//! - `expect_path = "crate::module::name"`: fail to compile unless something can
+//!   be imported from that path, which, if it's where the macro is re-exported,
+//!   catches moving the macro, or a module it's in, by accident. The path is
+//!   resolved from the macro's module, so can't tell whether it's reachable from
+//!   other crates. A `crate::...` path also tells a macro that invokes itself by
+//!   name where to find itself, as described above.
+//! - `extend`: define arms for a macro put together elsewhere, rather than a
+//!   macro, so that a large macro can be split across modules or files. The
+//!   part is hidden, as visible as its restriction, or the crate if it has
+//!   none, and takes no other options.
+//! - `extend_from(path::to::part, ...)`: add the arms of the `extend` parts at
+//!   these paths, in order, after the macro's own, and define the macro from all
+//!   of them with the other options. This names `#[macro_pub]` by its path
+//!   `::macro_pub::macro_pub`, so the crate can't be renamed.
+//! - `strategy = "..."`: define the macro a specific way, rather than as detected:
+//!   `"use_alias"` always uses the stable expansion above, `"decl_macro"` always
+//!   defines a `macro` with `macro_rules!` hygiene, which needs
+//!   `#![feature(decl_macro, rustc_attrs)]` (an error names the detected toolchain
+//!   if it lacks them), and `"legacy_export"` only
+//!   `#[macro_export]`s a world-public macro at the crate root. To expand every
+//!   macro without a `strategy` the same way on any toolchain, so that snapshots
+//!   of the generated code don't change with it, set the `MACRO_PUB_PIN_BACKEND`
+//!   environment variable to a comma-separated list of packages, like
+//!   `MACRO_PUB_DOC_REQUIRED`. Their macros then always use the stable expansion,
+//!   without a `pub macro` for documentation on nightly.
+//! - `metadata`: also define a hidden module `__macro_pub_metadata_name`, as
+//!   visible as the macro, with constants describing it: `NAME`, the number of
+//!   `ARMS`, its `VISIBILITY` as written, and the `MANGLED` name it's exported
+//!   under, if any. Tests can check these to catch accidental changes.
+//! - `debug`: warn with the code `#[macro_pub]` generated for the macro, and the
+//!   toolchain it detected, to see what it does without `cargo expand`.
+//! - `no_alias`: don't re-export the macro under its name, only define it, and
+//!   export it under its mangled name if it's world-public, to mount it yourself,
+//!   e.g. in a curated module of macros. The manifest lists the mangled name.
+//! - `unsafe_macro`: start the macro's documentation with a warning that it expands
+//!   to `unsafe` code, and end it with a "Safety" section if it has none. If no arm
+//!   of the macro contains `unsafe`, `#[macro_pub]` warns that the option is wrong.
+//! - `track_uses`: record which crates use a world-public macro in the manifest,
+//!   to find macros that could be `pub(crate)` (see below). Each use of the macro
+//!   goes through `macro_pub::track_use!`, which the crate must re-export at its
+//!   root as `__macro_pub_track_use`, and which needs Rust 1.45 or later.
+//! - `lint`: warn about code in the arms of a macro used in other crates that does
+//!   something else in those crates than it would in yours: `env!` and `option_env!`
+//!   read the environment compiling the crate using the macro, and `include!`,
+//!   `include_str!`, and `include_bytes!` find relative paths next to its files.
+//!   It also warns about `$crate::my_macro!` in the arms of `my_macro!`, which
+//!   only finds it at the crate root, where `#[macro_export]` put it.
+//! - `no_std`: warn about `std::` paths in the arms of a macro used in other crates,
+//!   which `#![no_std]` crates don't have, suggesting the `core` or `alloc` path of
+//!   the same module.
+//!
+//! Options that are renamed keep working under their old names, with a warning
+//! giving the new spelling, which they're read as.
+//!
+//! `#[macro_pub]` warns about options that can't have any effect, like `friends`
+//! on a world-public macro. It can't see where the macro is defined, though, so
+//! it can't tell that e.g. a `pub` macro in a function is only usable there.
+//!
+//! It also warns about `#[macro_export]` macros defined by the arms of a macro used
+//! in other crates: they're defined, and exported, by the crates using the macro,
+//! which can't use them by path. Define them with `#[macro_pub]` next to the macro
+//! instead, and use them through `$crate`.
+//!
+//! A `#[macro_export(local_inner_macros)]` left on the macro while migrating to
+//! `#[macro_pub]` is removed, and its effect kept: each macro its arms invoke by
+//! name alone, like `helper!(...)`, is invoked as `$crate::helper!(...)` instead,
+//! which finds `#[macro_export]` helpers at the crate root as before. As with the
+//! attribute, that includes the standard library's macros; to invoke those by name
+//! again, write `$crate::` before the crate's own macros and drop the attribute.
+//!
+//! # Lints
+//!
+//! Each of `#[macro_pub]`'s warnings is a lint, which can be set to `allow` it,
+//! `warn`, or `deny` it, making it an error, like rustc's lints:
+//!
+//! - `pointless_options`: options that can't have any effect.
+//! - `nested_exports`: `#[macro_export]` macros defined by the arms of a macro
+//!   used in other crates.
+//! - `ignored_arm_attrs`: attributes on arms that `#[macro_pub]` removed or
+//!   ignored.
+//! - `item_attrs`: attributes for other items, like `#[inline]` or `#[must_use]`,
+//!   which `#[macro_pub]` removed from the macro rather than copy them onto the
+//!   items it generates, where rustc would ignore or reject them.
+//! - `crate_root_paths`, `foreign_env`, and `relative_includes`: the checks of the
+//!   `lint` option, for `$crate::my_macro!`, `env!`, and relative `include!`s.
+//! - `self_calls`: a world-public macro invoking itself by name alone, which
+//!   `#[macro_pub]` can only point at its path if it knows it, as described above.
+//! - `std_paths`: the checks of the `no_std` option.
+//! - `std_names`: the macro, or a name it's exported as, also being the name of a
+//!   standard library macro like `vec!`, which users' glob imports of your macros
+//!   would shadow.
+//! - `narrowable`: `pub` macros the manifest only records uses of in their crate.
+//!
+//! Set them for a macro with the `allow(...)`, `warn(...)`, and `deny(...)`
+//! options, e.g. `#[macro_pub(deny(foreign_env, relative_includes))]`, or for a
+//! whole crate by setting the `MACRO_PUB_LINTS` environment variable to the
+//! same options, e.g. `deny(pointless_options)`. The macro's own levels take
+//! precedence, and the last level given for a lint wins. `std_names` and the
+//! lints of the `lint` and `no_std` options are allowed unless they're set or
+//! given a level, and the options warn about theirs regardless of the crate's
+//! levels. Denied lints are errors, except in packages Cargo compiles as
+//! dependencies, where they only warn, as `--cap-lints` would have them. Cargo
+//! doesn't rebuild crates when the variable changes, so clean them to check
+//! them again.
+//!
+//! Stable proc macros can only warn through rustc's own lints, so
+//! `#[macro_pub]`'s warnings are uses of a deprecated constant, and
+//! `#[allow(deprecated)]`, on the macro or the whole crate, silences every one
+//! of them. Allow them with the levels above instead, so that the others still
+//! warn, and deny the ones that must never be ignored.
+//!
+//! # Configuration file
+//!
+//! Build systems other than Cargo don't set `CARGO_PKG_NAME`, which the package
+//! lists in `MACRO_PUB_DOC_REQUIRED` and `MACRO_PUB_PIN_BACKEND` are matched
+//! against. For them, or to keep the settings in one place, set the
+//! `MACRO_PUB_CONFIG` environment variable to the path of a TOML file, relative to
+//! the crate's `Cargo.toml`, or to the directory rustc runs in without Cargo, with
+//! defaults for every crate compiled with it:
+//!
+//! ```toml
+//! # Like listing the crate in `MACRO_PUB_DOC_REQUIRED`.
+//! doc_required = true
+//! # Like listing the crate in `MACRO_PUB_PIN_BACKEND`.
+//! pin_backend = false
+//!
+//! # Like `MACRO_PUB_LINTS`, which gives its levels after these.
+//! [lints]
+//! pointless_options = "deny"
+//! ```
+//!
+//! Only these settings, `true` and `false`, lint levels in quotes, and `#`
+//! comments are understood, and anything else is an error. Each `#[macro_pub]`
+//! macro includes the file in an unused constant, so rustc tracks it, and crates
+//! are rebuilt when it changes.
+//!
+//! # Manifest
+//!
+//! If the `MACRO_PUB_MANIFEST` environment variable is set to a path, each
+//! `#[macro_pub]` macro compiled is described in a line of JSON appended to that
+//! file, for use by other tooling:
+//!
+//! ```text
+//! {"crate":"my_crate","name":"my_macro","visibility":"pub","mangled":"macro_impl_…_my_macro",
+//!  "export_path":"my_crate::macro_impl_…_my_macro","export_as":null,"deprecate_as":null,
+//!  "aliases":[],"hygienic_as":null,"friends":[],"since":null,"hidden":false,"unstable":false,
+//!  "feature":null,"legacy_name":false,"strategy":null,"metadata":false,
+//!  "no_alias":false,"unsafe_macro":false,"track_uses":false,"lint":false,
+//!  "no_std":false,"package":false,"doctest":false,"selfcheck":false,
+//!  "source":false,"hide_arms":false,"internal_rules":false,"textual":false,
+//!  "no_textual":false,"shadowable":false,"doc_required":false,
+//!  "catch_all":false,"hash_docs":false,"salt":null,"mangle":null,
+//!  "expect_path":null,"lint_levels":[]}
+//! ```
+//!
+//! `mangled` is `null` for macros that aren't exported under a mangled name, and
+//! `export_path` for those that aren't exported at all. Cargo doesn't rebuild
+//! crates when the variable changes, so clean them first (e.g.
+//! `cargo clean -p my_crate`) to get a complete list.
+//!
+//! A procedural macro crate can only export macros, so there's no API to run
+//! checks of your own while `#[macro_pub]` expands. To enforce house rules, like
+//! every world-public macro having a `since` version, check the manifest in CI
+//! instead.
+//!
+//! Each use of a `track_uses` macro compiled is also recorded, as a line like
+//! `{"crate":"other_crate","uses":"macro_impl_…_my_macro"}`. When the crate defining
+//! a tracked `pub` macro is rebuilt, and the manifest only records uses of it in that
+//! crate, `#[macro_pub]` warns that it could be `pub(crate)`, like rustc's
+//! `unreachable_pub` lint. Only uses compiled since the manifest was started are
+//! seen, so build everything using the macro, then clean and rebuild the defining
+//! crate. Uses are only recorded by crate, so macros aren't suggested narrower
+//! visibilities than `pub(crate)`.
+//!
+//! # Lock file
+//!
+//! The mangled name a world-public macro is exported under changes whenever its
+//! definition does, other than its doc comments. Crates only use it through the
+//! macro's public path, but if yours has users that name it directly anyway, set
+//! the `MACRO_PUB_LOCKFILE` environment variable to a path, relative to each
+//! crate's `Cargo.toml`, e.g. in `.cargo/config.toml`:
+//!
+//! ```toml
+//! [env]
+//! MACRO_PUB_LOCKFILE = "macro_pub.lock"
+//! ```
+//!
+//! Each macro's mangled name is then recorded in that file, as a line like
+//! `src/lib.rs:my_macro macro_impl_…_my_macro`, the first time it's compiled.
+//! Check the file in, and compiling a macro whose mangled name no longer
+//! matches it is an error, so the change can be released deliberately. Set
+//! `MACRO_PUB_LOCKFILE_MODE` to `warn` to only warn instead, or to `update` to
+//! accept the new names. Macros are looked up by the file they're defined in
+//! and their name, or by the path their `expect_path` option gives, so only
+//! macros of the same name in one file's modules need an `expect_path` each to
+//! be locked apart. Before Rust 1.88, which can't tell the file, that's any
+//! macros of the same name. Alternative definitions of a macro under different
+//! `#[cfg]`s share one entry, and as with the manifest, Cargo doesn't rebuild
+//! crates when the file changes.
+//!
+//! # Debugging
+//!
+//! If the `MACRO_PUB_DEBUG_DIR` environment variable is set to a directory, the
+//! code generated for each `#[macro_pub]` macro compiled is written to
+//! `my_crate/my_macro.rs` in it, and formatted with rustfmt if it's installed,
+//! so that it can be inspected or compared between versions. Macros with the same
+//! name in one crate overwrite each other's files, and as with the manifest,
+//! clean crates to have them rebuilt.
+//!
+//! To see how much of a slow build is spent in `#[macro_pub]`, set the
+//! `MACRO_PUB_TIMINGS` environment variable to a path. Each `#[macro_pub]` macro
+//! compiled then appends a line to that file with the crate's and the macro's
+//! names, and the microseconds spent parsing, hashing, and emitting it, separated
+//! by tabs, which can be summed up by crate with e.g.
+//!
+//! ```sh
+//! awk -F'\t' '{ n[$1]++; t[$1] += $3 + $4 + $5 }
+//!     END { for (c in t) printf "%s: %d macros, %dus\n", c, n[c], t[c] }' FILE
+//! ```
+//!
+//! As a procedural macro, `#[macro_pub]` only sees its own macros, so this
+//! doesn't include the time spent using them, and as with the manifest, clean
+//! crates to have them rebuilt.
+//!
+//! Coverage tools like `cargo llvm-cov` only count code in functions, and
+//! `#[macro_pub]` only generates macros, `use`s, and constants, none of which
+//! have any, so it adds nothing to coverage reports to exclude. Code expanded
+//! from a macro is counted where the macro is used, as it would be for a plain
+//! `macro_rules!` macro. rustc rejects `#[coverage(off)]` on all three kinds of
+//! item, so there's nothing to mark even where the attribute is available.
+//!
+//! Nor are the generated items marked `#[automatically_derived]`, for tools to
+//! skip as machinery: rustc only gives it meaning on trait impls, which
+//! `#[macro_pub]` doesn't generate, and warns that it will be an error on
+//! macros. The mangled macro and the other glue defined with it are
+//! `#[doc(hidden)]` instead, so rustdoc leaves them out.
+//!
+//! # Indexing macros
+//!
+//! `macro_pub::index!` generates a module re-exporting macros from across your
+//! crate, grouped into documented submodules, so that they can be browsed from
+//! one documentation page.
+//!
+//! # Namespaces
+//!
+//! `macro_pub::namespace!` takes modules of `macro_rules!` macros, and defines
+//! each module with its macros under `#[macro_pub]`, so that a crate's macros can
+//! be organized like its other items, as `your_crate::net::retry!` and
+//! `your_crate::net::http::get!`, from one declaration. Macros without a
+//! `#[macro_pub(...)]` of their own are world-public. Macros in a namespace
+//! aren't in scope in each other's arms by name, so they use each other through
+//! `$crate::net::retry!`.
+//!
+//! # Facades
+//!
+//! A plain `pub use` of a `#[macro_pub]` macro from another crate, like a facade
+//! re-exporting an implementation crate's API, re-exports the hidden, mangled macro
+//! the name refers to, so the macro is missing from the facade's documentation.
+//! `macro_pub::reexport!` takes paths like `index!` does, and generates the
+//! `#[doc(inline)] pub use` that documents the macro under the facade's path.
+//!
+//! # Importing macros
+//!
+//! `macro_pub::use_macros!` defines a macro that imports a curated set of your
+//! crate's macros, so downstream crates can write `your_crate::prelude!();` to bring
+//! them all into scope. Unlike `#[macro_use] extern crate`, the imports are ordinary
+//! path-based `use`s: they're scoped to the module invoking the macro, can be made
+//! `pub` with `your_crate::prelude!(pub);`, and don't import every macro the crate
+//! exports.
+//!
+//! `macro_pub` has no `prelude` module of its own to glob-import, since rustc
+//! doesn't let a procedural macro crate export anything but its macros. They're
+//! all at the crate root, so `use macro_pub::{macro_pub, index};` imports any
+//! of them in one line.
+//!
+//! # Platform-specific macros
+//!
+//! `macro_pub::cfg_macros!` takes a `macro_rules!` macro whose body is a list of
+//! `#[cfg(...)] { arms }` variants, and defines each variant as its own
+//! `#[macro_pub]` macro under that `cfg`, so that the one enabled is mounted
+//! under the macro's name. Separate `#[macro_pub]` definitions of a macro under
+//! mutually exclusive `#[cfg]`s work the same way, with the `#[cfg]` above or
+//! below `#[macro_pub]`, since rustc removes the disabled ones before
+//! `#[macro_pub]` sees them.
+//!
+//! On docs.rs, which documents each target separately, `#![feature(doc_cfg)]`
+//! badges macros in a module under `#[cfg(target_os = "...")]` with the targets
+//! they're available on, as it does other items, since their re-exports are in
+//! the module. rustc also removes the enabled `#[cfg]`s on the macro itself
+//! before `#[macro_pub]` sees them, so it can't badge those macros, and you need
+//! to add the badge yourself:
+//!
+//! ```rust
+//! # #![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+//! #![cfg_attr(doc, feature(doc_cfg))]
+//! # fn main() {}
+//!
+//! #[macro_pub::macro_pub]
+//! #[cfg(unix)]
+//! #[cfg_attr(doc, doc(cfg(unix)))]
+//! macro_rules! my_macro {
+//!     () => {};
+//! }
+//! ```
+//!
+//! Otherwise, two `#[macro_pub]` macros of the same name in one module fail to
+//! compile with rustc's "the name `my_macro` is defined multiple times" (E0252),
+//! pointing at both names, since both are imported into the module under it,
+//! where a plain `macro_rules!` macro would shadow the one before it.
+//! `#[macro_pub]` can't see which module a macro is in to explain this itself.
+//! Ignore rustc's suggestion to rename the import with `as`: rename or move one of
+//! the macros, or, if one is meant to take the other's place, like a test double,
+//! make the other `shadowable`.
+//!
+//! # Adopting behind a feature
+//!
+//! To trial `#[macro_pub]`, a library can apply it with `cfg_attr` behind an
+//! optional dependency, keeping its `macro_rules!` macros as they were without it:
+//!
+//! ```rust
+//! #![cfg_attr(all(doc, feature = "macro-pub"), feature(decl_macro, rustc_attrs))]
+//!
+//! pub mod exported {
+//!     #[cfg_attr(feature = "macro-pub", macro_pub::macro_pub)]
+//!     #[cfg_attr(not(feature = "macro-pub"), macro_export)]
+//!     macro_rules! my_macro {
+//!         () => {};
+//!     }
+//! }
+//!
+//! // `#[macro_export]` mounts the macro at the crate root, so keep it there.
+//! #[cfg(feature = "macro-pub")]
+//! pub use exported::my_macro;
+//! ```
+//!
+//! Name the attribute by its full path, since a `use macro_pub::macro_pub;` fails
+//! to resolve without the dependency, and only enable the nightly features above
+//! with it. `#[macro_pub(crate)]` and narrower macros keep their textual scope, so
+//! code using them that way compiles either way, but paths to them only resolve
+//! with the feature, and unlike a plain `macro_rules!` macro, they can't share
+//! their name with a function or other item in their module, which their `use`
+//! would import too. The manifest, lock file, and the other environment
+//! variables above only see macros compiled with the feature, and macros
+//! compiled without it leave their lock file entries as they were.
+//!
 //! # Examples
 //!
 //! In a module with `pub(crate)` visibility:
@@ -123,146 +657,296 @@
 //! test::m!();
 //! ```
 
-use proc_macro::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
-use xxhash_rust::xxh3::xxh3_128;
+extern crate proc_macro;
 
-#[proc_macro_attribute]
-pub fn macro_pub(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let has_simple_decl_macro = cfg!(has_simple_decl_macro);
-    let hash = xxh3_128(item.to_string().as_bytes());
-    let error_output = {
-        let mut output = item.clone();
-        output.extend(
-            r#"compile_error! { "`#[macro_pub]` must be used on a `macro_rules!` macro" }"#
-                .parse::<TokenStream>()
-                .unwrap(),
-        );
-        output
-    };
+mod arms;
+mod cfg_macros;
+mod config_file;
+mod docs;
+mod dump;
+mod expand;
+mod extend;
+mod friend;
+mod index;
+mod lints;
+mod lockfile;
+mod manifest;
+mod namespace;
+mod parse;
+mod timings;
+mod use_macros;
 
-    let mut attrs = TokenStream::new();
-    let mut tokens = item.into_iter();
+use crate::expand::Config;
 
-    let macro_rules = loop {
-        match tokens.next() {
-            Some(TokenTree::Ident(ident)) if ident.to_string() == "macro_rules" => {
-                break TokenTree::Ident(ident);
-            }
-            // #[attribute]
-            Some(TokenTree::Punct(punct)) if punct.as_char() == '#' => match tokens.next() {
-                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => {
-                    attrs.extend([TokenTree::Punct(punct), TokenTree::Group(group)])
-                }
-                _ => return error_output,
-            },
-            _ => return error_output,
+#[proc_macro_attribute]
+pub fn macro_pub(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let attr = proc_macro2::TokenStream::from(attr);
+    let item = proc_macro2::TokenStream::from(item);
+    let args = parse::Args::parse(attr.clone());
+    // A renamed macro is expanded, and recorded, as if it were written with
+    // its new name.
+    let item = expand::renamed(args.as_ref().ok(), item);
+    let timings = timings::Timings::from_env(&attr, &item);
+    let config = Config::detected();
+    let mut output = expand::macro_pub(attr, &args, item.clone(), &config);
+    if let Ok(Some(ref path)) = config.config_file {
+        output.extend(config_file::track(path));
+    }
+    // Parts of a macro, and macros mounting them, are only recorded once the
+    // parts are put together.
+    if !args
+        .as_ref()
+        .map_or(false, |args| args.extend || !args.extend_from.is_empty())
+    {
+        output.extend(dump::dump_from_env(item.clone(), &output));
+        if let Ok(ref args) = args {
+            output.extend(manifest::narrowing_from_env(args, item.clone()));
+            output.extend(lockfile::lock_from_env(args, item.clone()));
+            output.extend(manifest::record_from_env(args, item));
         }
-    };
-
-    let bang = match tokens.next() {
-        Some(TokenTree::Punct(punct)) if punct.as_char() == '!' => TokenTree::Punct(punct),
-        _ => return error_output,
-    };
-
-    let macro_name = match tokens.next() {
-        Some(TokenTree::Ident(ident)) => ident,
-        _ => return error_output,
-    };
-
-    let macro_arms = match tokens.next() {
-        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => group.stream(),
-        _ => return error_output,
-    };
+    }
+    output.extend(timings.map(timings::Timings::record));
+    output.into()
+}
 
-    let (vis, need_macro_export) = if attr.is_empty() {
-        (
-            [TokenTree::Ident(Ident::new("pub", Span::call_site()))]
-                .into_iter()
-                .collect::<TokenStream>(),
-            true,
-        )
-    } else {
-        (
-            [
-                TokenTree::Ident(Ident::new("pub", Span::call_site())),
-                TokenTree::Group(Group::new(Delimiter::Parenthesis, attr)),
-            ]
-            .into_iter()
-            .collect(),
-            false,
-        )
-    };
+/// Generates a module re-exporting macros, to give them one documentation
+/// page.
+///
+/// Each path in the module becomes a `#[doc(inline)] pub use`, and nested
+/// `mod`s group related macros into their own documented modules:
+///
+/// ```
+/// # use macro_pub::macro_pub;
+/// mod imp {
+///     # use super::*;
+///     /// Makes a thing.
+///     #[macro_pub]
+///     macro_rules! make {
+///         () => {};
+///     }
+///
+///     /// Breaks a thing.
+///     #[macro_pub]
+///     macro_rules! break_ {
+///         () => {};
+///     }
+/// }
+///
+/// macro_pub::index! {
+///     /// All of this crate's macros.
+///     pub mod macros {
+///         crate::imp::make;
+///         /// Macros for breaking things.
+///         mod breaking {
+///             crate::imp::break_;
+///         }
+///     }
+/// }
+///
+/// macros::make!();
+/// macros::breaking::break_!();
+/// # fn main() {}
+/// ```
+#[proc_macro]
+pub fn index(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    index::index(input.into()).into()
+}
 
-    let macro_rules_name = TokenTree::Ident(Ident::new(
-        &format!("macro_impl_{}_{}", hash, macro_name),
-        macro_name.span(),
-    ));
+/// Defines macros in modules, to give them a path like `net::retry!`.
+///
+/// Each module in the input is defined as written, and each macro in it with
+/// `#[macro_pub]`, or the `#[macro_pub(...)]` it has. Nested `mod`s become
+/// `pub mod`s, for a hierarchy of macros:
+///
+/// ```
+/// macro_pub::namespace! {
+///     /// Networking macros.
+///     pub mod net {
+///         /// Retries an expression.
+///         macro_rules! retry {
+///             ($e:expr) => { $e };
+///         }
+///         /// HTTP macros.
+///         mod http {
+///             /// Makes a request.
+///             #[macro_pub(crate)]
+///             macro_rules! get {
+///                 () => { "GET" };
+///             }
+///         }
+///     }
+/// }
+///
+/// fn main() {
+///     assert_eq!(net::retry!(1), 1);
+///     assert_eq!(net::http::get!(), "GET");
+/// }
+/// ```
+#[proc_macro]
+pub fn namespace(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    namespace::namespace(input.into()).into()
+}
 
-    let mut output = attrs.clone();
+/// Re-exports macros in the current module, documenting them there.
+///
+/// This is for facade crates re-exporting `#[macro_pub]` macros from other
+/// crates, which a plain `pub use` leaves undocumented. The input lists paths
+/// and groups them into `mod`s, like a module for [`index!`]:
+///
+/// ```
+/// # mod other_crate {
+/// #     use macro_pub::macro_pub;
+/// #     /// Makes a thing.
+/// #     #[macro_pub]
+/// #     macro_rules! make {
+/// #         () => {};
+/// #     }
+/// #     /// Breaks a thing.
+/// #     #[macro_pub]
+/// #     macro_rules! break_ {
+/// #         () => {};
+/// #     }
+/// # }
+/// macro_pub::reexport! {
+///     other_crate::make;
+///     /// Macros for breaking things.
+///     mod breaking {
+///         crate::other_crate::break_;
+///     }
+/// }
+///
+/// make!();
+/// breaking::break_!();
+/// # fn main() {}
+/// ```
+#[proc_macro]
+pub fn reexport(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    index::reexport(input.into()).into()
+}
 
-    if has_simple_decl_macro && need_macro_export {
-        output.extend(
-            r##"#[cfg(doc)] #[rustc_macro_transparency = "semitransparent"]"##
-                .parse::<TokenStream>()
-                .unwrap(),
-        );
-        output.extend(vis.clone());
-        output.extend([
-            TokenTree::Ident(Ident::new("macro", Span::mixed_site())),
-            TokenTree::Ident(macro_name.clone()),
-            TokenTree::Group(Group::new(
-                Delimiter::Brace,
-                macro_arms
-                    .clone()
-                    .into_iter()
-                    .map(|tt| match tt {
-                        TokenTree::Punct(punct) if punct.as_char() == ';' => {
-                            TokenTree::Punct(Punct::new(',', punct.spacing()))
-                        }
-                        tt => tt,
-                    })
-                    .collect(),
-            )),
-        ]);
-        output.extend(attrs);
-        output.extend(r##"#[cfg(not(doc))]"##.parse::<TokenStream>().unwrap());
-    }
+/// Defines a macro with a different implementation for each of several
+/// `cfg`s, under one name.
+///
+/// Each `#[cfg(...)] { arms }` variant becomes its own `#[macro_pub]` macro
+/// with that `cfg` and the other attributes, so whichever is enabled is
+/// mounted at the macro's path:
+///
+/// ```
+/// macro_pub::cfg_macros! {
+///     /// The platform's path separator.
+///     #[macro_pub(crate)]
+///     macro_rules! separator {
+///         #[cfg(windows)] {
+///             () => { '\\' };
+///         }
+///         #[cfg(not(windows))] {
+///             () => { '/' };
+///         }
+///     }
+/// }
+///
+/// fn main() {
+///     assert_eq!(crate::separator!(), std::path::MAIN_SEPARATOR);
+/// }
+/// ```
+#[proc_macro]
+pub fn cfg_macros(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    cfg_macros::cfg_macros(input.into()).into()
+}
 
-    if need_macro_export {
-        output.extend(
-            "#[macro_export] #[doc(hidden)]"
-                .parse::<TokenStream>()
-                .unwrap(),
-        );
-    }
-    output.extend([
-        macro_rules,
-        bang,
-        if need_macro_export {
-            macro_rules_name.clone()
-        } else {
-            TokenTree::Ident(macro_name.clone())
-        },
-        TokenTree::Group(Group::new(Delimiter::Brace, macro_arms)),
-    ]);
+/// Defines a macro that imports a set of the crate's macros in one line.
+///
+/// The body lists paths to macros starting with `crate::`, like [`index!`]
+/// does, and the macro, defined with `#[macro_pub]` and any `#[macro_pub(...)]`
+/// arguments given, expands to a `use` of each, with an optional visibility:
+///
+/// ```
+/// mod make {
+///     #[macro_pub::macro_pub(crate)]
+///     macro_rules! make {
+///         () => { 1 };
+///     }
+///     #[macro_pub::macro_pub(crate)]
+///     macro_rules! remake {
+///         () => { 2 };
+///     }
+/// }
+///
+/// macro_pub::use_macros! {
+///     /// Imports `make!` and `remake!`.
+///     #[macro_pub(crate)]
+///     macro_rules! prelude {
+///         crate::make::{make, remake};
+///     }
+/// }
+///
+/// mod user {
+///     crate::prelude!();
+///
+///     pub fn made() -> i32 {
+///         make!() + remake!()
+///     }
+/// }
+///
+/// fn main() {
+///     assert_eq!(user::made(), 3);
+/// }
+/// ```
+#[proc_macro]
+pub fn use_macros(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    use_macros::use_macros(input.into()).into()
+}
 
-    if has_simple_decl_macro && need_macro_export {
-        output.extend(r##"#[cfg(not(doc))]"##.parse::<TokenStream>().unwrap());
-    }
+/// Imports a macro that another crate shares with this one, experimentally,
+/// with `#[macro_pub(friends("this-crate"))]`.
+///
+/// `macro_pub::friend!(path::to::name)` defines a `pub(crate)` macro `name` in
+/// the current module, which forwards to the hidden export that only accepts
+/// invocations from the listed crates. Crates that aren't listed can't use it,
+/// but nothing stops a crate from pretending to be another, so this keeps
+/// macros out of the public API rather than securing them.
+#[proc_macro]
+pub fn friend(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let krate = std::env::var("CARGO_CRATE_NAME")
+        .or_else(|_| std::env::var("CARGO_PKG_NAME"))
+        .ok();
+    friend::friend(input.into(), krate).into()
+}
 
-    output.extend(vis);
-    output.extend([
-        TokenTree::Ident(Ident::new("use", Span::mixed_site())),
-        if need_macro_export {
-            macro_rules_name
-        } else {
-            TokenTree::Ident(macro_name.clone())
-        },
-        TokenTree::Ident(Ident::new("as", Span::mixed_site())),
-        TokenTree::Ident(macro_name),
-        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
-    ]);
-    output.extend(tokens);
+/// Imports a macro that the package's library shares with its tests, benches,
+/// and examples, with `#[macro_pub(package)]`.
+///
+/// `macro_pub::package!(my_crate::path::to::name)` defines a `pub(crate)` macro
+/// `name` in the current module, which forwards to the hidden export that only
+/// accepts invocations from the same package. Like [`friend!`], this keeps the
+/// macro out of the public API rather than securing it.
+#[proc_macro]
+pub fn package(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    friend::package(input.into(), std::env::var("CARGO_PKG_NAME").ok()).into()
+}
 
-    output
+/// Records a use of a `#[macro_pub(track_uses)]` macro in the manifest.
+///
+/// Crates with macros that track their uses re-export this at the crate root,
+/// where the macros' expansions find it:
+///
+/// ```
+/// #[doc(hidden)]
+/// pub use macro_pub::track_use as __macro_pub_track_use;
+///
+/// #[macro_pub::macro_pub(track_uses)]
+/// macro_rules! m {
+///     () => { 1 };
+/// }
+///
+/// fn main() {
+///     assert_eq!(m!(), 1);
+/// }
+/// ```
+#[proc_macro]
+pub fn track_use(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    manifest::track_use_from_env(input.into()).into()
 }
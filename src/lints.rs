@@ -0,0 +1,593 @@
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
+
+use crate::arms::Arms;
+use crate::expand::{last_level, Config};
+use crate::parse::{transcribers, Args, Level, Strategy};
+
+/// Lints about code in `arms` that does something else in the crates using
+/// `macro_name!`, and about the names it's defined under, besides those
+/// rewriting the arms found.
+pub(crate) fn macro_lints(
+    arms: &Arms,
+    macro_name: &Ident,
+    args: &Args,
+    config: &Config,
+    cross_crate: bool,
+) -> Vec<(&'static str, String, Span)> {
+    let mut lints = Vec::new();
+    if cross_crate {
+        lints.extend(cross_crate_lints(&arms.written, macro_name, args, config));
+    }
+
+    // Hidden arms are only left out of the documented macro.
+    let hides_arms = args.restriction.is_empty() && args.strategy.is_none() && !args.no_alias;
+    if !hides_arms {
+        for &(_, span) in &arms.hidden {
+            lints.push((
+                "ignored_arm_attrs",
+                "`#[macro_pub]` ignored `#[doc(hidden)]` on this arm, since only world-public \
+                 macros documented as a `pub macro` can hide arms"
+                    .to_string(),
+                span,
+            ));
+        }
+    }
+
+    lints.extend(std_name_lints(macro_name, args, config));
+    if cross_crate {
+        lints.extend(nested_export_lints(&arms.arms, macro_name));
+    }
+    lints
+}
+
+/// Lints about code in `macro_arms` that does something else in the crates
+/// using the macro, for a macro used in other crates.
+fn cross_crate_lints(
+    macro_arms: &TokenStream,
+    macro_name: &Ident,
+    args: &Args,
+    config: &Config,
+) -> Vec<(&'static str, String, Span)> {
+    let level = |name: &str| lint_level(args, config, name);
+    let mut lints = Vec::new();
+    for transcriber in transcribers(macro_arms.clone()) {
+        let envs = if level("foreign_env") != Level::Allow {
+            invocations(transcriber.stream(), &["env", "option_env"])
+        } else {
+            Vec::new()
+        };
+        for (env, _) in envs {
+            lints.push((
+                "foreign_env",
+                format!(
+                    "`#[macro_pub]` macro `{}!` uses `{}!`, which reads the environment \
+                     compiling the crate using the macro, not this one; to read this crate's, \
+                     use it in a `pub const` here, and use that through `$crate`",
+                    macro_name, env,
+                ),
+                env.span(),
+            ));
+        }
+        let includes = &["include", "include_str", "include_bytes"];
+        for (include, input) in invocations(transcriber.stream(), includes) {
+            if level("relative_includes") != Level::Allow && is_relative_path(input) {
+                lints.push((
+                    "relative_includes",
+                    format!(
+                        "`#[macro_pub]` macro `{}!` uses `{}!` with a relative path, which is \
+                         relative to the file using the macro, not this one; `{1}!` it in a \
+                         `pub const` here instead, and use that through `$crate`",
+                        macro_name, include,
+                    ),
+                    include.span(),
+                ));
+            }
+        }
+    }
+
+    if level("std_paths") != Level::Allow {
+        for transcriber in transcribers(macro_arms.clone()) {
+            for (std, module) in std_paths(transcriber.stream()) {
+                let path = match module {
+                    Some(ref module) => format!("std::{}", module),
+                    None => "std".to_string(),
+                };
+                let instead = match module {
+                    Some(ref module) if CORE_MODULES.contains(&&**module) => {
+                        format!("use `::core::{}` instead", module)
+                    }
+                    Some(ref module) if ALLOC_MODULES.contains(&&**module) => format!(
+                        "use `::alloc::{}` instead, which needs `extern crate alloc;`",
+                        module
+                    ),
+                    Some(ref module) if STD_ONLY_MODULES.contains(&&**module) => {
+                        "it has no equivalent in `core` or `alloc`".to_string()
+                    }
+                    _ => "use `::core` or `::alloc` instead, if they have it".to_string(),
+                };
+                lints.push((
+                    "std_paths",
+                    format!(
+                        "`#[macro_pub]` macro `{}!` uses `{}`, which `#![no_std]` crates using \
+                         the macro don't have; {}",
+                        macro_name, path, instead,
+                    ),
+                    std.span(),
+                ));
+            }
+        }
+    }
+    lints
+}
+
+/// Lints about the names the macro is defined under that are also the names
+/// of standard library macros.
+fn std_name_lints(
+    macro_name: &Ident,
+    args: &Args,
+    config: &Config,
+) -> Vec<(&'static str, String, Span)> {
+    let mut lints = Vec::new();
+    if lint_level(args, config, "std_names") == Level::Allow {
+        return lints;
+    }
+    let names = Some(macro_name)
+        .into_iter()
+        .chain(args.export_as.as_ref())
+        .chain(args.deprecate_as.as_ref())
+        .chain(&args.aliases)
+        .chain(args.hygienic_as.as_ref());
+    for name in names {
+        if STD_MACROS.contains(&&*name.to_string()) {
+            lints.push((
+                "std_names",
+                format!(
+                    "`#[macro_pub]` macro `{0}!` has the same name as the standard library's \
+                     `{0}!`, which glob imports of it shadow; consider renaming it, or \
+                     telling users to import it by name",
+                    name,
+                ),
+                macro_name.span(),
+            ));
+        }
+    }
+    lints
+}
+
+/// Lints about `#[macro_export]` macros defined by `macro_arms`, for a macro
+/// used in other crates. Those are defined in the crates using it, so
+/// exporting them doesn't do what it would here.
+fn nested_export_lints(
+    macro_arms: &TokenStream,
+    macro_name: &Ident,
+) -> Vec<(&'static str, String, Span)> {
+    let mut lints = Vec::new();
+    for transcriber in transcribers(macro_arms.clone()) {
+        for nested in nested_exports(transcriber.stream()) {
+            lints.push((
+                "nested_exports",
+                format!(
+                    "`#[macro_pub]` macro `{}!` defines `#[macro_export]` macro `{}!`, which \
+                     is exported from the crates using `{0}!`, where it can't be used by \
+                     path; consider defining it with `#[macro_pub]` here, and using it \
+                     through `$crate`",
+                    macro_name, nested,
+                ),
+                macro_name.span(),
+            ));
+        }
+    }
+    lints
+}
+
+/// Warnings about options in `args` that can't change who can use the macro
+/// with `arms`, or how it's shown, and other mistakes.
+pub(crate) fn pointless_options(
+    args: &Args,
+    macro_name: &Ident,
+    arms: &Arms,
+    mangled: Option<&str>,
+    cross_crate: bool,
+    hidden_attr: bool,
+) -> Vec<String> {
+    let legacy_export = args.strategy == Some(Strategy::LegacyExport);
+    let mut pointless = Vec::new();
+    if !args.friends.is_empty() && args.restriction.is_empty() {
+        pointless.push(
+            "`#[macro_pub]` option `friends` has no effect, since the macro is already `pub`"
+                .to_string(),
+        );
+    }
+    if args.doctest && args.restriction.is_empty() {
+        pointless.push(
+            "`#[macro_pub]` option `doctest` has no effect, since the macro is already `pub`"
+                .to_string(),
+        );
+    }
+    if args.selfcheck && (args.strategy.is_some() || args.no_alias) {
+        pointless.push(
+            "`#[macro_pub]` option `selfcheck` has no effect, since the macro is never documented \
+             as a `pub macro`"
+                .to_string(),
+        );
+    }
+    if args.internal_rules && arms.helper.is_empty() {
+        pointless.push(
+            "`#[macro_pub]` option `internal_rules` has no effect, since no arm's matcher starts \
+             with `@`"
+                .to_string(),
+        );
+    }
+    if args.hide_arms && (!args.restriction.is_empty() || args.strategy.is_some() || args.no_alias)
+    {
+        pointless.push(
+            "`#[macro_pub]` option `hide_arms` has no effect, since only world-public macros \
+             documented as a `pub macro` can hide their arms"
+                .to_string(),
+        );
+    }
+    if args.lint && !cross_crate {
+        pointless.push(
+            "`#[macro_pub]` option `lint` has no effect, since the macro can't be used in other \
+             crates"
+                .to_string(),
+        );
+    }
+    if args.no_std && !cross_crate {
+        pointless.push(
+            "`#[macro_pub]` option `no_std` has no effect, since the macro can't be used in \
+             other crates"
+                .to_string(),
+        );
+    }
+    if args.track_uses && mangled.is_none() {
+        pointless.push(
+            "`#[macro_pub]` option `track_uses` has no effect, since only world-public macros \
+             exported under a mangled name are tracked"
+                .to_string(),
+        );
+    }
+    if args.hidden && args.unstable {
+        pointless.push(
+            "`#[macro_pub]` option `hidden` has no effect, since `unstable` already hides the macro"
+                .to_string(),
+        );
+    }
+    if args.hidden && hidden_attr {
+        pointless.push(
+            "`#[macro_pub]` option `hidden` has no effect, since the macro is already \
+             `#[doc(hidden)]`"
+                .to_string(),
+        );
+    }
+    if args.textual && (legacy_export || args.also_export_legacy_name) {
+        pointless.push(
+            "`#[macro_pub]` option `textual` has no effect, since the macro is already exported \
+             under its own name, which is textually scoped"
+                .to_string(),
+        );
+    } else if args.textual
+        && !args.restriction.is_empty()
+        && args.strategy != Some(Strategy::DeclMacro)
+    {
+        pointless.push(
+            "`#[macro_pub]` option `textual` has no effect, since restricted macros are already \
+             defined under their own name"
+                .to_string(),
+        );
+    }
+    if args.no_textual
+        && (args.restriction.is_empty() || args.strategy == Some(Strategy::DeclMacro))
+    {
+        pointless.push(
+            "`#[macro_pub]` option `no_textual` has no effect, since only restricted \
+             `macro_rules!` macros are defined under their own name"
+                .to_string(),
+        );
+    }
+    if args.doc_required && !args.restriction.is_empty() {
+        pointless.push(
+            "`#[macro_pub]` option `doc_required` has no effect, since only world-public macros \
+             need documentation"
+                .to_string(),
+        );
+    }
+    if args.shadowable
+        && (args.no_alias || legacy_export || args.strategy == Some(Strategy::DeclMacro))
+    {
+        pointless.push(
+            "`#[macro_pub]` option `shadowable` has no effect, since the macro isn't re-exported \
+             under its name"
+                .to_string(),
+        );
+    }
+    if args.salt.is_some() && mangled.is_none() && args.friends.is_empty() {
+        pointless.push(
+            "`#[macro_pub]` option `salt` has no effect, since the macro isn't exported under a \
+             mangled name"
+                .to_string(),
+        );
+    }
+    if args.hash_docs && mangled.is_none() && args.friends.is_empty() {
+        pointless.push(
+            "`#[macro_pub]` option `hash_docs` has no effect, since the macro isn't exported \
+             under a mangled name"
+                .to_string(),
+        );
+    }
+    if args.mangle.is_some() && mangled.is_none() && !args.no_textual {
+        pointless.push(
+            "`#[macro_pub]` option `mangle` has no effect, since the macro isn't exported under a \
+             mangled name"
+                .to_string(),
+        );
+    }
+    if args.also_export_legacy_name && legacy_export {
+        pointless.push(format!(
+            "`#[macro_pub]` option `also_export_legacy_name` has no effect, since strategy \
+             `legacy_export` already exports `{}` at the crate root",
+            macro_name,
+        ));
+    }
+    let unsafe_arms = transcribers(arms.arms.clone())
+        .into_iter()
+        .any(|transcriber| contains_unsafe(transcriber.stream()));
+    if args.unsafe_macro && !unsafe_arms {
+        pointless.push(
+            "`#[macro_pub]` option `unsafe_macro` is set, but no arm of the macro expands to \
+             `unsafe` code"
+                .to_string(),
+        );
+    }
+
+    pointless
+}
+
+/// The level of the lint `name` for a macro with `args`.
+///
+/// Lints checked by an option warn for macros with it, and levels set for the
+/// crate give way to those set for the macro.
+pub(crate) fn lint_level(args: &Args, config: &Config, name: &str) -> Level {
+    let opted_in = match name {
+        "crate_root_paths" | "foreign_env" | "relative_includes" => Some(args.lint),
+        "std_paths" => Some(args.no_std),
+        // Only checked if it's given a level.
+        "std_names" => Some(false),
+        _ => None,
+    };
+    let crate_level = match config.lint_levels {
+        Ok(ref levels) => last_level(levels, name),
+        Err(_) => None,
+    };
+    let default = match opted_in {
+        Some(true) => Level::Warn,
+        Some(false) => crate_level.unwrap_or(Level::Allow),
+        None => crate_level.unwrap_or(Level::Warn),
+    };
+    config.cap(last_level(&args.lint_levels, name).unwrap_or(default))
+}
+
+/// The macros the standard library exports, all of which are in scope
+/// everywhere through the prelude.
+const STD_MACROS: &[&str] = &[
+    "assert",
+    "assert_eq",
+    "assert_ne",
+    "cfg",
+    "column",
+    "compile_error",
+    "concat",
+    "dbg",
+    "debug_assert",
+    "debug_assert_eq",
+    "debug_assert_ne",
+    "env",
+    "eprint",
+    "eprintln",
+    "file",
+    "format",
+    "format_args",
+    "include",
+    "include_bytes",
+    "include_str",
+    "line",
+    "matches",
+    "module_path",
+    "option_env",
+    "panic",
+    "print",
+    "println",
+    "stringify",
+    "thread_local",
+    "todo",
+    "try",
+    "unimplemented",
+    "unreachable",
+    "vec",
+    "write",
+    "writeln",
+];
+
+/// The modules of `std` that are re-exported from `core`.
+const CORE_MODULES: &[&str] = &[
+    "any",
+    "array",
+    "ascii",
+    "cell",
+    "char",
+    "clone",
+    "cmp",
+    "convert",
+    "default",
+    "f32",
+    "f64",
+    "fmt",
+    "future",
+    "hash",
+    "hint",
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "i128",
+    "isize",
+    "iter",
+    "marker",
+    "mem",
+    "num",
+    "ops",
+    "option",
+    "panic",
+    "pin",
+    "primitive",
+    "ptr",
+    "result",
+    "slice",
+    "str",
+    "task",
+    "time",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "usize",
+];
+
+/// The modules of `std` that are re-exported from `alloc`.
+const ALLOC_MODULES: &[&str] = &["boxed", "collections", "rc", "string", "vec"];
+
+/// The modules of `std` that `core` and `alloc` have no part of.
+const STD_ONLY_MODULES: &[&str] = &["env", "fs", "io", "net", "os", "path", "process", "thread"];
+
+/// Checks whether `tokens` contain the `unsafe` keyword.
+fn contains_unsafe(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(ident) => ident == "unsafe",
+        TokenTree::Group(group) => contains_unsafe(group.stream()),
+        _ => false,
+    })
+}
+
+/// The names of the `#[macro_export]` macros that `tokens` define, with a
+/// leading `$` for names substituted by a macro.
+fn nested_exports(tokens: TokenStream) -> Vec<String> {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut names = Vec::new();
+    let mut exported = false;
+    for (i, tt) in tokens.iter().enumerate() {
+        match *tt {
+            TokenTree::Group(ref group) if group.delimiter() == Delimiter::Bracket => {
+                let is_export = match group.stream().into_iter().next() {
+                    Some(TokenTree::Ident(ref ident)) => ident == "macro_export",
+                    _ => false,
+                };
+                exported |= is_export;
+            }
+            TokenTree::Group(ref group) => names.extend(nested_exports(group.stream())),
+            TokenTree::Ident(ref ident) if ident == "macro_rules" => {
+                let name = match (tokens.get(i + 2), tokens.get(i + 3)) {
+                    (Some(TokenTree::Ident(name)), _) => name.to_string(),
+                    (Some(TokenTree::Punct(dollar)), Some(TokenTree::Ident(name)))
+                        if dollar.as_char() == '$' =>
+                    {
+                        format!("${}", name)
+                    }
+                    _ => continue,
+                };
+                if exported {
+                    names.push(name);
+                }
+                exported = false;
+            }
+            TokenTree::Punct(ref punct) if punct.as_char() == '#' => {}
+            _ => exported = false,
+        }
+    }
+    names
+}
+
+/// The invocations in `tokens` of macros named one of `names`, with their
+/// input.
+fn invocations(tokens: TokenStream, names: &[&str]) -> Vec<(Ident, TokenStream)> {
+    let mut found = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Group(group) => found.extend(invocations(group.stream(), names)),
+            TokenTree::Ident(ident) if names.contains(&&*ident.to_string()) => {
+                match tokens.peek() {
+                    Some(TokenTree::Punct(bang)) if bang.as_char() == '!' => {}
+                    _ => continue,
+                }
+                tokens.next();
+                let input = match tokens.peek() {
+                    Some(TokenTree::Group(input)) => input.stream(),
+                    _ => TokenStream::new(),
+                };
+                found.push((ident, input));
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// The `std` of each path in `tokens` starting with `std::` or `::std::`,
+/// and the module the path names in it, if any.
+fn std_paths(tokens: TokenStream) -> Vec<(Ident, Option<String>)> {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let is_colon = |i: usize| match tokens.get(i) {
+        Some(TokenTree::Punct(punct)) => punct.as_char() == ':',
+        _ => false,
+    };
+    let mut found = Vec::new();
+    for (i, tt) in tokens.iter().enumerate() {
+        match *tt {
+            TokenTree::Group(ref group) => found.extend(std_paths(group.stream())),
+            TokenTree::Ident(ref std) if std == "std" && is_colon(i + 1) && is_colon(i + 2) => {
+                // Not `$crate::std::`, or `other::std::`.
+                let nested = i >= 3
+                    && is_colon(i - 1)
+                    && match tokens[i - 3] {
+                        TokenTree::Ident(ref ident) => !KEYWORDS.contains(&&*ident.to_string()),
+                        TokenTree::Punct(ref punct) => punct.as_char() == '>',
+                        _ => false,
+                    };
+                if nested {
+                    continue;
+                }
+                let module = match tokens.get(i + 3) {
+                    Some(TokenTree::Ident(module)) => Some(module.to_string()),
+                    _ => None,
+                };
+                found.push((std.clone(), module));
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// Keywords that can come before an expression or a type starting with a
+/// path.
+const KEYWORDS: &[&str] = &[
+    "as", "break", "dyn", "else", "if", "impl", "in", "let", "match", "mut", "return", "where",
+    "while", "yield",
+];
+
+/// Checks whether `input`, the input of an `include!`-like macro, is a
+/// relative path.
+fn is_relative_path(input: TokenStream) -> bool {
+    match input.into_iter().next() {
+        Some(TokenTree::Literal(path)) => {
+            let path = path.to_string();
+            if !path.ends_with('"') && !path.ends_with('#') {
+                return false;
+            }
+            let path = path.trim_start_matches(|c| c == 'r' || c == '#' || c == '"');
+            !(path.starts_with('/') || path.starts_with('\\') || path.get(1..2) == Some(":"))
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,214 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenStream;
+
+use crate::expand::{macro_hash, mangled_name, spanned_error, warning};
+use crate::parse::{Args, MacroRules};
+
+/// What to do when a macro's mangled name doesn't match the lock file.
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Error,
+    Warn,
+    Update,
+}
+
+/// Checks the mangled name of `#[macro_pub(args)] item` against the lock file
+/// at `MACRO_PUB_LOCKFILE`, if it's set, recording it if it isn't there yet,
+/// or returns an error or warning to emit.
+pub(crate) fn lock_from_env(args: &Args, item: TokenStream) -> TokenStream {
+    let path = match env::var_os("MACRO_PUB_LOCKFILE") {
+        Some(path) => path,
+        None => return TokenStream::new(),
+    };
+    // Each crate has its own lock file, relative to its `Cargo.toml`.
+    let path = match env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => Path::new(&dir).join(path),
+        None => PathBuf::from(path),
+    };
+    let mode = match env::var("MACRO_PUB_LOCKFILE_MODE") {
+        Err(env::VarError::NotPresent) => Mode::Error,
+        Ok(ref mode) if mode == "error" => Mode::Error,
+        Ok(ref mode) if mode == "warn" => Mode::Warn,
+        Ok(ref mode) if mode == "update" => Mode::Update,
+        _ => {
+            return "compile_error! { \"`MACRO_PUB_LOCKFILE_MODE` expects `error`, `warn`, or `update`\" }"
+                .parse()
+                .unwrap()
+        }
+    };
+    let name = match MacroRules::parse(item.clone()) {
+        Some(parsed) => parsed.name,
+        None => return TokenStream::new(),
+    };
+    let mangled = match mangled_name(
+        macro_hash(&item, args.salt.as_deref(), args.hash_docs),
+        &name.to_string(),
+        &args.restriction,
+        args.strategy,
+        args.mangle.as_ref(),
+    ) {
+        Some(mangled) => mangled,
+        None => return TokenStream::new(),
+    };
+    // Macros of the same name are told apart by the file they're defined in,
+    // or the path `expect_path` gives, which also tells apart those in one
+    // file's modules.
+    let file = defining_file();
+    let key = match (&args.expect_path, &file) {
+        (Some(path), _) => path.to_string().replace(' ', ""),
+        (None, Some(file)) => format!("{}:{}", file, name),
+        (None, None) => name.to_string(),
+    };
+    let locked = match lock(&path, &key, &mangled, mode == Mode::Update) {
+        Ok(None) => return TokenStream::new(),
+        Ok(Some(locked)) => locked,
+        Err(message) => {
+            return format!("compile_error! {{ {:?} }}", message)
+                .parse()
+                .unwrap()
+        }
+    };
+    let mut message = format!(
+        "`#[macro_pub]` macro `{}!` is now exported as `{}`, but `MACRO_PUB_LOCKFILE` locks it \
+         as `{}`, which crates using its hidden path may rely on; build with \
+         `MACRO_PUB_LOCKFILE_MODE=update` to accept the change",
+        name, mangled, locked,
+    );
+    if args.expect_path.is_none() {
+        message.push_str(&format!(
+            ", or, if another macro named `{0}!`{1} is locked, give each an \
+             `expect_path = \"crate::path::to::{0}\"` to lock them apart",
+            name,
+            if file.is_some() { " in this file" } else { "" },
+        ));
+    }
+    if mode == Mode::Warn {
+        warning(&message, name.span())
+    } else {
+        spanned_error(&message, name.span())
+    }
+}
+
+/// Looks up `key`, which identifies a macro, in the lock file at `path`,
+/// returning the mangled name it's locked as if that isn't `mangled`. New keys
+/// are added, and changed ones replaced if `update` is set.
+fn lock(path: &Path, key: &str, mangled: &str, update: bool) -> Result<Option<String>, String> {
+    let io_error = |error: std::io::Error| {
+        format!(
+            "`#[macro_pub]` could not update `MACRO_PUB_LOCKFILE` at {}: {}",
+            path.display(),
+            error,
+        )
+    };
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(ref error) if error.kind() == ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(io_error(error)),
+    };
+    let prefix = format!("{} ", key);
+    let locked = contents.lines().find(|line| line.starts_with(&prefix));
+    match locked {
+        Some(line) if &line[prefix.len()..] == mangled => Ok(None),
+        Some(line) if !update => Ok(Some(line[prefix.len()..].to_string())),
+        Some(_) => {
+            let mut updated = String::new();
+            for line in contents.lines() {
+                if line.starts_with(&prefix) {
+                    updated.push_str(&prefix);
+                    updated.push_str(mangled);
+                } else {
+                    updated.push_str(line);
+                }
+                updated.push('\n');
+            }
+            fs::write(path, updated).map_err(io_error)?;
+            Ok(None)
+        }
+        None => OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}{}", prefix, mangled))
+            .map(|()| None)
+            .map_err(io_error),
+    }
+}
+
+/// The file the macro being expanded is defined in, relative to the crate's
+/// `Cargo.toml`, where the compiler can tell.
+#[cfg(has_span_local_file)]
+#[clippy::msrv = "1.88"]
+fn defining_file() -> Option<String> {
+    let file = fs::canonicalize(proc_macro::Span::call_site().local_file()?).ok()?;
+    let file = env::var_os("CARGO_MANIFEST_DIR")
+        .and_then(|dir| fs::canonicalize(dir).ok())
+        .and_then(|dir| file.strip_prefix(dir).ok().map(Path::to_path_buf))
+        .unwrap_or(file);
+    Some(file.to_string_lossy().replace('\\', "/"))
+}
+
+#[cfg(not(has_span_local_file))]
+fn defining_file() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locks_names() {
+        let path = env::temp_dir().join(format!("macro_pub-lock-{}", std::process::id()));
+        assert_eq!(lock(&path, "m", "macro_impl_1_m", false), Ok(None));
+        assert_eq!(lock(&path, "n", "macro_impl_2_n", false), Ok(None));
+        assert_eq!(lock(&path, "m", "macro_impl_1_m", false), Ok(None));
+        assert_eq!(
+            lock(&path, "m", "macro_impl_3_m", false),
+            Ok(Some("macro_impl_1_m".to_string())),
+        );
+        assert_eq!(lock(&path, "m", "macro_impl_3_m", true), Ok(None));
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, "m macro_impl_3_m\nn macro_impl_2_n\n");
+    }
+
+    #[test]
+    fn locks_same_names_by_path() {
+        let path = env::temp_dir().join(format!("macro_pub-lock-paths-{}", std::process::id()));
+        assert_eq!(
+            lock(&path, "crate::a::m", "macro_impl_1_m", false),
+            Ok(None)
+        );
+        assert_eq!(
+            lock(&path, "crate::b::m", "macro_impl_2_m", false),
+            Ok(None)
+        );
+        assert_eq!(
+            lock(&path, "crate::a::m", "macro_impl_1_m", false),
+            Ok(None)
+        );
+        assert_eq!(lock(&path, "crate::b::m", "macro_impl_2_m", true), Ok(None));
+        assert_eq!(lock(&path, "crate::a::m", "macro_impl_1_m", true), Ok(None));
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            written,
+            "crate::a::m macro_impl_1_m\ncrate::b::m macro_impl_2_m\n",
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let dir = env::temp_dir();
+        let error = lock(&dir, "m", "macro_impl_1_m", false).unwrap_err();
+        assert!(
+            error.starts_with("`#[macro_pub]` could not update `MACRO_PUB_LOCKFILE` at "),
+            "{}",
+            error,
+        );
+    }
+}
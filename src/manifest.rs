@@ -0,0 +1,376 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use proc_macro2::{TokenStream, TokenTree};
+
+use crate::expand::{last_level, lint_warning, macro_hash, mangled_name, visibility, Config};
+use crate::parse::{Args, Level, MacroRules, Mangle, Strategy};
+
+/// Records `#[macro_pub(args)] item` in the manifest at `MACRO_PUB_MANIFEST`,
+/// if it's set, or returns an error to emit if it can't be written.
+pub(crate) fn record_from_env(args: &Args, item: TokenStream) -> TokenStream {
+    let path = match env::var_os("MACRO_PUB_MANIFEST") {
+        Some(path) => path,
+        None => return TokenStream::new(),
+    };
+    let krate = env::var("CARGO_CRATE_NAME").unwrap_or_default();
+    match record(path.as_ref(), &krate, args, item) {
+        Ok(()) => TokenStream::new(),
+        Err(message) => format!("compile_error! {{ {:?} }}", message)
+            .parse()
+            .unwrap(),
+    }
+}
+
+/// Appends the manifest entry for `#[macro_pub(args)] item`, if it's valid,
+/// to the file at `path`.
+fn record(path: &Path, krate: &str, args: &Args, item: TokenStream) -> Result<(), String> {
+    match entry(krate, args, item) {
+        Some(entry) => append(path, &entry),
+        None => Ok(()),
+    }
+}
+
+/// Expands `macro_pub::track_use! { "mangled" tokens }` to `tokens`,
+/// recording a use of the macro exported as `mangled` in the manifest at
+/// `MACRO_PUB_MANIFEST`, if it's set.
+pub(crate) fn track_use_from_env(input: TokenStream) -> TokenStream {
+    let mut tokens = input.into_iter();
+    let mangled = match tokens.next() {
+        Some(TokenTree::Literal(mangled)) => mangled.to_string(),
+        _ => {
+            return "compile_error! { \"`macro_pub::track_use!` is only for `#[macro_pub(track_uses)]` macros\" }"
+                .parse()
+                .unwrap()
+        }
+    };
+    if let Some(path) = env::var_os("MACRO_PUB_MANIFEST") {
+        // Mangled names are identifiers, so the literal is also a JSON string.
+        let krate = env::var("CARGO_CRATE_NAME").unwrap_or_default();
+        let line = format!("{{\"crate\":{},\"uses\":{}}}\n", string(&krate), mangled);
+        if let Err(message) = append(path.as_ref(), &line) {
+            return format!("compile_error! {{ {:?} }}", message)
+                .parse()
+                .unwrap();
+        }
+    }
+    tokens.collect()
+}
+
+/// Warns if `#[macro_pub(args)] item` tracks its uses, is world-public, and
+/// the manifest at `MACRO_PUB_MANIFEST` records uses of it only in the crate
+/// defining it.
+pub(crate) fn narrowing_from_env(args: &Args, item: TokenStream) -> TokenStream {
+    let path = match env::var_os("MACRO_PUB_MANIFEST") {
+        Some(path) => path,
+        None => return TokenStream::new(),
+    };
+    let krate = env::var("CARGO_CRATE_NAME").unwrap_or_default();
+    // A manifest that hasn't been written yet records no uses.
+    let manifest = fs::read_to_string(&path).unwrap_or_default();
+    let name = match MacroRules::parse(item.clone()) {
+        Some(parsed) => parsed.name,
+        None => return TokenStream::new(),
+    };
+    let mangled = match mangled_name(
+        macro_hash(&item, args.salt.as_deref(), args.hash_docs),
+        &name.to_string(),
+        &args.restriction,
+        args.strategy,
+        args.mangle.as_ref(),
+    ) {
+        Some(ref mangled) if args.track_uses => mangled.clone(),
+        _ => return TokenStream::new(),
+    };
+    let config = Config::detected();
+    let crate_levels = config.lint_levels.clone().unwrap_or_default();
+    let level = last_level(&args.lint_levels, "narrowable")
+        .or_else(|| last_level(&crate_levels, "narrowable"))
+        .unwrap_or(Level::Warn);
+    let level = config.cap(level);
+    let users = users(&manifest, &mangled);
+    if users.is_empty() || users.iter().any(|user| *user != string(&krate)) {
+        return TokenStream::new();
+    }
+    lint_warning(
+        level,
+        &format!(
+            "`#[macro_pub]` macro `{}!` is `pub`, but `MACRO_PUB_MANIFEST` only records uses \
+             of it in `{}`; consider `#[macro_pub(crate)]`",
+            name, krate,
+        ),
+        name.span(),
+    )
+}
+
+/// The crates, as JSON strings, that `manifest` records using the macro
+/// exported as `mangled`.
+fn users<'a>(manifest: &'a str, mangled: &str) -> Vec<&'a str> {
+    let prefix = "{\"crate\":";
+    let suffix = format!(",\"uses\":{}}}", string(mangled));
+    manifest
+        .lines()
+        .filter(|line| line.starts_with(prefix) && line.ends_with(&suffix))
+        .map(|line| &line[prefix.len()..line.len() - suffix.len()])
+        .collect()
+}
+
+/// Appends `line` to the manifest at `path`.
+fn append(path: &Path, line: &str) -> Result<(), String> {
+    // Crates compile in parallel, so append each line with a single write.
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|error| {
+            format!(
+                "`#[macro_pub]` could not write to `MACRO_PUB_MANIFEST` at {}: {}",
+                path.display(),
+                error,
+            )
+        })
+}
+
+/// The manifest entry describing `#[macro_pub(attr)] item`, defined in
+/// `krate`: a line of JSON like
+///
+/// ```text
+/// {"crate":"krate","name":"m","visibility":"pub","mangled":"macro_impl_HASH_m",
+///  "export_path":"krate::macro_impl_HASH_m","export_as":null,"deprecate_as":null,
+///  "aliases":[],"hygienic_as":null,"friends":[],"since":null,"hidden":false,
+///  "unstable":false,"feature":null,"legacy_name":false,"strategy":null,"metadata":false,"no_alias":false,
+///  "unsafe_macro":false,"track_uses":false,"lint":false,"no_std":false,
+///  "package":false,"doctest":false,"selfcheck":false,"source":false,
+///  "hide_arms":false,"internal_rules":false,"textual":false,"no_textual":false,
+///  "shadowable":false,"doc_required":false,"catch_all":false,"hash_docs":false,
+///  "salt":null,"mangle":null,"expect_path":null,"lint_levels":[]}
+/// ```
+///
+/// `mangled` is `null` for macros that aren't exported under a mangled name,
+/// and `export_path` for those that aren't exported at all.
+/// The path the macro is re-exported at isn't known to the attribute.
+fn entry(krate: &str, args: &Args, item: TokenStream) -> Option<String> {
+    let parsed = MacroRules::parse(item.clone())?;
+    let name = parsed.name.to_string();
+    let hash = macro_hash(&item, args.salt.as_deref(), args.hash_docs);
+
+    let visibility = visibility(args.restriction.clone());
+    let mangled = mangled_name(
+        hash,
+        &name,
+        &args.restriction,
+        args.strategy,
+        args.mangle.as_ref(),
+    );
+    let export_path = match args.strategy {
+        _ if !args.restriction.is_empty() => None,
+        Some(Strategy::DeclMacro) => None,
+        Some(Strategy::LegacyExport) => Some(format!("{}::{}", krate, name)),
+        _ => Some(format!("{}::{}", krate, mangled.as_ref().unwrap())),
+    };
+    let lint_levels = args
+        .lint_levels
+        .iter()
+        .map(|&(ref lint, level)| {
+            let level = match level {
+                Level::Allow => "allow",
+                Level::Warn => "warn",
+                Level::Deny => "deny",
+            };
+            string(&format!("{}({})", level, lint))
+        })
+        .collect::<Vec<_>>();
+    let aliases = args
+        .aliases
+        .iter()
+        .map(|alias| string(&alias.to_string()))
+        .collect::<Vec<_>>();
+    let friends = args
+        .friends
+        .iter()
+        .map(|friend| string(friend))
+        .collect::<Vec<_>>();
+
+    Some(format!(
+        "{{\"crate\":{},\"name\":{},\"visibility\":{},\"mangled\":{},\"export_path\":{},\
+         \"export_as\":{},\"deprecate_as\":{},\"aliases\":[{}],\"hygienic_as\":{},\"friends\":[{}],\"since\":{},\"hidden\":{},\
+         \"unstable\":{},\"feature\":{},\"legacy_name\":{},\"strategy\":{},\"metadata\":{},\
+         \"no_alias\":{},\"unsafe_macro\":{},\"track_uses\":{},\"lint\":{},\"no_std\":{},\
+         \"package\":{},\"doctest\":{},\"selfcheck\":{},\
+         \"source\":{},\"hide_arms\":{},\"internal_rules\":{},\"textual\":{},\"no_textual\":{},\
+         \"shadowable\":{},\"doc_required\":{},\"catch_all\":{},\"hash_docs\":{},\"salt\":{},\"mangle\":{},\"expect_path\":{},\"lint_levels\":[{}]}}\n",
+        string(krate),
+        string(&name),
+        string(&visibility),
+        optional(mangled),
+        optional(export_path),
+        optional(args.export_as.as_ref()),
+        optional(args.deprecate_as.as_ref()),
+        aliases.join(","),
+        optional(args.hygienic_as.as_ref()),
+        friends.join(","),
+        optional(args.since.as_ref()),
+        args.hidden || parsed.hidden,
+        args.unstable,
+        optional(args.feature.as_ref()),
+        args.also_export_legacy_name,
+        optional(args.strategy.map(|strategy| match strategy {
+            Strategy::UseAlias => "use_alias",
+            Strategy::DeclMacro => "decl_macro",
+            Strategy::LegacyExport => "legacy_export",
+        })),
+        args.metadata,
+        args.no_alias,
+        args.unsafe_macro,
+        args.track_uses,
+        args.lint,
+        args.no_std,
+        args.package,
+        args.doctest,
+        args.selfcheck,
+        args.source,
+        args.hide_arms,
+        args.internal_rules,
+        args.textual,
+        args.no_textual,
+        args.shadowable,
+        args.doc_required,
+        args.catch_all,
+        args.hash_docs,
+        optional(args.salt.as_ref()),
+        match args.mangle {
+            Some(Mangle::Prefix(ref prefix)) => string(prefix),
+            Some(Mangle::Off) => "false".to_string(),
+            None => "null".to_string(),
+        },
+        optional(
+            args.expect_path.as_ref()
+                .map(|path| path.to_string().replace(' ', ""))
+        ),
+        lint_levels.join(","),
+    ))
+}
+
+/// `value` as a JSON string, or `null`.
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or("null".to_string(), |value| string(&value.to_string()))
+}
+
+/// `value` as a JSON string.
+fn string(value: &str) -> String {
+    let mut json = "\"".to_string();
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn assert_entry(attr: &str, item: &str, expected: Option<&str>) {
+        let hash = macro_hash(&item.parse().unwrap(), None, false);
+        let expected = expected.map(|expected| expected.replace("HASH", &hash.to_string()) + "\n");
+        assert_eq!(
+            Args::parse(attr.parse().unwrap())
+                .ok()
+                .and_then(|args| entry("krate", &args, item.parse().unwrap())),
+            expected,
+        );
+    }
+
+    #[test]
+    fn entries() {
+        assert_entry(
+            "",
+            "macro_rules! m { () => {}; }",
+            Some(concat!(
+                r#"{"crate":"krate","name":"m","visibility":"pub","mangled":"macro_impl_HASH_m","#,
+                r#""export_path":"krate::macro_impl_HASH_m","export_as":null,"deprecate_as":null,"#,
+                r#""aliases":[],"hygienic_as":null,"friends":[],"since":null,"hidden":false,"unstable":false,"feature":null,"#,
+                r#""legacy_name":false,"strategy":null,"metadata":false,"no_alias":false,"#,
+                r#""unsafe_macro":false,"track_uses":false,"lint":false,"no_std":false,"#,
+                r#""package":false,"doctest":false,"selfcheck":false,"source":false,"#,
+                r#""hide_arms":false,"internal_rules":false,"textual":false,"no_textual":false,"#,
+                r#""shadowable":false,"doc_required":false,"catch_all":false,"hash_docs":false,"salt":null,"#,
+                r#""mangle":null,"expect_path":null,"lint_levels":[]}"#,
+            )),
+        );
+        assert_entry(
+            concat!(
+                r#"in crate::a, export_as = b, deprecate_as = "c", alias = "h", hygienic_as = "g","#,
+                r#"friends("d-e"), since = "1.0","#,
+                r#"unstable, feature = "f", also_export_legacy_name, strategy = "use_alias", salt = "v2","#,
+                r#"mangle = "mine","#,
+                r#"expect_path = "crate::a::m", deny(std_names), allow(narrowable)"#,
+            ),
+            "macro_rules! m { () => {}; }",
+            Some(concat!(
+                r#"{"crate":"krate","name":"m","visibility":"pub(in crate::a)","mangled":null,"#,
+                r#""export_path":null,"export_as":"b","deprecate_as":"c","aliases":["h"],"#,
+                r#""hygienic_as":"g","friends":["d_e"],"#,
+                r#""since":"1.0","hidden":false,"unstable":true,"feature":"f","#,
+                r#""legacy_name":true,"strategy":"use_alias","metadata":false,"no_alias":false,"#,
+                r#""unsafe_macro":false,"track_uses":false,"lint":false,"no_std":false,"#,
+                r#""package":false,"doctest":false,"selfcheck":false,"source":false,"#,
+                r#""hide_arms":false,"internal_rules":false,"textual":false,"no_textual":false,"#,
+                r#""shadowable":false,"doc_required":false,"catch_all":false,"hash_docs":false,"salt":"v2","#,
+                r#""mangle":"mine","expect_path":"crate::a::m","lint_levels":["deny(std_names)","allow(narrowable)"]}"#,
+            )),
+        );
+    }
+
+    #[test]
+    fn strategies() {
+        assert_entry(
+            r#"strategy = "legacy_export""#,
+            "macro_rules! m { () => {}; }",
+            Some(concat!(
+                r#"{"crate":"krate","name":"m","visibility":"pub","mangled":null,"#,
+                r#""export_path":"krate::m","export_as":null,"deprecate_as":null,"#,
+                r#""aliases":[],"hygienic_as":null,"friends":[],"since":null,"hidden":false,"unstable":false,"feature":null,"legacy_name":false,"#,
+                r#""strategy":"legacy_export","metadata":false,"no_alias":false,"unsafe_macro":false,"#,
+                r#""track_uses":false,"lint":false,"no_std":false,"#,
+                r#""package":false,"doctest":false,"selfcheck":false,"source":false,"#,
+                r#""hide_arms":false,"internal_rules":false,"textual":false,"no_textual":false,"#,
+                r#""shadowable":false,"doc_required":false,"catch_all":false,"hash_docs":false,"salt":null,"#,
+                r#""mangle":null,"expect_path":null,"lint_levels":[]}"#,
+            )),
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert_entry("", "fn f() {}", None);
+        assert_entry("pub", "macro_rules! m { () => {}; }", None);
+    }
+
+    #[test]
+    fn uses() {
+        let manifest = concat!(
+            "{\"crate\":\"a\",\"name\":\"m\",\"visibility\":\"pub\"}\n",
+            "{\"crate\":\"a\",\"uses\":\"macro_impl_1_m\"}\n",
+            "{\"crate\":\"b\",\"uses\":\"macro_impl_1_m\"}\n",
+            "{\"crate\":\"b\",\"uses\":\"macro_impl_2_m\"}\n",
+        );
+        assert_eq!(users(manifest, "macro_impl_1_m"), ["\"a\"", "\"b\""]);
+        assert_eq!(users(manifest, "macro_impl_2_m"), ["\"b\""]);
+        assert!(users(manifest, "macro_impl_3_m").is_empty());
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(string("a\"b\\c\n"), r#""a\"b\\c\u000a""#);
+    }
+}
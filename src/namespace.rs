@@ -0,0 +1,236 @@
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use crate::cfg_macros::is_macro_pub;
+use crate::index::{attrs, Tokens};
+
+/// Expands `namespace! { modules }`.
+///
+/// The input is one or more modules whose bodies define `macro_rules!` macros,
+/// optionally with `#[macro_pub(...)]` attributes, and nested `mod`s of more
+/// of them:
+///
+/// ```text
+/// /// Networking macros.
+/// pub mod net {
+///     /// Retries an expression.
+///     macro_rules! retry { ... }
+///     /// HTTP macros.
+///     mod http {
+///         #[macro_pub(crate)]
+///         macro_rules! get { ... }
+///     }
+/// }
+/// ```
+///
+/// Each module is defined as written, and each nested one as a `pub mod`. Each
+/// macro gets `#[::macro_pub::macro_pub]`, with the arguments of its own
+/// `#[macro_pub(...)]`, if any.
+pub(crate) fn namespace(input: TokenStream) -> TokenStream {
+    let mut tokens = input.into_iter().peekable();
+    let mut output = TokenStream::new();
+    while tokens.peek().is_some() {
+        output.extend(attrs(&mut tokens));
+        loop {
+            match tokens.next() {
+                Some(TokenTree::Ident(ident)) if ident == "mod" => {
+                    output.extend(Some(TokenTree::Ident(ident)));
+                    break;
+                }
+                Some(tt) => output.extend(Some(tt)),
+                None => return error(EXPECTED),
+            }
+        }
+        match module(&mut tokens) {
+            Ok(module) => output.extend(module),
+            Err(message) => return error(message),
+        }
+    }
+    if output.is_empty() {
+        return error(EXPECTED);
+    }
+    output
+}
+
+const EXPECTED: &str = "`macro_pub::namespace!` expects modules like `pub mod name { ... }`";
+const EXPECTED_ITEM: &str =
+    "`macro_pub::namespace!` expects `macro_rules!` macros and `mod`s in its modules";
+
+/// Expands `name { items }`, after the `mod` keyword.
+fn module(tokens: &mut Tokens) -> Result<TokenStream, &'static str> {
+    let (name, body) = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(name)), Some(TokenTree::Group(body)))
+            if body.delimiter() == Delimiter::Brace =>
+        {
+            (name, body)
+        }
+        _ => return Err(EXPECTED),
+    };
+
+    let mut items = body.stream().into_iter().peekable();
+    let mut contents = TokenStream::new();
+    while items.peek().is_some() {
+        let attrs = attrs(&mut items);
+        match items.next() {
+            Some(TokenTree::Ident(ident)) if ident == "mod" => {
+                contents.extend(attrs);
+                contents.extend(vec![
+                    TokenTree::Ident(Ident::new("pub", Span::call_site())),
+                    TokenTree::Ident(ident),
+                ]);
+                contents.extend(module(&mut items)?);
+            }
+            Some(TokenTree::Ident(macro_rules)) if macro_rules == "macro_rules" => {
+                match (items.next(), items.next(), items.next()) {
+                    (
+                        Some(TokenTree::Punct(bang)),
+                        Some(TokenTree::Ident(name)),
+                        Some(TokenTree::Group(arms)),
+                    ) if bang.as_char() == '!' && arms.delimiter() != Delimiter::None => {
+                        contents.extend(macro_pub_attrs(attrs)?);
+                        let braced = arms.delimiter() == Delimiter::Brace;
+                        contents.extend(vec![
+                            TokenTree::Ident(macro_rules),
+                            TokenTree::Punct(bang),
+                            TokenTree::Ident(name),
+                            TokenTree::Group(arms),
+                        ]);
+                        if !braced {
+                            match items.next() {
+                                Some(TokenTree::Punct(semi)) if semi.as_char() == ';' => {
+                                    contents.extend(Some(TokenTree::Punct(semi)));
+                                }
+                                _ => return Err(EXPECTED_ITEM),
+                            }
+                        }
+                    }
+                    _ => return Err(EXPECTED_ITEM),
+                }
+            }
+            _ => return Err(EXPECTED_ITEM),
+        }
+    }
+
+    let body = Group::new(Delimiter::Brace, contents);
+    Ok(vec![TokenTree::Ident(name), TokenTree::Group(body)]
+        .into_iter()
+        .collect())
+}
+
+/// Names the `#[macro_pub(...)]` in a macro's `attrs` by its full path, or
+/// adds a `#[macro_pub]` before them if there isn't one.
+fn macro_pub_attrs(attrs: TokenStream) -> Result<TokenStream, &'static str> {
+    let mut output = TokenStream::new();
+    let mut found = false;
+    let mut tokens = attrs.into_iter();
+    while let Some(pound) = tokens.next() {
+        let attr = match tokens.next() {
+            Some(TokenTree::Group(attr)) if is_macro_pub(&attr) => attr,
+            attr => {
+                output.extend(Some(pound));
+                output.extend(attr);
+                continue;
+            }
+        };
+        if found {
+            return Err("`macro_pub::namespace!` expects one `#[macro_pub]` on each macro");
+        }
+        found = true;
+        let mut path: TokenStream = "::macro_pub::macro_pub".parse().unwrap();
+        path.extend(attr.stream().into_iter().skip(1));
+        output.extend(vec![
+            pound,
+            TokenTree::Group(Group::new(Delimiter::Bracket, path)),
+        ]);
+    }
+    if found {
+        return Ok(output);
+    }
+    let mut attr = vec![
+        TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+        TokenTree::Group(Group::new(
+            Delimiter::Bracket,
+            "::macro_pub::macro_pub".parse().unwrap(),
+        )),
+    ]
+    .into_iter()
+    .collect::<TokenStream>();
+    attr.extend(output);
+    Ok(attr)
+}
+
+fn error(message: &str) -> TokenStream {
+    format!("compile_error! {{ {:?} }}", message)
+        .parse()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn assert_expands(input: &str, expected: &str) {
+        assert_eq!(
+            namespace(input.parse().unwrap()).to_string(),
+            expected.parse::<TokenStream>().unwrap().to_string(),
+        );
+    }
+
+    #[test]
+    fn modules() {
+        assert_expands(
+            r#"
+                /// Networking.
+                pub mod net {
+                    /// Retries.
+                    macro_rules! retry { ($e:expr) => { $e }; }
+                    macro_rules! wait [ () => {}; ];
+                    /// HTTP.
+                    mod http {
+                        #[cfg(all())]
+                        #[macro_pub(crate, signatures)]
+                        macro_rules! get { () => {}; }
+                    }
+                }
+                pub(crate) mod fs {}
+            "#,
+            r#"
+                #[doc = " Networking."]
+                pub mod net {
+                    #[::macro_pub::macro_pub]
+                    #[doc = " Retries."]
+                    macro_rules! retry { ($e:expr) => { $e }; }
+                    #[::macro_pub::macro_pub]
+                    macro_rules! wait [ () => {}; ];
+                    #[doc = " HTTP."]
+                    pub mod http {
+                        #[cfg(all())]
+                        #[::macro_pub::macro_pub(crate, signatures)]
+                        macro_rules! get { () => {}; }
+                    }
+                }
+                pub(crate) mod fs {}
+            "#,
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let expected = format!("compile_error! {{ {:?} }}", EXPECTED);
+        for input in &["", "pub net {}", "mod net;", "mod net {} fn f() {}"] {
+            assert_expands(input, &expected);
+        }
+        let expected = format!("compile_error! {{ {:?} }}", EXPECTED_ITEM);
+        for input in &[
+            "mod net { fn f() {} }",
+            "mod net { macro_rules! m; }",
+            "mod net { macro_rules! m () }",
+        ] {
+            assert_expands(input, &expected);
+        }
+        assert_expands(
+            "mod net { #[macro_pub] #[macro_pub(crate)] macro_rules! m {} }",
+            r#"compile_error! { "`macro_pub::namespace!` expects one `#[macro_pub]` on each macro" }"#,
+        );
+    }
+}
@@ -0,0 +1,2018 @@
+use std::mem;
+
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// A `macro_rules!` definition annotated with `#[macro_pub]`.
+pub(crate) struct MacroRules {
+    /// Outer attributes, including doc comments.
+    pub(crate) attrs: TokenStream,
+    /// `#[allow_internal_unstable]` and `#[allow_internal_unsafe]`, which
+    /// only belong on definitions used by other crates, not documentation.
+    pub(crate) internal_attrs: TokenStream,
+    /// Whether `attrs` include `#[doc(hidden)]`.
+    pub(crate) hidden: bool,
+    /// Attributes that only apply to other items, like `#[inline]`, which
+    /// aren't in `attrs`.
+    pub(crate) item_attrs: Vec<TokenStream>,
+    /// Whether the macro was `#[macro_export(local_inner_macros)]`, which
+    /// isn't in `attrs`.
+    pub(crate) local_inner_macros: bool,
+    pub(crate) macro_rules: Ident,
+    pub(crate) bang: Punct,
+    pub(crate) name: Ident,
+    /// The contents of the body, in any delimiters, without any `arm_attrs`.
+    pub(crate) arms: TokenStream,
+    /// Attributes, including doc comments, placed before individual arms,
+    /// which neither `macro_rules!` nor `macro` accept.
+    pub(crate) arm_attrs: Vec<TokenStream>,
+    /// The indices of the arms marked `#[doc(hidden)]`, which aren't in
+    /// `arm_attrs`, and the spans of the attributes.
+    pub(crate) hidden_arms: Vec<(usize, Span)>,
+    /// The indices of the arms marked `#[doc(cfg(...))]`, which aren't in
+    /// `arm_attrs`, and the predicates they're documented as needing.
+    pub(crate) arm_cfgs: Vec<(usize, TokenStream)>,
+    /// Anything following the definition, which is passed through untouched.
+    pub(crate) rest: TokenStream,
+}
+
+impl MacroRules {
+    /// Parses `item`, or returns `None` if it isn't a `macro_rules!` macro.
+    pub(crate) fn parse(item: TokenStream) -> Option<Self> {
+        let mut attrs = TokenStream::new();
+        let mut internal_attrs = TokenStream::new();
+        let mut hidden = false;
+        let mut item_attrs = Vec::new();
+        let mut local_inner_macros = false;
+        let mut tokens = item.into_iter();
+
+        let macro_rules = loop {
+            match tokens.next()? {
+                TokenTree::Ident(ident) if ident == "macro_rules" => break ident,
+                // #[attribute]
+                TokenTree::Punct(punct) if punct.as_char() == '#' => match tokens.next()? {
+                    TokenTree::Group(group) if group.delimiter() == Delimiter::Bracket => {
+                        let attr = vec![TokenTree::Punct(punct), TokenTree::Group(group.clone())];
+                        if is_internal_attr(&group) {
+                            internal_attrs.extend(attr);
+                        } else if is_item_attr(&group) {
+                            item_attrs.push(attr.into_iter().collect());
+                        } else if is_local_inner_macros(&group) {
+                            local_inner_macros = true;
+                        } else {
+                            hidden |= is_doc_hidden(&group);
+                            attrs.extend(attr);
+                        }
+                    }
+                    _ => return None,
+                },
+                _ => return None,
+            }
+        };
+
+        let bang = match tokens.next()? {
+            TokenTree::Punct(punct) if punct.as_char() == '!' => punct,
+            _ => return None,
+        };
+
+        let name = match tokens.next()? {
+            TokenTree::Ident(ident) => ident,
+            _ => return None,
+        };
+
+        // `macro_rules! name(...);` and `macro_rules! name[...];` are the same
+        // macro as `macro_rules! name {...}`, which they're expanded as.
+        let (arms, arm_attrs, hidden_arms, arm_cfgs) = match tokens.next()? {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                strip_arm_attrs(group.stream())
+            }
+            TokenTree::Group(group) if group.delimiter() != Delimiter::None => {
+                match tokens.next()? {
+                    TokenTree::Punct(ref semi) if semi.as_char() == ';' => {
+                        strip_arm_attrs(group.stream())
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        Some(MacroRules {
+            attrs,
+            internal_attrs,
+            hidden,
+            item_attrs,
+            local_inner_macros,
+            macro_rules,
+            bang,
+            name,
+            arms,
+            arm_attrs,
+            hidden_arms,
+            arm_cfgs,
+            rest: tokens.collect(),
+        })
+    }
+}
+
+/// Checks whether `attr`, the `[...]` of an attribute, is
+/// `allow_internal_unstable` or `allow_internal_unsafe`.
+fn is_internal_attr(attr: &Group) -> bool {
+    match attr.stream().into_iter().next() {
+        Some(TokenTree::Ident(name)) => {
+            name == "allow_internal_unstable" || name == "allow_internal_unsafe"
+        }
+        _ => false,
+    }
+}
+
+/// Attributes for functions, types, and other items, which rustc ignores or
+/// rejects on macros.
+const ITEM_ATTRS: &[&str] = &[
+    "inline",
+    "cold",
+    "track_caller",
+    "must_use",
+    "no_mangle",
+    "export_name",
+    "link_section",
+    "used",
+    "target_feature",
+    "repr",
+    "derive",
+    "non_exhaustive",
+    "test",
+    "ignore",
+    "should_panic",
+];
+
+/// Checks whether `attr`, the `[...]` of an attribute, is one of
+/// [`ITEM_ATTRS`].
+fn is_item_attr(attr: &Group) -> bool {
+    match attr.stream().into_iter().next() {
+        Some(TokenTree::Ident(name)) => ITEM_ATTRS.contains(&&*name.to_string()),
+        _ => false,
+    }
+}
+
+/// The matchers of each arm in `arms`.
+pub(crate) fn matchers(arms: TokenStream) -> Vec<Group> {
+    let mut matchers = Vec::new();
+    let mut arm_start = true;
+    for tt in arms {
+        match tt {
+            TokenTree::Group(group) if arm_start => matchers.push(group),
+            TokenTree::Punct(punct) if punct.as_char() == ';' => {
+                arm_start = true;
+                continue;
+            }
+            _ => {}
+        }
+        arm_start = false;
+    }
+    matchers
+}
+
+/// Each arm in `arms`, ending with `;`.
+pub(crate) fn arms(arms: TokenStream) -> Vec<TokenStream> {
+    let mut split = Vec::new();
+    let mut arm = TokenStream::new();
+    for tt in arms {
+        let end = match tt {
+            TokenTree::Punct(ref punct) => punct.as_char() == ';',
+            _ => false,
+        };
+        arm.extend(Some(tt));
+        if end {
+            split.push(mem::replace(&mut arm, TokenStream::new()));
+        }
+    }
+    if !arm.is_empty() {
+        arm.extend(Some(TokenTree::Punct(Punct::new(';', Spacing::Alone))));
+        split.push(arm);
+    }
+    split
+}
+
+/// The transcribers, after `=>`, of each arm in `arms`.
+pub(crate) fn transcribers(arms: TokenStream) -> Vec<Group> {
+    let mut transcribers = Vec::new();
+    let mut after_arrow = false;
+    for tt in arms {
+        match tt {
+            TokenTree::Group(group) if after_arrow => transcribers.push(group),
+            TokenTree::Punct(ref punct) if punct.as_char() == '>' => {
+                after_arrow = true;
+                continue;
+            }
+            _ => {}
+        }
+        after_arrow = false;
+    }
+    transcribers
+}
+
+/// The arms without their attributes, the attributes, the hidden arms, and the
+/// arms' `doc(cfg)` predicates, as separated by [`strip_arm_attrs`].
+type StrippedArms = (
+    TokenStream,
+    Vec<TokenStream>,
+    Vec<(usize, Span)>,
+    Vec<(usize, TokenStream)>,
+);
+
+/// Removes `#[attribute]`s from the start of each arm of `arms`, separating
+/// out `#[doc(hidden)]` and `#[doc(cfg(...))]` by the index of their arm.
+fn strip_arm_attrs(arms: TokenStream) -> StrippedArms {
+    let mut stripped = TokenStream::new();
+    let mut attrs = Vec::new();
+    let mut hidden = Vec::new();
+    let mut cfgs = Vec::new();
+    let mut tokens = arms.into_iter().peekable();
+    let mut arm_start = true;
+    let mut arm = 0;
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Punct(ref punct) if arm_start && punct.as_char() == '#' => {
+                match tokens.peek() {
+                    Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => {
+                        if is_doc_hidden(group) {
+                            hidden.push((arm, group.span()));
+                            tokens.next();
+                        } else if let Some(predicate) = doc_cfg(group) {
+                            cfgs.push((arm, predicate));
+                            tokens.next();
+                        } else {
+                            attrs.push(vec![tt, tokens.next().unwrap()].into_iter().collect());
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        arm_start = match tt {
+            TokenTree::Punct(ref punct) => punct.as_char() == ';',
+            _ => false,
+        };
+        if arm_start {
+            arm += 1;
+        }
+        stripped.extend(Some(tt));
+    }
+    (stripped, attrs, hidden, cfgs)
+}
+
+/// Checks whether `attr`, the `[...]` of an attribute, is
+/// `macro_export(local_inner_macros)`.
+fn is_local_inner_macros(attr: &Group) -> bool {
+    let mut tokens = attr.stream().into_iter();
+    match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(export)), Some(TokenTree::Group(args)), None)
+            if export == "macro_export" && args.delimiter() == Delimiter::Parenthesis =>
+        {
+            let mut args = args.stream().into_iter();
+            match (args.next(), args.next()) {
+                (Some(TokenTree::Ident(local)), None) => local == "local_inner_macros",
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Checks whether `attr`, the `[...]` of an attribute, is `doc(hidden)`.
+fn is_doc_hidden(attr: &Group) -> bool {
+    let mut tokens = attr.stream().into_iter();
+    match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(doc)), Some(TokenTree::Group(args)), None) if doc == "doc" => {
+            args.delimiter() == Delimiter::Parenthesis && args.stream().to_string() == "hidden"
+        }
+        _ => false,
+    }
+}
+
+/// The predicate of `attr`, the `[...]` of an attribute, if it's
+/// `doc(cfg(predicate))`.
+fn doc_cfg(attr: &Group) -> Option<TokenStream> {
+    let mut tokens = attr.stream().into_iter();
+    let args = match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(doc)), Some(TokenTree::Group(args)), None)
+            if doc == "doc" && args.delimiter() == Delimiter::Parenthesis =>
+        {
+            args
+        }
+        _ => return None,
+    };
+    let mut args = args.stream().into_iter();
+    match (args.next(), args.next(), args.next()) {
+        (Some(TokenTree::Ident(cfg)), Some(TokenTree::Group(predicate)), None)
+            if cfg == "cfg" && predicate.delimiter() == Delimiter::Parenthesis =>
+        {
+            Some(predicate.stream())
+        }
+        _ => None,
+    }
+}
+
+/// The arguments to `#[macro_pub(...)]`: an optional visibility restriction,
+/// followed by comma-separated options.
+#[derive(Clone, Default)]
+pub(crate) struct Args {
+    /// The inside of the `pub(...)` restriction, or empty for world-public,
+    /// given first or as `vis = pub(...)`.
+    pub(crate) restriction: TokenStream,
+    /// `rename = "name"`: define the macro as `name`, as if it were written
+    /// with that name.
+    pub(crate) rename: Option<Ident>,
+    /// `signatures`: document each arm's matcher.
+    pub(crate) signatures: bool,
+    /// `inline`: always `#[doc(inline)]` the re-export.
+    pub(crate) inline: bool,
+    /// `no_inline`: always `#[doc(no_inline)]` the re-export.
+    pub(crate) no_inline: bool,
+    /// `hidden`: `#[doc(hidden)]` the macro and its re-export.
+    pub(crate) hidden: bool,
+    /// `export_as = name`: also export the macro, hidden, as world-public
+    /// `name`.
+    pub(crate) export_as: Option<Ident>,
+    /// `deprecate_as = "name"`: also define the macro, hidden and
+    /// deprecated, as `name`.
+    pub(crate) deprecate_as: Option<Ident>,
+    /// `alias = "name"`, repeatable: also re-export the macro, hidden, as
+    /// each `name`.
+    pub(crate) aliases: Vec<Ident>,
+    /// `hygienic_as = "name"`: on toolchains with `decl_macro`, also define
+    /// the macro as a `macro` with its default hygiene, as `name`.
+    pub(crate) hygienic_as: Option<Ident>,
+    /// `friends("name", ...)`: also export the macro, hidden, for use by the
+    /// named crates through `macro_pub::friend!`. Names use `_` for `-`.
+    pub(crate) friends: Vec<String>,
+    /// `since = "version"`: document the version the macro was added in.
+    pub(crate) since: Option<String>,
+    /// `unstable`: hide the macro from documentation, and mark it as exempt
+    /// from semver where it's shown.
+    pub(crate) unstable: bool,
+    /// `feature = "name"`: without the cargo feature `name`, replace the
+    /// macro with one that asks for the feature to be enabled.
+    pub(crate) feature: Option<String>,
+    /// `also_export_legacy_name`: also `#[macro_export]` the macro under its
+    /// own name, for `#[macro_use] extern crate` users.
+    pub(crate) also_export_legacy_name: bool,
+    /// `strategy = "name"`: how to define the macro, rather than detecting it.
+    pub(crate) strategy: Option<Strategy>,
+    /// `metadata`: also define a hidden module of constants describing the
+    /// macro.
+    pub(crate) metadata: bool,
+    /// `debug`: warn with the expansion, for debugging.
+    pub(crate) debug: bool,
+    /// `no_alias`: don't re-export the macro under its name, only define (and
+    /// export) it.
+    pub(crate) no_alias: bool,
+    /// `unsafe_macro`: document that the macro expands to `unsafe` code.
+    pub(crate) unsafe_macro: bool,
+    /// `track_uses`: record the crates using the macro in the manifest.
+    pub(crate) track_uses: bool,
+    /// `lint`: warn about code in the arms that behaves differently in other
+    /// crates.
+    pub(crate) lint: bool,
+    /// `no_std`: warn about `std::` paths in the arms.
+    pub(crate) no_std: bool,
+    /// `package`: also export the macro, hidden, for use by the package's
+    /// tests, benches, and examples through `macro_pub::package!`. Without
+    /// a restriction, the macro is `pub(crate)`.
+    pub(crate) package: bool,
+    /// `doctest`: make a restricted macro usable in the examples in its
+    /// documentation, which are compiled as other crates.
+    pub(crate) doctest: bool,
+    /// `selfcheck`: where the macro is documented as a `pub macro`, check
+    /// that it matches the same invocations as the `macro_rules!` macro.
+    pub(crate) selfcheck: bool,
+    /// `source`: document the macro's definition.
+    pub(crate) source: bool,
+    /// `hide_arms`: document the macro as taking any tokens, rather than
+    /// with its arms.
+    pub(crate) hide_arms: bool,
+    /// `internal_rules`: move the arms whose matchers start with `@` to a
+    /// hidden helper macro.
+    pub(crate) internal_rules: bool,
+    /// `textual`: also define the macro under its own name, for textual
+    /// scope within the crate.
+    pub(crate) textual: bool,
+    /// `no_textual`: define a restricted macro under its mangled name too, so
+    /// it can only be used through its path.
+    pub(crate) no_textual: bool,
+    /// `shadowable`: re-export the macro under its name through a glob, so
+    /// other items of the same name in the module shadow it.
+    pub(crate) shadowable: bool,
+    /// `doc_required`: make it an error for the macro to be world-public
+    /// without documentation.
+    pub(crate) doc_required: bool,
+    /// `catch_all`: add a last arm failing with the signatures of the others.
+    pub(crate) catch_all: bool,
+    /// `salt = "text"`: mix `text` into the hash the macro's exported name is
+    /// mangled with.
+    pub(crate) salt: Option<String>,
+    /// `hash_docs`: keep doc comments in the hash the macro's exported name
+    /// is mangled with.
+    pub(crate) hash_docs: bool,
+    /// `mangle = "prefix"` or `mangle = false`: how to name the macro
+    /// `#[macro_export]`ed under a mangled name.
+    pub(crate) mangle: Option<Mangle>,
+    /// `expect_path = "path"`: fail to compile unless the macro can be named
+    /// by `path`, spanned at the option.
+    pub(crate) expect_path: Option<TokenStream>,
+    /// `allow(lint, ...)`, `warn(...)`, and `deny(...)`: the levels of
+    /// `#[macro_pub]`'s lints for the macro, in order.
+    pub(crate) lint_levels: Vec<(String, Level)>,
+    /// The warnings for deprecated spellings of options, at the options.
+    pub(crate) deprecated: Vec<(String, Span)>,
+    /// `extend`: define arms for a macro mounted elsewhere with `extend_from`,
+    /// rather than a macro.
+    pub(crate) extend: bool,
+    /// `extend_from(path, ...)`: the `extend` parts whose arms follow the
+    /// macro's own.
+    pub(crate) extend_from: Vec<TokenStream>,
+}
+
+/// A way to define a macro with a path, chosen with `strategy = "..."`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Strategy {
+    /// `"use_alias"`: a `macro_rules!` macro re-exported by `use`, which is
+    /// exported under a mangled name if it's world-public.
+    UseAlias,
+    /// `"decl_macro"`: an unstable `macro` with `macro_rules!` hygiene, which
+    /// needs `#![feature(decl_macro, rustc_attrs)]`.
+    DeclMacro,
+    /// `"legacy_export"`: a `#[macro_export]` macro at the crate root, for
+    /// world-public macros only.
+    LegacyExport,
+}
+
+/// How to name the `#[macro_export]`ed macro behind a mangled name, chosen
+/// with `mangle = ...`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Mangle {
+    /// `"prefix"`: `prefix_<hex hash>_name`, rather than `macro_impl_<hash>_name`.
+    Prefix(String),
+    /// `false`: the macro's own name.
+    Off,
+}
+
+/// How to report one of `#[macro_pub]`'s [`LINTS`], set like rustc's lint
+/// levels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Level {
+    /// `allow`: don't check for it.
+    Allow,
+    /// `warn`: report it as a warning.
+    Warn,
+    /// `deny`: report it as an error, or as a warning in dependencies, as
+    /// `--cap-lints` would.
+    Deny,
+}
+
+/// The names of `#[macro_pub]`'s lints.
+pub(crate) const LINTS: &[&str] = &[
+    "pointless_options",
+    "nested_exports",
+    "ignored_arm_attrs",
+    "item_attrs",
+    "crate_root_paths",
+    "self_calls",
+    "foreign_env",
+    "relative_includes",
+    "std_paths",
+    "std_names",
+    "narrowable",
+];
+
+/// Options that are either present or not.
+const FLAGS: &[&str] = &[
+    "signatures",
+    "inline",
+    "no_inline",
+    "hidden",
+    "unstable",
+    "also_export_legacy_name",
+    "metadata",
+    "debug",
+    "no_alias",
+    "unsafe_macro",
+    "track_uses",
+    "lint",
+    "no_std",
+    "package",
+    "doctest",
+    "selfcheck",
+    "source",
+    "hide_arms",
+    "internal_rules",
+    "textual",
+    "no_textual",
+    "shadowable",
+    "doc_required",
+    "catch_all",
+    "hash_docs",
+    "extend",
+];
+/// Options that take a macro name, like `option = name`.
+const NAMES: &[&str] = &["export_as"];
+/// Options that take a visibility, like `option = pub(crate)`.
+const VISIBILITIES: &[&str] = &["vis"];
+/// Options that take a string, like `option = "name"`.
+const STRINGS: &[&str] = &[
+    "deprecate_as",
+    "alias",
+    "hygienic_as",
+    "rename",
+    "since",
+    "feature",
+    "strategy",
+    "salt",
+    "mangle",
+    "expect_path",
+];
+/// Options that take a list of crate names in quotes, like `option("name")`.
+const LISTS: &[&str] = &["friends", "extend_from", "allow", "warn", "deny"];
+/// Options that can be given more than once.
+const REPEATABLE: &[&str] = &["alias"];
+/// Old spellings of options, with what they're spelled as now, which is parsed
+/// in their place. Spellings replaced with an option and its value can't have
+/// values of their own. Deliberately empty, since no option has been renamed.
+const DEPRECATED: &[(&str, &str)] = &[];
+
+/// An error in the arguments to `#[macro_pub(...)]`.
+#[derive(Clone)]
+pub(crate) struct ArgsError {
+    pub(crate) message: String,
+    /// The option or tokens at fault, or the call site for conflicting options.
+    pub(crate) span: Span,
+}
+
+impl Args {
+    /// Parses `attr`, or returns an error if it's malformed.
+    pub(crate) fn parse(attr: TokenStream) -> Result<Self, ArgsError> {
+        let mut span = Span::call_site();
+        Args::parse_at(attr, &mut span).map_err(|message| ArgsError { message, span })
+    }
+
+    /// Parses `attr`, setting `span` to the tokens at fault in any error.
+    fn parse_at(attr: TokenStream, span: &mut Span) -> Result<Self, String> {
+        let mut args = Args::default();
+        let mut seen = Vec::new();
+        let mut segments = vec![TokenStream::new()];
+        for tt in attr {
+            match tt {
+                TokenTree::Punct(ref punct) if punct.as_char() == ',' => {
+                    segments.push(TokenStream::new())
+                }
+                tt => segments.last_mut().unwrap().extend(Some(tt)),
+            }
+        }
+        if segments.last().map_or(false, TokenStream::is_empty) {
+            segments.pop();
+        }
+
+        for (i, segment) in segments.into_iter().enumerate() {
+            *span = segment
+                .clone()
+                .into_iter()
+                .next()
+                .map_or_else(Span::call_site, |tt| tt.span());
+            if i == 0 && is_visibility_restriction(segment.clone()) {
+                args.restriction = segment;
+                continue;
+            }
+            if i == 0 {
+                if let Some(at_fault) = bad_in_path(segment.clone()) {
+                    *span = at_fault.span();
+                    return Err(
+                        "`#[macro_pub]` expects a path to an enclosing module after `in`, \
+                         like `in crate::module` or `in super::super`"
+                            .to_string(),
+                    );
+                }
+            }
+            let (segment, written) = replace_deprecated(segment, DEPRECATED, &mut args.deprecated)?;
+            // Anything unrecognized in the first position was most likely
+            // meant to be a restriction.
+            let unexpected = if i == 0 {
+                "`#[macro_pub]` expects a visibility restriction like `crate`, `super`, or `in path`"
+            } else {
+                "`#[macro_pub]` expects options like `signatures`"
+            };
+            let mut tokens = segment.into_iter();
+            let option = match tokens.next() {
+                Some(TokenTree::Ident(option)) => option.to_string(),
+                _ => return Err(unexpected.to_string()),
+            };
+            let is_list = LISTS.contains(&&*option);
+            let value = match tokens.next() {
+                None => None,
+                Some(TokenTree::Punct(ref punct)) if punct.as_char() == '=' && !is_list => {
+                    Some(tokens.collect::<TokenStream>())
+                }
+                Some(TokenTree::Group(ref group))
+                    if group.delimiter() == Delimiter::Parenthesis && is_list =>
+                {
+                    if tokens.next().is_some() {
+                        return Err(list_error(&option));
+                    }
+                    Some(group.stream())
+                }
+                _ if is_list => return Err(list_error(&option)),
+                _ => return Err(unexpected.to_string()),
+            };
+
+            let is_flag = FLAGS.contains(&&*option);
+            let is_string = STRINGS.contains(&&*option);
+            if !is_flag
+                && !is_string
+                && !is_list
+                && !NAMES.contains(&&*option)
+                && !VISIBILITIES.contains(&&*option)
+            {
+                if i == 0 {
+                    return Err(unexpected.to_string());
+                }
+                return Err(format!("`#[macro_pub]` has no option `{}`", option));
+            }
+            let written = written.unwrap_or_else(|| option.clone());
+            if seen.contains(&written) && !REPEATABLE.contains(&&*written) {
+                return Err(format!("`#[macro_pub]` option `{}` is repeated", written));
+            }
+            let value = match value {
+                Some(_) if is_flag => {
+                    return Err(format!(
+                        "`#[macro_pub]` option `{}` does not take a value",
+                        option,
+                    ))
+                }
+                None if option == "since" => return Err(version_error(&option)),
+                None if option == "feature" => return Err(feature_error(&option)),
+                None if option == "strategy" => return Err(strategy_error(&option)),
+                None if option == "salt" => return Err(salt_error(&option)),
+                None if option == "mangle" => return Err(mangle_error(&option)),
+                None if option == "expect_path" => return Err(expect_path_error(&option)),
+                None if option == "vis" => return Err(vis_error(&option)),
+                None if is_string => return Err(quoted_name_error(&option)),
+                None if is_list => return Err(list_error(&option)),
+                None if !is_flag => {
+                    return Err(format!(
+                        "`#[macro_pub]` option `{0}` expects a name, like `{0} = name`",
+                        option,
+                    ))
+                }
+                value => value,
+            };
+
+            match &*option {
+                "vis" if !args.restriction.is_empty() => {
+                    return Err(
+                        "`#[macro_pub]` option `vis` can't be given with a visibility restriction"
+                            .to_string(),
+                    )
+                }
+                "vis" => args.restriction = vis(&option, value.unwrap())?,
+                "rename" => args.rename = Some(quoted_name(&option, value.unwrap())?),
+                "signatures" => args.signatures = true,
+                "inline" => args.inline = true,
+                "no_inline" => args.no_inline = true,
+                "hidden" => args.hidden = true,
+                "export_as" => args.export_as = Some(name(&option, value.unwrap())?),
+                "deprecate_as" => args.deprecate_as = Some(quoted_name(&option, value.unwrap())?),
+                "alias" => args.aliases.push(quoted_name(&option, value.unwrap())?),
+                "hygienic_as" => args.hygienic_as = Some(quoted_name(&option, value.unwrap())?),
+                "friends" => args.friends = crate_names(&option, value.unwrap())?,
+                "since" => args.since = Some(version(&option, value.unwrap())?),
+                "unstable" => args.unstable = true,
+                "also_export_legacy_name" => args.also_export_legacy_name = true,
+                "metadata" => args.metadata = true,
+                "debug" => args.debug = true,
+                "no_alias" => args.no_alias = true,
+                "unsafe_macro" => args.unsafe_macro = true,
+                "track_uses" => args.track_uses = true,
+                "lint" => args.lint = true,
+                "no_std" => args.no_std = true,
+                "package" => args.package = true,
+                "doctest" => args.doctest = true,
+                "selfcheck" => args.selfcheck = true,
+                "source" => args.source = true,
+                "hide_arms" => args.hide_arms = true,
+                "internal_rules" => args.internal_rules = true,
+                "textual" => args.textual = true,
+                "no_textual" => args.no_textual = true,
+                "shadowable" => args.shadowable = true,
+                "doc_required" => args.doc_required = true,
+                "catch_all" => args.catch_all = true,
+                "hash_docs" => args.hash_docs = true,
+                "extend" => args.extend = true,
+                "extend_from" => args.extend_from = paths(&option, value.unwrap())?,
+                "strategy" => args.strategy = Some(strategy(&option, value.unwrap())?),
+                "feature" => args.feature = Some(feature(&option, value.unwrap())?),
+                "salt" => args.salt = Some(salt(&option, value.unwrap())?),
+                "mangle" => args.mangle = Some(mangle(&option, value.unwrap())?),
+                "allow" | "warn" | "deny" => {
+                    let level = level(&option);
+                    for lint in lint_names(&option, value.unwrap())? {
+                        args.lint_levels.push((lint, level));
+                    }
+                }
+                "expect_path" => args.expect_path = Some(expect_path(&option, value.unwrap())?),
+                _ => unreachable!(),
+            }
+            seen.push(written);
+        }
+        *span = Span::call_site();
+        if args.package && args.restriction.is_empty() {
+            args.restriction = "crate".parse().unwrap();
+        }
+        if args.inline && args.no_inline {
+            return Err("`#[macro_pub]` options `inline` and `no_inline` conflict".to_string());
+        }
+        if args.hidden && (args.inline || args.no_inline) {
+            let inline = if args.inline { "inline" } else { "no_inline" };
+            return Err(format!(
+                "`#[macro_pub]` options `hidden` and `{}` conflict",
+                inline,
+            ));
+        }
+        if args.strategy == Some(Strategy::LegacyExport) && !args.restriction.is_empty() {
+            return Err(
+                "`#[macro_pub]` strategy `legacy_export` can't have a visibility restriction"
+                    .to_string(),
+            );
+        }
+        if args.no_alias {
+            let conflict = if args.inline {
+                Some("inline")
+            } else if args.no_inline {
+                Some("no_inline")
+            } else if args.feature.is_some() {
+                Some("feature")
+            } else {
+                None
+            };
+            if let Some(conflict) = conflict {
+                return Err(format!(
+                    "`#[macro_pub]` options `no_alias` and `{}` conflict",
+                    conflict,
+                ));
+            }
+        }
+        if args.no_textual && args.mangle == Some(Mangle::Off) {
+            return Err(
+                "`#[macro_pub]` options `no_textual` and `mangle = false` conflict".to_string(),
+            );
+        }
+        if args.extend && !args.extend_from.is_empty() {
+            return Err("`#[macro_pub]` options `extend` and `extend_from` conflict".to_string());
+        }
+        if args.extend && seen.len() > 1 {
+            return Err(
+                "`#[macro_pub]` option `extend` only takes a visibility restriction; give other \
+                 options where the arms are mounted with `extend_from`"
+                    .to_string(),
+            );
+        }
+        match args.strategy {
+            Some(Strategy::DeclMacro) | Some(Strategy::LegacyExport) if args.feature.is_some() => {
+                return Err("`#[macro_pub]` option `feature` needs strategy `use_alias`".to_string())
+            }
+            Some(Strategy::DeclMacro) | Some(Strategy::LegacyExport) if args.no_alias => {
+                return Err(
+                    "`#[macro_pub]` option `no_alias` needs strategy `use_alias`".to_string(),
+                )
+            }
+            _ => {}
+        }
+        Ok(args)
+    }
+}
+
+/// Replaces a deprecated spelling of an option at the start of `segment` with
+/// its replacement in `deprecated`, spanned at it, adding a warning to
+/// `warnings`. Returns the spelling replaced, if any.
+fn replace_deprecated(
+    segment: TokenStream,
+    deprecated: &[(&str, &str)],
+    warnings: &mut Vec<(String, Span)>,
+) -> Result<(TokenStream, Option<String>), String> {
+    let mut tokens = segment.clone().into_iter();
+    let old = match tokens.next() {
+        Some(TokenTree::Ident(old)) => old,
+        _ => return Ok((segment, None)),
+    };
+    let name = old.to_string();
+    let replacement = match deprecated
+        .iter()
+        .find(|&&(deprecated, _)| deprecated == name)
+    {
+        Some(&(_, replacement)) => replacement,
+        None => return Ok((segment, None)),
+    };
+    let mut replaced = respan(replacement.parse().unwrap(), old.span());
+    let has_value = replaced.clone().into_iter().count() > 1;
+    if has_value && tokens.next().is_some() {
+        return Err(format!(
+            "`#[macro_pub]` option `{}` does not take a value",
+            name,
+        ));
+    }
+    replaced.extend(tokens);
+    warnings.push((
+        format!(
+            "`#[macro_pub]` option `{}` is deprecated; write `{}` instead",
+            name, replacement,
+        ),
+        old.span(),
+    ));
+    Ok((replaced, Some(name)))
+}
+
+/// Gives `tokens`, and the tokens in their groups, `span`.
+fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|tt| {
+            let mut tt = match tt {
+                TokenTree::Group(group) => {
+                    TokenTree::Group(Group::new(group.delimiter(), respan(group.stream(), span)))
+                }
+                tt => tt,
+            };
+            tt.set_span(span);
+            tt
+        })
+        .collect()
+}
+
+/// Checks that `value` is a single identifier, other than `_`.
+fn name(option: &str, value: TokenStream) -> Result<Ident, String> {
+    let mut tokens = value.into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(name)), None) if name != "_" => Ok(name),
+        _ => Err(format!(
+            "`#[macro_pub]` option `{0}` expects a name, like `{0} = name`",
+            option,
+        )),
+    }
+}
+
+/// Checks that `value` is a string literal holding a single identifier, other
+/// than `_`, and returns that identifier with the literal's span.
+fn quoted_name(option: &str, value: TokenStream) -> Result<Ident, String> {
+    let mut tokens = value.into_iter();
+    let literal = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal,
+        _ => return Err(quoted_name_error(option)),
+    };
+    let text = literal.to_string();
+    let name = match text.get(1..text.len() - 1) {
+        Some(name) if text.starts_with('"') && text.ends_with('"') => name,
+        _ => return Err(quoted_name_error(option)),
+    };
+    if !is_identifier(name) || name == "_" || is_keyword(name) {
+        return Err(quoted_name_error(option));
+    }
+    Ok(Ident::new(name, literal.span()))
+}
+
+/// Whether `name` is made of the characters of an identifier, by Unicode's
+/// `XID_Start` and `XID_Continue`, as `Ident::new` checks.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => {
+            (first == '_' || unicode_ident::is_xid_start(first))
+                && chars.all(unicode_ident::is_xid_continue)
+        }
+        None => false,
+    }
+}
+
+fn quoted_name_error(option: &str) -> String {
+    format!(
+        "`#[macro_pub]` option `{0}` expects a name in quotes, like `{0} = \"name\"`",
+        option,
+    )
+}
+
+/// Checks that `value` is `pub` or `pub(...)` with a visibility restriction,
+/// and returns the inside of the restriction, or nothing for `pub`.
+fn vis(option: &str, value: TokenStream) -> Result<TokenStream, String> {
+    let mut tokens = value.into_iter();
+    match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(ref vis)), None, None) if vis == "pub" => Ok(TokenStream::new()),
+        (Some(TokenTree::Ident(ref vis)), Some(TokenTree::Group(ref restriction)), None)
+            if vis == "pub"
+                && restriction.delimiter() == Delimiter::Parenthesis
+                && is_visibility_restriction(restriction.stream()) =>
+        {
+            Ok(restriction.stream())
+        }
+        _ => Err(vis_error(option)),
+    }
+}
+
+fn vis_error(option: &str) -> String {
+    format!(
+        "`#[macro_pub]` option `{0}` expects a visibility, like `{0} = pub` or \
+         `{0} = pub(crate)`",
+        option,
+    )
+}
+
+/// Checks that `value` is a string literal holding a version, like `1.4.0`,
+/// and returns the version.
+fn version(option: &str, value: TokenStream) -> Result<String, String> {
+    let mut tokens = value.into_iter();
+    let text = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal.to_string(),
+        _ => return Err(version_error(option)),
+    };
+    match text.get(1..text.len() - 1) {
+        Some(version)
+            if text.starts_with('"')
+                && text.ends_with('"')
+                && version.starts_with(|c: char| c.is_ascii_digit())
+                && version
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || ".+-".contains(c)) =>
+        {
+            Ok(version.to_string())
+        }
+        _ => Err(version_error(option)),
+    }
+}
+
+fn version_error(option: &str) -> String {
+    format!(
+        "`#[macro_pub]` option `{0}` expects a version in quotes, like `{0} = \"1.0.0\"`",
+        option,
+    )
+}
+
+/// Checks that `value` is a string literal holding a cargo feature name, and
+/// returns the name.
+fn feature(option: &str, value: TokenStream) -> Result<String, String> {
+    let mut tokens = value.into_iter();
+    let text = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal.to_string(),
+        _ => return Err(feature_error(option)),
+    };
+    match text.get(1..text.len() - 1) {
+        Some(feature)
+            if text.starts_with('"')
+                && text.ends_with('"')
+                && !feature.is_empty()
+                && feature
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "_-+.".contains(c)) =>
+        {
+            Ok(feature.to_string())
+        }
+        _ => Err(feature_error(option)),
+    }
+}
+
+fn feature_error(option: &str) -> String {
+    format!(
+        "`#[macro_pub]` option `{0}` expects a feature name in quotes, like `{0} = \"name\"`",
+        option,
+    )
+}
+
+/// Checks that `value` is a non-empty string literal, and returns its text as
+/// written.
+fn salt(option: &str, value: TokenStream) -> Result<String, String> {
+    let mut tokens = value.into_iter();
+    let text = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal.to_string(),
+        _ => return Err(salt_error(option)),
+    };
+    match text.get(1..text.len() - 1) {
+        Some(salt) if text.starts_with('"') && text.ends_with('"') && !salt.is_empty() => {
+            Ok(salt.to_string())
+        }
+        _ => Err(salt_error(option)),
+    }
+}
+
+/// Checks that `value` is a string literal holding a path, like
+/// `crate::module::name`, and returns the path, spanned at the literal.
+fn expect_path(option: &str, value: TokenStream) -> Result<TokenStream, String> {
+    let mut tokens = value.into_iter();
+    let literal = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal,
+        _ => return Err(expect_path_error(option)),
+    };
+    let text = literal.to_string();
+    let path = match text.get(1..text.len() - 1) {
+        Some(path) if text.starts_with('"') && text.ends_with('"') => path.parse().ok(),
+        _ => None,
+    };
+    match path {
+        Some(path) if is_path(&path) => Ok(path
+            .into_iter()
+            .map(|mut tt| {
+                tt.set_span(literal.span());
+                tt
+            })
+            .collect()),
+        _ => Err(expect_path_error(option)),
+    }
+}
+
+fn expect_path_error(option: &str) -> String {
+    format!(
+        "`#[macro_pub]` option `{0}` expects a path in quotes, like `{0} = \"crate::module::name\"`",
+        option,
+    )
+}
+
+/// Checks that `value` is `false`, or a string literal holding the start of
+/// an identifier, like `"my_crate"`.
+fn mangle(option: &str, value: TokenStream) -> Result<Mangle, String> {
+    let mut tokens = value.into_iter();
+    let literal = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(ident)), None) if ident == "false" => return Ok(Mangle::Off),
+        (Some(TokenTree::Literal(literal)), None) => literal,
+        _ => return Err(mangle_error(option)),
+    };
+    let text = literal.to_string();
+    let prefix = match text.get(1..text.len() - 1) {
+        Some(prefix) if text.starts_with('"') && text.ends_with('"') => prefix,
+        _ => return Err(mangle_error(option)),
+    };
+    if !is_identifier(prefix) {
+        return Err(mangle_error(option));
+    }
+    Ok(Mangle::Prefix(prefix.to_string()))
+}
+
+fn mangle_error(option: &str) -> String {
+    format!(
+        "`#[macro_pub]` option `{0}` expects the start of a name in quotes, like \
+         `{0} = \"my_crate\"`, or `{0} = false`",
+        option,
+    )
+}
+
+fn salt_error(option: &str) -> String {
+    format!(
+        "`#[macro_pub]` option `{0}` expects a string, like `{0} = \"v2\"`",
+        option,
+    )
+}
+
+/// Checks that `value` is a string literal naming a [`Strategy`].
+fn strategy(option: &str, value: TokenStream) -> Result<Strategy, String> {
+    let mut tokens = value.into_iter();
+    let text = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal.to_string(),
+        _ => return Err(strategy_error(option)),
+    };
+    match &*text {
+        "\"use_alias\"" => Ok(Strategy::UseAlias),
+        "\"decl_macro\"" => Ok(Strategy::DeclMacro),
+        "\"legacy_export\"" => Ok(Strategy::LegacyExport),
+        _ => Err(strategy_error(option)),
+    }
+}
+
+fn strategy_error(option: &str) -> String {
+    format!(
+        "`#[macro_pub]` option `{}` expects one of \"use_alias\", \"decl_macro\", or \
+         \"legacy_export\"",
+        option,
+    )
+}
+
+/// Checks that `value` is a comma-separated, nonempty list of crate names in
+/// quotes, and returns them as they're spelled in paths.
+fn crate_names(option: &str, value: TokenStream) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    let mut tokens = value.into_iter();
+    loop {
+        let text = match tokens.next() {
+            Some(TokenTree::Literal(literal)) => literal.to_string(),
+            _ => return Err(crate_names_error(option)),
+        };
+        let name = match text.get(1..text.len() - 1) {
+            Some(name) if text.starts_with('"') && text.ends_with('"') => name,
+            _ => return Err(crate_names_error(option)),
+        };
+        let is_name = !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name
+                .chars()
+                .all(|c| c == '_' || c == '-' || c.is_ascii_alphanumeric());
+        if !is_name {
+            return Err(crate_names_error(option));
+        }
+        names.push(name.replace('-', "_"));
+        match tokens.next() {
+            None => return Ok(names),
+            Some(TokenTree::Punct(ref punct)) if punct.as_char() == ',' => {}
+            _ => return Err(crate_names_error(option)),
+        }
+        if tokens.clone().next().is_none() {
+            return Ok(names);
+        }
+    }
+}
+
+/// Splits `value` into comma-separated paths, like `a::m, b::m`.
+fn paths(option: &str, value: TokenStream) -> Result<Vec<TokenStream>, String> {
+    let mut paths = vec![TokenStream::new()];
+    for tt in value {
+        match tt {
+            TokenTree::Punct(ref punct) if punct.as_char() == ',' => paths.push(TokenStream::new()),
+            tt => paths.last_mut().unwrap().extend(Some(tt)),
+        }
+    }
+    if paths.last().map_or(false, TokenStream::is_empty) {
+        paths.pop();
+    }
+    if paths.is_empty() || !paths.iter().all(is_path) {
+        return Err(list_error(option));
+    }
+    Ok(paths)
+}
+
+/// Checks whether `path` is made of identifiers and `::`.
+fn is_path(path: &TokenStream) -> bool {
+    let tokens = path.clone().into_iter().collect::<Vec<_>>();
+    !tokens.is_empty()
+        && tokens.iter().all(|tt| match *tt {
+            TokenTree::Ident(_) => true,
+            TokenTree::Punct(ref punct) => punct.as_char() == ':',
+            _ => false,
+        })
+}
+
+/// Parses the lint levels in `MACRO_PUB_LINTS`, like
+/// `deny(foreign_env), allow(pointless_options)`.
+pub(crate) fn lint_levels(tokens: TokenStream) -> Result<Vec<(String, Level)>, String> {
+    let mut levels = Vec::new();
+    let mut tokens = tokens.into_iter();
+    while let Some(tt) = tokens.next() {
+        let option = match (tt, tokens.next()) {
+            (TokenTree::Ident(option), Some(TokenTree::Group(ref group)))
+                if ["allow", "warn", "deny"].contains(&&*option.to_string())
+                    && group.delimiter() == Delimiter::Parenthesis =>
+            {
+                let option = option.to_string();
+                for lint in lint_names(&option, group.stream())? {
+                    levels.push((lint, level(&option)));
+                }
+                option
+            }
+            _ => {
+                return Err(
+                    "`MACRO_PUB_LINTS` expects lint levels, like `deny(foreign_env)`".to_string(),
+                )
+            }
+        };
+        match tokens.next() {
+            Some(TokenTree::Punct(ref punct)) if punct.as_char() == ',' => {}
+            None => {}
+            _ => return Err(list_error(&option)),
+        }
+    }
+    Ok(levels)
+}
+
+fn level(option: &str) -> Level {
+    match option {
+        "allow" => Level::Allow,
+        "warn" => Level::Warn,
+        _ => Level::Deny,
+    }
+}
+
+/// Checks that `value` is a list of `#[macro_pub]`'s [`LINTS`], and returns
+/// them.
+fn lint_names(option: &str, value: TokenStream) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    let mut tokens = value.into_iter();
+    loop {
+        match (tokens.next(), tokens.next()) {
+            (Some(TokenTree::Ident(name)), comma) => {
+                let name = name.to_string();
+                if !LINTS.contains(&&*name) {
+                    return Err(format!(
+                        "`#[macro_pub]` has no lint `{}`; its lints are `{}`",
+                        name,
+                        LINTS.join("`, `"),
+                    ));
+                }
+                names.push(name);
+                match comma {
+                    Some(TokenTree::Punct(ref punct)) if punct.as_char() == ',' => {}
+                    None => break,
+                    _ => return Err(list_error(option)),
+                }
+            }
+            (None, _) if !names.is_empty() => break,
+            _ => return Err(list_error(option)),
+        }
+    }
+    Ok(names)
+}
+
+/// The error for a malformed list option.
+fn list_error(option: &str) -> String {
+    if ["allow", "warn", "deny"].contains(&option) {
+        format!(
+            "`#[macro_pub]` option `{0}` expects lint names, like `{0}(pointless_options)`",
+            option,
+        )
+    } else if option == "extend_from" {
+        format!(
+            "`#[macro_pub]` option `{0}` expects paths to macros, like `{0}(module::name)`",
+            option,
+        )
+    } else {
+        crate_names_error(option)
+    }
+}
+
+fn crate_names_error(option: &str) -> String {
+    format!(
+        "`#[macro_pub]` option `{0}` expects crate names in quotes, like `{0}(\"name\")`",
+        option,
+    )
+}
+
+/// Checks whether `name` is a keyword, which can't be a macro's name.
+fn is_keyword(name: &str) -> bool {
+    [
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+        // Reserved for future use, in some edition or another.
+        "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+        "typeof", "unsized", "virtual", "yield",
+    ]
+    .contains(&name)
+}
+
+/// Checks that `attr` is the inside of a `pub(...)` visibility restriction:
+/// `crate`, `self`, `super`, or `in` followed by a path starting with one of
+/// those.
+fn is_visibility_restriction(attr: TokenStream) -> bool {
+    let mut tokens = attr.into_iter();
+    match tokens.next() {
+        Some(TokenTree::Ident(ident)) => match &*ident.to_string() {
+            "crate" | "self" | "super" => tokens.next().is_none(),
+            "in" => {
+                match tokens.next() {
+                    Some(TokenTree::Ident(ident)) => match &*ident.to_string() {
+                        "crate" | "self" | "super" => {}
+                        _ => return false,
+                    },
+                    _ => return false,
+                }
+                loop {
+                    match (tokens.next(), tokens.next()) {
+                        (None, _) => break true,
+                        (Some(TokenTree::Punct(first)), Some(TokenTree::Punct(second)))
+                            if first.as_char() == ':'
+                                && first.spacing() == Spacing::Joint
+                                && second.as_char() == ':' => {}
+                        _ => break false,
+                    }
+                    match tokens.next() {
+                        Some(TokenTree::Ident(ident)) if ident != "_" => {}
+                        _ => break false,
+                    }
+                }
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// For an invalid `in path` restriction, the first token that makes it
+/// invalid, or `in` itself if the path is missing.
+fn bad_in_path(attr: TokenStream) -> Option<TokenTree> {
+    let mut tokens = attr.into_iter();
+    let keyword = match tokens.next() {
+        Some(TokenTree::Ident(ident)) if ident == "in" => ident,
+        _ => return None,
+    };
+    match tokens.next() {
+        Some(TokenTree::Ident(ref ident))
+            if ident == "crate" || ident == "self" || ident == "super" => {}
+        Some(tt) => return Some(tt),
+        None => return Some(TokenTree::Ident(keyword)),
+    }
+    loop {
+        match tokens.next() {
+            None => return None,
+            Some(TokenTree::Punct(ref first))
+                if first.as_char() == ':' && first.spacing() == Spacing::Joint =>
+            {
+                match tokens.next() {
+                    Some(TokenTree::Punct(ref second)) if second.as_char() == ':' => {}
+                    Some(tt) => return Some(tt),
+                    None => return Some(TokenTree::Punct(first.clone())),
+                }
+            }
+            Some(tt) => return Some(tt),
+        }
+        match tokens.next() {
+            Some(TokenTree::Ident(ref ident)) if ident != "_" => {}
+            Some(tt) => return Some(tt),
+            None => return Some(TokenTree::Ident(keyword)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(item: &str) -> Option<MacroRules> {
+        MacroRules::parse(item.parse().unwrap())
+    }
+
+    fn is_restriction(attr: &str) -> bool {
+        is_visibility_restriction(attr.parse().unwrap())
+    }
+
+    #[test]
+    fn macro_rules() {
+        let parsed = parse("/// docs\n#[attr] macro_rules! m { () => {}; }").unwrap();
+        assert_eq!(parsed.attrs.to_string(), "# [doc = \" docs\"] # [attr]");
+        assert!(parsed.internal_attrs.is_empty());
+        assert_eq!(parsed.name, "m");
+        assert_eq!(parsed.arms.to_string(), "() => { } ;");
+        assert!(parsed.arm_attrs.is_empty());
+        assert!(parsed.rest.is_empty());
+    }
+
+    #[test]
+    fn delimiters() {
+        for item in &[
+            "macro_rules! m ( () => {}; );",
+            "macro_rules! m [ () => {}; ];",
+        ] {
+            let parsed = parse(item).unwrap();
+            assert_eq!(parsed.arms.to_string(), "() => { } ;");
+            assert!(parsed.rest.is_empty());
+        }
+        assert!(parse("macro_rules! m ( () => {}; )").is_none());
+        assert!(parse("macro_rules! m [ () => {}; ] fn f() {}").is_none());
+    }
+
+    #[test]
+    fn nested_attrs() {
+        let parsed = parse(
+            r#"#[doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/docs/m.md"))]
+            #[cfg_attr(all(), doc = concat!("in ", module_path!()), allow(unused))]
+            macro_rules! m { () => {}; }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.attrs.to_string(),
+            concat!(
+                r#"# [doc = include_str ! (concat ! (env ! ("CARGO_MANIFEST_DIR") , "/docs/m.md"))] "#,
+                r#"# [cfg_attr (all () , doc = concat ! ("in " , module_path ! ()) , allow (unused))]"#,
+            ),
+        );
+        assert_eq!(parsed.name, "m");
+    }
+
+    #[test]
+    fn env_lint_levels() {
+        let levels = |levels: &str| lint_levels(levels.parse().unwrap());
+        assert_eq!(
+            levels("deny(foreign_env), allow(pointless_options, narrowable),").unwrap(),
+            [
+                ("foreign_env".to_string(), Level::Deny),
+                ("pointless_options".to_string(), Level::Allow),
+                ("narrowable".to_string(), Level::Allow),
+            ],
+        );
+        assert!(levels("").unwrap().is_empty());
+        for malformed in &[
+            "deny",
+            "forbid(foreign_env)",
+            "crate",
+            "deny(foreign_env) allow(std_paths)",
+        ] {
+            assert!(levels(malformed).is_err(), "{}", malformed);
+        }
+    }
+
+    #[test]
+    fn internal_attrs() {
+        let parsed = parse(
+            "#[allow_internal_unstable(core_intrinsics)] /// docs\n\
+             #[allow_internal_unsafe] macro_rules! m { () => {}; }",
+        )
+        .unwrap();
+        assert_eq!(parsed.attrs.to_string(), "# [doc = \" docs\"]");
+        assert_eq!(
+            parsed.internal_attrs.to_string(),
+            "# [allow_internal_unstable (core_intrinsics)] # [allow_internal_unsafe]",
+        );
+    }
+
+    #[test]
+    fn item_attrs() {
+        let parsed = parse(
+            "/// docs\n#[inline] #[must_use = \"reason\"] #[allow(unused)] macro_rules! m { () => {}; }",
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.attrs.to_string(),
+            "# [doc = \" docs\"] # [allow (unused)]"
+        );
+        let attrs = parsed
+            .item_attrs
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(attrs, ["# [inline]", "# [must_use = \"reason\"]"]);
+    }
+
+    #[test]
+    fn local_inner_macros() {
+        let parsed =
+            parse("/// docs\n#[macro_export(local_inner_macros)] macro_rules! m { () => {}; }")
+                .unwrap();
+        assert_eq!(parsed.attrs.to_string(), "# [doc = \" docs\"]");
+        assert!(parsed.local_inner_macros);
+        assert!(
+            !parse("macro_rules! m { () => {}; }")
+                .unwrap()
+                .local_inner_macros
+        );
+        assert!(
+            !parse("#[macro_export(local_inner_macros, other)] macro_rules! m { () => {}; }")
+                .unwrap()
+                .local_inner_macros
+        );
+    }
+
+    #[test]
+    fn arm_attrs() {
+        let parsed = parse(
+            "macro_rules! m { /// docs\n () => {}; #[a] #[b] ($e:expr) => { #[c] $e }; #[d] }",
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.arms.to_string(),
+            "() => { } ; ($ e : expr) => { # [c] $ e } ;",
+        );
+        let attrs = parsed
+            .arm_attrs
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(attrs, ["# [doc = \" docs\"]", "# [a]", "# [b]", "# [d]"]);
+    }
+
+    #[test]
+    fn hidden_arms() {
+        let parsed = parse(
+            "macro_rules! m { () => {}; #[doc(hidden)] (@a) => {}; #[a] #[doc(hidden)] (@b) => {}; }",
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.arms.to_string(),
+            "() => { } ; (@ a) => { } ; (@ b) => { } ;"
+        );
+        let hidden = parsed
+            .hidden_arms
+            .iter()
+            .map(|arm| arm.0)
+            .collect::<Vec<_>>();
+        assert_eq!(hidden, [1, 2]);
+        assert_eq!(parsed.arm_attrs.len(), 1);
+    }
+
+    #[test]
+    fn arm_cfgs() {
+        let parsed = parse(
+            r#"macro_rules! m { () => {}; #[doc(cfg(feature = "json"))] #[doc(cfg)] (json) => {}; }"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.arms.to_string(), "() => { } ; (json) => { } ;");
+        let cfgs = parsed
+            .arm_cfgs
+            .iter()
+            .map(|(arm, predicate)| (*arm, predicate.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(cfgs, [(1, "feature = \"json\"".to_string())]);
+        assert_eq!(parsed.arm_attrs.len(), 1);
+    }
+
+    #[test]
+    fn not_macro_rules() {
+        assert!(parse("fn m() {}").is_none());
+        assert!(parse("macro_rules! { () => {} }").is_none());
+        assert!(parse("macro_rules m { () => {} }").is_none());
+        assert!(parse("#![inner] macro_rules! m { () => {} }").is_none());
+        assert!(parse("macro_rules! m").is_none());
+        assert!(parse("").is_none());
+    }
+
+    #[test]
+    fn matchers() {
+        let matchers = super::matchers("() => {}; [$e:expr] => { $e }; {} => ()".parse().unwrap());
+        let matchers = matchers.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(matchers, ["()", "[$ e : expr]", "{ }"]);
+    }
+
+    #[test]
+    fn arms() {
+        let arms = super::arms("() => {}; [$e:expr] => { $e }; {} => ()".parse().unwrap());
+        let arms = arms.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            arms,
+            ["() => { } ;", "[$ e : expr] => { $ e } ;", "{ } => () ;"]
+        );
+    }
+
+    #[test]
+    fn transcribers() {
+        let transcribers = super::transcribers(
+            "() => {}; [$e:expr] => { $e > 0 }; {} => ()"
+                .parse()
+                .unwrap(),
+        );
+        let transcribers = transcribers
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(transcribers, ["{ }", "{ $ e > 0 }", "()"]);
+    }
+
+    fn parse_args(attr: &str) -> Result<Args, String> {
+        Args::parse(attr.parse().unwrap()).map_err(|error| error.message)
+    }
+
+    #[test]
+    fn args() {
+        let parsed = parse_args("").unwrap();
+        assert!(parsed.restriction.is_empty());
+        assert!(!parsed.signatures);
+
+        let parsed = parse_args("in crate::module, signatures,").unwrap();
+        assert_eq!(parsed.restriction.to_string(), "in crate :: module");
+        assert!(parsed.signatures);
+
+        let parsed = parse_args("signatures").unwrap();
+        assert!(parsed.restriction.is_empty());
+        assert!(parsed.signatures);
+
+        let parsed = parse_args("no_inline").unwrap();
+        assert!(!parsed.inline);
+        assert!(parsed.no_inline);
+
+        let parsed = parse_args("crate, hidden").unwrap();
+        assert!(parsed.hidden);
+
+        let parsed = parse_args("crate, export_as = other").unwrap();
+        assert_eq!(parsed.export_as.unwrap(), "other");
+
+        let parsed = parse_args(r#"deprecate_as = "old_name""#).unwrap();
+        assert_eq!(parsed.deprecate_as.unwrap(), "old_name");
+
+        let parsed = parse_args(r#"crate, alias = "a", alias = "b""#).unwrap();
+        assert_eq!(parsed.aliases, ["a", "b"]);
+
+        let parsed = parse_args(r#"hygienic_as = "m2""#).unwrap();
+        assert_eq!(parsed.hygienic_as.unwrap(), "m2");
+
+        let parsed = parse_args(r#"signatures, vis = pub(in crate::a), rename = "n""#).unwrap();
+        assert_eq!(parsed.restriction.to_string(), "in crate :: a");
+        assert_eq!(parsed.rename.unwrap(), "n");
+
+        let parsed = parse_args("vis = pub").unwrap();
+        assert!(parsed.restriction.is_empty());
+
+        let parsed = parse_args(r#"crate, friends("a", "b-c",)"#).unwrap();
+        assert_eq!(parsed.friends, ["a", "b_c"]);
+
+        let parsed = parse_args("package").unwrap();
+        assert_eq!(parsed.restriction.to_string(), "crate");
+        assert!(parsed.package);
+
+        let parsed = parse_args("super, package").unwrap();
+        assert_eq!(parsed.restriction.to_string(), "super");
+        assert!(parsed.package);
+
+        let parsed = parse_args("crate, doctest").unwrap();
+        assert!(parsed.doctest);
+        assert!(!parsed.package);
+
+        let parsed = parse_args("selfcheck").unwrap();
+        assert!(parsed.selfcheck);
+
+        let parsed = parse_args("crate, signatures, source").unwrap();
+        assert!(parsed.signatures);
+        assert!(parsed.source);
+
+        let parsed = parse_args("hide_arms").unwrap();
+        assert!(parsed.hide_arms);
+
+        let parsed = parse_args("crate, internal_rules").unwrap();
+        assert!(parsed.internal_rules);
+
+        let parsed = parse_args("textual").unwrap();
+        assert!(parsed.textual);
+
+        let parsed = parse_args("crate, no_textual").unwrap();
+        assert!(parsed.no_textual);
+
+        let parsed = parse_args("shadowable").unwrap();
+        assert!(parsed.shadowable);
+
+        let parsed = parse_args("doc_required").unwrap();
+        assert!(parsed.doc_required);
+
+        let parsed = parse_args("catch_all").unwrap();
+        assert!(parsed.catch_all);
+
+        let parsed = parse_args("hash_docs").unwrap();
+        assert!(parsed.hash_docs);
+
+        let parsed = parse_args(r#"crate, salt = "v2""#).unwrap();
+        assert_eq!(parsed.salt.unwrap(), "v2");
+
+        let parsed = parse_args("deny(foreign_env, std_paths), allow(pointless_options)").unwrap();
+        assert_eq!(
+            parsed.lint_levels,
+            [
+                ("foreign_env".to_string(), Level::Deny),
+                ("std_paths".to_string(), Level::Deny),
+                ("pointless_options".to_string(), Level::Allow),
+            ],
+        );
+
+        let parsed = parse_args(r#"expect_path = "crate::a::m""#).unwrap();
+        assert_eq!(parsed.expect_path.unwrap().to_string(), "crate :: a :: m");
+
+        let parsed = parse_args("crate, extend").unwrap();
+        assert!(parsed.extend);
+
+        let parsed = parse_args("extend_from(a::m, crate::b::m)").unwrap();
+        assert_eq!(
+            parsed
+                .extend_from
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["a :: m", "crate :: b :: m"],
+        );
+
+        let parsed = parse_args(r#"feature = "experimental-macros""#).unwrap();
+        assert_eq!(parsed.feature.unwrap(), "experimental-macros");
+
+        let parsed = parse_args(r#"since = "1.4.0-rc.1", unstable"#).unwrap();
+        assert_eq!(parsed.since.unwrap(), "1.4.0-rc.1");
+        assert!(parsed.unstable);
+
+        let parsed = parse_args(r#"strategy = "decl_macro""#).unwrap();
+        assert_eq!(parsed.strategy, Some(Strategy::DeclMacro));
+
+        let parsed = parse_args("crate, also_export_legacy_name").unwrap();
+        assert!(parsed.also_export_legacy_name);
+
+        let parsed = parse_args("metadata").unwrap();
+        assert!(parsed.metadata);
+
+        let parsed = parse_args(r#"mangle = "my_crate""#).unwrap();
+        assert_eq!(parsed.mangle, Some(Mangle::Prefix("my_crate".to_string())));
+        let parsed = parse_args("mangle = false").unwrap();
+        assert_eq!(parsed.mangle, Some(Mangle::Off));
+
+        let parsed = parse_args("crate, debug").unwrap();
+        assert!(parsed.debug);
+
+        let parsed = parse_args("no_alias").unwrap();
+        assert!(parsed.no_alias);
+
+        let parsed = parse_args("crate, unsafe_macro").unwrap();
+        assert!(parsed.unsafe_macro);
+
+        let parsed = parse_args("track_uses").unwrap();
+        assert!(parsed.track_uses);
+
+        let parsed = parse_args("lint").unwrap();
+        assert!(parsed.lint);
+
+        let parsed = parse_args("no_std").unwrap();
+        assert!(parsed.no_std);
+    }
+
+    #[test]
+    fn bad_args() {
+        let error = |attr| parse_args(attr).err().unwrap();
+        assert_eq!(
+            error("pub"),
+            "`#[macro_pub]` expects a visibility restriction like `crate`, `super`, or `in path`",
+        );
+        assert_eq!(
+            error("crate, in crate"),
+            "`#[macro_pub]` expects options like `signatures`",
+        );
+        assert_eq!(
+            error("crate, signature"),
+            "`#[macro_pub]` has no option `signature`",
+        );
+        assert_eq!(
+            error("signatures, signatures"),
+            "`#[macro_pub]` option `signatures` is repeated",
+        );
+        assert_eq!(
+            error("crate, signatures = yes"),
+            "`#[macro_pub]` option `signatures` does not take a value",
+        );
+        assert_eq!(
+            error("crate, export_as"),
+            "`#[macro_pub]` option `export_as` expects a name, like `export_as = name`",
+        );
+        assert_eq!(
+            error("crate, export_as = a::b"),
+            "`#[macro_pub]` option `export_as` expects a name, like `export_as = name`",
+        );
+        for attr in &[
+            "vis",
+            "vis = crate",
+            "vis = pub(foo)",
+            "vis = pub(crate) fn",
+        ] {
+            assert_eq!(
+                error(attr),
+                "`#[macro_pub]` option `vis` expects a visibility, like `vis = pub` or \
+                 `vis = pub(crate)`",
+            );
+        }
+        assert_eq!(
+            error("crate, vis = pub"),
+            "`#[macro_pub]` option `vis` can't be given with a visibility restriction",
+        );
+        assert_eq!(
+            error("vis = pub, vis = pub(crate)"),
+            "`#[macro_pub]` option `vis` is repeated",
+        );
+        assert_eq!(
+            error("export_as = a, export_as = b"),
+            "`#[macro_pub]` option `export_as` is repeated",
+        );
+        for attr in &[
+            "deprecate_as",
+            "deprecate_as = old",
+            r#"deprecate_as = "a::b""#,
+            r#"deprecate_as = "_""#,
+            r#"deprecate_as = "fn""#,
+            r#"deprecate_as = "try""#,
+            r#"deprecate_as = "gen""#,
+            r#"deprecate_as = "a²""#,
+            r#"deprecate_as = "1a""#,
+            r#"deprecate_as = r"old""#,
+        ] {
+            assert_eq!(
+                error(attr),
+                r#"`#[macro_pub]` option `deprecate_as` expects a name in quotes, like `deprecate_as = "name"`"#,
+            );
+        }
+        for attr in &[
+            "friends",
+            "friends()",
+            r#"friends = "a""#,
+            r#"friends("a") x"#,
+            "friends(a)",
+            r#"friends("a" "b")"#,
+            r#"friends("a::b")"#,
+            r#"friends("")"#,
+        ] {
+            assert_eq!(
+                error(attr),
+                r#"`#[macro_pub]` option `friends` expects crate names in quotes, like `friends("name")`"#,
+            );
+        }
+        for attr in &[
+            "extend_from",
+            "extend_from()",
+            "extend_from = a",
+            r#"extend_from("a")"#,
+            "extend_from(a::m!)",
+            "extend_from(a,,b)",
+        ] {
+            assert_eq!(
+                error(attr),
+                "`#[macro_pub]` option `extend_from` expects paths to macros, like `extend_from(module::name)`",
+            );
+        }
+        assert_eq!(
+            error("crate, extend, signatures"),
+            "`#[macro_pub]` option `extend` only takes a visibility restriction; give other options where the arms are mounted with `extend_from`",
+        );
+        assert_eq!(
+            error("extend, extend_from(a::m)"),
+            "`#[macro_pub]` options `extend` and `extend_from` conflict",
+        );
+        for attr in &[
+            "since",
+            "since = 1.4",
+            r#"since = "v1""#,
+            r#"since = "1 2""#,
+            r#"since = """#,
+        ] {
+            assert_eq!(
+                error(attr),
+                r#"`#[macro_pub]` option `since` expects a version in quotes, like `since = "1.0.0"`"#,
+            );
+        }
+        for attr in &[
+            "feature",
+            "feature = x",
+            r#"feature = """#,
+            r#"feature = "a b""#,
+        ] {
+            assert_eq!(
+                error(attr),
+                r#"`#[macro_pub]` option `feature` expects a feature name in quotes, like `feature = "name"`"#,
+            );
+        }
+        for attr in &[
+            "expect_path",
+            "expect_path = a::m",
+            r#"expect_path = """#,
+            r#"expect_path = "a::m!""#,
+            r#"expect_path = "a::m(""#,
+        ] {
+            assert_eq!(
+                error(attr),
+                r#"`#[macro_pub]` option `expect_path` expects a path in quotes, like `expect_path = "crate::module::name"`"#,
+            );
+        }
+        for attr in &[
+            "deny",
+            "deny = foreign_env",
+            "deny()",
+            "deny(foreign_env std_paths)",
+        ] {
+            assert_eq!(
+                error(attr),
+                "`#[macro_pub]` option `deny` expects lint names, like `deny(pointless_options)`",
+            );
+        }
+        assert_eq!(
+            error("warn(unused)"),
+            "`#[macro_pub]` has no lint `unused`; its lints are `pointless_options`, \
+             `nested_exports`, `ignored_arm_attrs`, `item_attrs`, `crate_root_paths`, `self_calls`, `foreign_env`, \
+             `relative_includes`, `std_paths`, `std_names`, `narrowable`",
+        );
+        for attr in &["salt", "salt = v2", r#"salt = """#, "salt = 2"] {
+            assert_eq!(
+                error(attr),
+                r#"`#[macro_pub]` option `salt` expects a string, like `salt = "v2"`"#,
+            );
+        }
+        for attr in &[
+            "mangle",
+            "mangle = true",
+            "mangle = my_crate",
+            r#"mangle = """#,
+            r#"mangle = "my crate""#,
+            r#"mangle = "1st""#,
+        ] {
+            assert_eq!(
+                error(attr),
+                r#"`#[macro_pub]` option `mangle` expects the start of a name in quotes, like `mangle = "my_crate"`, or `mangle = false`"#,
+            );
+        }
+        assert_eq!(
+            error("crate, no_textual, mangle = false"),
+            "`#[macro_pub]` options `no_textual` and `mangle = false` conflict",
+        );
+        for attr in &["strategy", "strategy = use_alias", r#"strategy = "fast""#] {
+            assert_eq!(
+                error(attr),
+                r#"`#[macro_pub]` option `strategy` expects one of "use_alias", "decl_macro", or "legacy_export""#,
+            );
+        }
+        assert_eq!(
+            error(r#"crate, strategy = "legacy_export""#),
+            "`#[macro_pub]` strategy `legacy_export` can't have a visibility restriction",
+        );
+        assert_eq!(
+            error(r#"strategy = "decl_macro", feature = "extra""#),
+            "`#[macro_pub]` option `feature` needs strategy `use_alias`",
+        );
+        assert_eq!(
+            error(r#"strategy = "legacy_export", no_alias"#),
+            "`#[macro_pub]` option `no_alias` needs strategy `use_alias`",
+        );
+        assert_eq!(
+            error("no_alias, inline"),
+            "`#[macro_pub]` options `no_alias` and `inline` conflict",
+        );
+        assert_eq!(
+            error(r#"crate, feature = "extra", no_alias"#),
+            "`#[macro_pub]` options `no_alias` and `feature` conflict",
+        );
+        assert_eq!(
+            error("inline, no_inline"),
+            "`#[macro_pub]` options `inline` and `no_inline` conflict",
+        );
+        assert_eq!(
+            error("hidden, no_inline"),
+            "`#[macro_pub]` options `hidden` and `no_inline` conflict",
+        );
+    }
+
+    #[test]
+    fn deprecated() {
+        let deprecated = &[("old_flag", "signatures"), ("old_warn", "warn(std_names)")];
+        let replace = |segment: &str| {
+            let mut warnings = Vec::new();
+            replace_deprecated(segment.parse().unwrap(), deprecated, &mut warnings).map(
+                |(segment, written)| {
+                    let warnings = warnings.into_iter().map(|(warning, _)| warning);
+                    (segment.to_string(), written, warnings.collect::<Vec<_>>())
+                },
+            )
+        };
+        assert_eq!(
+            replace("old_warn"),
+            Ok((
+                "warn (std_names)".to_string(),
+                Some("old_warn".to_string()),
+                vec![
+                    "`#[macro_pub]` option `old_warn` is deprecated; write `warn(std_names)` \
+                     instead"
+                        .to_string()
+                ],
+            )),
+        );
+        // Flags keep their values, to be rejected as usual.
+        assert_eq!(replace("old_flag = yes").unwrap().0, "signatures = yes");
+        assert_eq!(
+            replace("old_warn = yes"),
+            Err("`#[macro_pub]` option `old_warn` does not take a value".to_string()),
+        );
+        assert_eq!(
+            replace("signatures"),
+            Ok(("signatures".to_string(), None, vec![]))
+        );
+    }
+
+    #[test]
+    fn visibility_restrictions() {
+        assert!(is_restriction("crate"));
+        assert!(is_restriction("self"));
+        assert!(is_restriction("super"));
+        assert!(is_restriction("in crate"));
+        assert!(is_restriction("in super::super::module"));
+        assert!(is_restriction("in super::super::super::a::b::c"));
+        assert!(is_restriction("in self::module"));
+    }
+
+    #[test]
+    fn bad_in_paths() {
+        let at_fault = |attr: &str| bad_in_path(attr.parse().unwrap()).map(|tt| tt.to_string());
+        assert_eq!(at_fault("in super::super::utils"), None);
+        assert_eq!(at_fault("crate"), None);
+        assert_eq!(at_fault("in").as_deref(), Some("in"));
+        assert_eq!(at_fault("in module").as_deref(), Some("module"));
+        assert_eq!(at_fault("in crate::").as_deref(), Some("in"));
+        assert_eq!(at_fault("in super::_").as_deref(), Some("_"));
+        assert_eq!(at_fault("in super:module").as_deref(), Some(":"));
+        assert_eq!(at_fault("in super::a.b").as_deref(), Some("."));
+        assert_eq!(at_fault("in ::crate").as_deref(), Some(":"));
+
+        let error = Args::parse("in super::a::1".parse().unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.message,
+            "`#[macro_pub]` expects a path to an enclosing module after `in`, like \
+             `in crate::module` or `in super::super`",
+        );
+    }
+
+    #[test]
+    fn not_visibility_restrictions() {
+        assert!(!is_restriction("pub"));
+        assert!(!is_restriction("crate self"));
+        assert!(!is_restriction("in"));
+        assert!(!is_restriction("in module"));
+        assert!(!is_restriction("in crate::"));
+        assert!(!is_restriction("in crate::_"));
+        assert!(!is_restriction("in crate:module"));
+        assert!(!is_restriction("in ::crate"));
+    }
+}
@@ -0,0 +1,117 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use proc_macro2::TokenStream;
+
+use crate::expand::macro_hash;
+use crate::parse::{Args, MacroRules};
+
+/// How long one `#[macro_pub]` invocation took, for `MACRO_PUB_TIMINGS`.
+pub(crate) struct Timings {
+    path: OsString,
+    krate: String,
+    name: String,
+    parse: Duration,
+    hash: Duration,
+    start: Instant,
+}
+
+impl Timings {
+    /// Starts timing `#[macro_pub(attr)] item`, if `MACRO_PUB_TIMINGS` is set.
+    ///
+    /// Expanding the macro parses and hashes it as it goes, so those are
+    /// timed by doing them here on their own first.
+    pub(crate) fn from_env(attr: &TokenStream, item: &TokenStream) -> Option<Timings> {
+        let path = env::var_os("MACRO_PUB_TIMINGS")?;
+        let start = Instant::now();
+        let name = MacroRules::parse(item.clone())?.name.to_string();
+        let args = Args::parse(attr.clone()).ok();
+        let parse = start.elapsed();
+        let start = Instant::now();
+        macro_hash(
+            item,
+            args.as_ref().and_then(|args| args.salt.as_deref()),
+            args.as_ref().map_or(false, |args| args.hash_docs),
+        );
+        let hash = start.elapsed();
+        Some(Timings {
+            path,
+            krate: env::var("CARGO_CRATE_NAME").unwrap_or_default(),
+            name,
+            parse,
+            hash,
+            start: Instant::now(),
+        })
+    }
+
+    /// Appends the timings to the file at `MACRO_PUB_TIMINGS`, counting
+    /// everything since [`Timings::from_env`] other than parsing and hashing
+    /// as emitting, or returns an error to emit if it can't be written.
+    pub(crate) fn record(self) -> TokenStream {
+        let emit = self.start.elapsed().checked_sub(self.parse + self.hash);
+        let line = line(
+            &self.krate,
+            &self.name,
+            self.parse,
+            self.hash,
+            emit.unwrap_or_default(),
+        );
+        match append(self.path.as_ref(), &line) {
+            Ok(()) => TokenStream::new(),
+            Err(message) => format!("compile_error! {{ {:?} }}", message)
+                .parse()
+                .unwrap(),
+        }
+    }
+}
+
+/// The line recording the timings of `name` in `krate`: the two, then the
+/// microseconds spent parsing, hashing, and emitting, separated by tabs.
+fn line(krate: &str, name: &str, parse: Duration, hash: Duration, emit: Duration) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}\n",
+        krate,
+        name,
+        parse.as_micros(),
+        hash.as_micros(),
+        emit.as_micros(),
+    )
+}
+
+fn append(path: &Path, line: &str) -> Result<(), String> {
+    // Crates compile in parallel, so append each line with a single write.
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|error| {
+            format!(
+                "`#[macro_pub]` could not write to `MACRO_PUB_TIMINGS` at {}: {}",
+                path.display(),
+                error,
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    #[test]
+    fn appends_lines() {
+        let path = env::temp_dir().join(format!("macro_pub-timings-{}", std::process::id()));
+        let micros = Duration::from_micros;
+        append(&path, &line("a", "m", micros(1), micros(2), micros(3))).unwrap();
+        append(&path, &line("b", "n", micros(40), micros(0), micros(1500))).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, "a\tm\t1\t2\t3\nb\tn\t40\t0\t1500\n");
+    }
+}
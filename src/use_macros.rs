@@ -0,0 +1,218 @@
+use proc_macro2::{Delimiter, Group, Punct, Spacing, TokenStream, TokenTree};
+
+/// Expands `use_macros! { input }`.
+///
+/// The input is a `macro_rules!` macro, optionally with a `#[macro_pub(...)]`
+/// attribute, whose body lists paths to the crate's macros, each followed by
+/// `;`:
+///
+/// ```text
+/// /// Imports the macros.
+/// #[macro_pub(crate)]
+/// macro_rules! prelude {
+///     crate::a::first;
+///     crate::b::{second, third};
+/// }
+/// ```
+///
+/// It becomes a `#[macro_pub(...)]` macro that imports each path, with an
+/// optional visibility, into the scope it's invoked in:
+///
+/// ```text
+/// #[macro_pub(crate)]
+/// macro_rules! prelude {
+///     () => {
+///         use $crate::a::first;
+///         use $crate::b::{second, third};
+///     };
+///     ($vis:vis) => {
+///         $vis use $crate::a::first;
+///         $vis use $crate::b::{second, third};
+///     };
+/// }
+/// ```
+pub(crate) fn use_macros(input: TokenStream) -> TokenStream {
+    let mut attrs = TokenStream::new();
+    let mut macro_pub = None;
+    let mut tokens = input.into_iter();
+    let (macro_rules, bang, name, body) = loop {
+        match tokens.next() {
+            Some(TokenTree::Punct(pound)) if pound.as_char() == '#' => {
+                let attr = match tokens.next() {
+                    Some(TokenTree::Group(attr)) if attr.delimiter() == Delimiter::Bracket => attr,
+                    _ => return error(EXPECTED),
+                };
+                if is_macro_pub(&attr) {
+                    if macro_pub.is_some() {
+                        return error("`macro_pub::use_macros!` expects one `#[macro_pub]`");
+                    }
+                    macro_pub = Some(attr.stream().into_iter().skip(1).collect::<TokenStream>());
+                } else {
+                    attrs.extend(vec![TokenTree::Punct(pound), TokenTree::Group(attr)]);
+                }
+            }
+            Some(TokenTree::Ident(macro_rules)) if macro_rules == "macro_rules" => {
+                match (tokens.next(), tokens.next(), tokens.next(), tokens.next()) {
+                    (
+                        Some(TokenTree::Punct(bang)),
+                        Some(TokenTree::Ident(name)),
+                        Some(TokenTree::Group(body)),
+                        None,
+                    ) if bang.as_char() == '!' && body.delimiter() == Delimiter::Brace => {
+                        break (macro_rules, bang, name, body);
+                    }
+                    _ => return error(EXPECTED),
+                }
+            }
+            _ => return error(EXPECTED),
+        }
+    };
+
+    let mut paths = Vec::new();
+    let mut tokens = body.stream().into_iter().peekable();
+    while tokens.peek().is_some() {
+        match tokens.next() {
+            Some(TokenTree::Ident(ref krate)) if krate == "crate" => {}
+            _ => {
+                return error(
+                    "`macro_pub::use_macros!` expects paths starting with `crate::`, each \
+                     followed by `;`",
+                )
+            }
+        }
+        let mut path = TokenStream::new();
+        loop {
+            match tokens.next() {
+                Some(TokenTree::Punct(ref punct)) if punct.as_char() == ';' => break,
+                Some(tt) => path.extend(Some(tt)),
+                None => return error("`macro_pub::use_macros!` expects `;` after each path"),
+            }
+        }
+        paths.push(path);
+    }
+    if paths.is_empty() {
+        return error(EXPECTED);
+    }
+
+    let mut macro_pub_attr: TokenStream = "::macro_pub::macro_pub".parse().unwrap();
+    macro_pub_attr.extend(macro_pub);
+    // `$vis:vis` doesn't match empty input on its own, so private imports
+    // get their own arm.
+    let mut arms = TokenStream::new();
+    for &(matcher, vis) in &[("()", ""), ("($vis:vis)", "$vis")] {
+        let mut imports = TokenStream::new();
+        for path in &paths {
+            imports.extend(
+                format!("{} use $crate", vis)
+                    .parse::<TokenStream>()
+                    .unwrap(),
+            );
+            imports.extend(path.clone());
+            imports.extend(Some(TokenTree::Punct(Punct::new(';', Spacing::Alone))));
+        }
+        arms.extend(format!("{} =>", matcher).parse::<TokenStream>().unwrap());
+        arms.extend(vec![
+            TokenTree::Group(Group::new(Delimiter::Brace, imports)),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ]);
+    }
+
+    let mut output: TokenStream = vec![
+        TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Bracket, macro_pub_attr)),
+    ]
+    .into_iter()
+    .collect();
+    output.extend(attrs);
+    output.extend(vec![
+        TokenTree::Ident(macro_rules),
+        TokenTree::Punct(bang),
+        TokenTree::Ident(name),
+        TokenTree::Group(Group::new(Delimiter::Brace, arms)),
+    ]);
+    output
+}
+
+const EXPECTED: &str =
+    "`macro_pub::use_macros!` expects a `macro_rules!` macro listing paths to macros";
+
+/// Checks whether `attr`, the inside of `#[...]`, is `macro_pub` or
+/// `macro_pub(...)`.
+fn is_macro_pub(attr: &Group) -> bool {
+    let mut tokens = attr.stream().into_iter();
+    match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(name)), args, None) if name == "macro_pub" => match args {
+            None => true,
+            Some(TokenTree::Group(args)) => args.delimiter() == Delimiter::Parenthesis,
+            Some(_) => false,
+        },
+        _ => false,
+    }
+}
+
+fn error(message: &str) -> TokenStream {
+    format!("compile_error! {{ {:?} }}", message)
+        .parse()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn assert_expands(input: &str, expected: &str) {
+        assert_eq!(
+            use_macros(input.parse().unwrap()).to_string(),
+            expected.parse::<TokenStream>().unwrap().to_string(),
+        );
+    }
+
+    #[test]
+    fn imports() {
+        assert_expands(
+            r#"
+                /// Imports.
+                #[macro_pub(crate)]
+                macro_rules! prelude {
+                    crate::a::first;
+                    crate::b::{second, third as fourth};
+                }
+            "#,
+            r#"
+                #[::macro_pub::macro_pub(crate)]
+                #[doc = " Imports."]
+                macro_rules! prelude {
+                    () => {
+                        use $crate::a::first;
+                        use $crate::b::{second, third as fourth};
+                    };
+                    ($vis:vis) => {
+                        $vis use $crate::a::first;
+                        $vis use $crate::b::{second, third as fourth};
+                    };
+                }
+            "#,
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_expands(
+            "macro_rules! prelude {}",
+            r#"compile_error! { "`macro_pub::use_macros!` expects a `macro_rules!` macro listing paths to macros" }"#,
+        );
+        assert_expands(
+            "macro_rules! prelude { self::a; }",
+            r#"compile_error! { "`macro_pub::use_macros!` expects paths starting with `crate::`, each followed by `;`" }"#,
+        );
+        assert_expands(
+            "macro_rules! prelude { crate::a }",
+            r#"compile_error! { "`macro_pub::use_macros!` expects `;` after each path" }"#,
+        );
+        assert_expands(
+            "#[macro_pub] #[macro_pub(crate)] macro_rules! prelude { crate::a; }",
+            r#"compile_error! { "`macro_pub::use_macros!` expects one `#[macro_pub]`" }"#,
+        );
+    }
+}
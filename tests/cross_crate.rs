@@ -0,0 +1,596 @@
+//! Cross-crate behavior, checked by building the `tests/workspace` crates.
+//!
+//! `definer` defines macros with `#[macro_pub]`, `consumer` uses the
+//! world-public ones and those shared with it as a friend, `facade` re-exports
+//! some, `rejected` tries to use a `pub(crate)` one and one behind a disabled
+//! feature, `stranger` ones shared with `consumer` and `definer`'s tests only,
+//! and `adopter` applies `#[macro_pub]` behind a feature of its own. The
+//! `editions/*` crates compile the same source in each edition.
+//!
+//! Every check runs in each [`Mode`], so the nightly expansion is exercised
+//! and held to the same behavior as the stable one on any toolchain.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// A toolchain configuration to build the workspace under.
+struct Mode {
+    name: &'static str,
+    rustflags: &'static str,
+    docs_rs: bool,
+}
+
+const MODES: &[Mode] = &[
+    Mode {
+        name: "stable",
+        rustflags: "",
+        docs_rs: false,
+    },
+    // The cfgs the build script would detect on a nightly toolchain.
+    Mode {
+        name: "nightly",
+        rustflags: "--cfg has_simple_decl_macro --cfg has_semiopaque_transparency --cfg doc_decl_macro",
+        docs_rs: false,
+    },
+    // Compiles the `pub macro` used for documentation as the real definition,
+    // so it is checked against the same tests as the `macro_rules!` one.
+    Mode {
+        name: "nightly-doc",
+        rustflags: "--cfg has_simple_decl_macro --cfg has_semiopaque_transparency --cfg doc_decl_macro --cfg doc",
+        docs_rs: false,
+    },
+];
+
+/// Documentation builds on docs.rs, on whatever toolchain is running the tests.
+const DOCS_RS: Mode = Mode {
+    name: "docs.rs",
+    rustflags: "",
+    docs_rs: true,
+};
+
+/// Documentation builds with the `nightly-docs` feature, which callers enable.
+const NIGHTLY_DOCS: Mode = Mode {
+    name: "nightly-docs",
+    rustflags: "",
+    docs_rs: false,
+};
+
+fn cargo(mode: &Mode, args: &[&str]) -> Output {
+    cargo_in(mode.name, mode, args)
+}
+
+/// Runs cargo with a separate target directory, for builds that would
+/// otherwise overwrite the output of another test.
+fn cargo_in(target: &str, mode: &Mode, args: &[&str]) -> Output {
+    command(target, mode).args(args).output().unwrap()
+}
+
+/// A cargo command for the workspace, building into `target`.
+fn command(target: &str, mode: &Mode) -> Command {
+    let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/workspace");
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("workspace")
+        .join(target);
+    let mut command = Command::new(env!("CARGO"));
+    if mode.docs_rs {
+        command.env("DOCS_RS", "1");
+    } else {
+        command.env_remove("DOCS_RS");
+    }
+    command
+        .current_dir(workspace)
+        // Lets the defining crates enable the nightly documentation features
+        // on any toolchain without also forcing the build script probe to pass.
+        .env(
+            "RUSTC_BOOTSTRAP",
+            "adopter,definer,edition2015,edition2018,edition2021,edition2024",
+        )
+        .env("RUSTFLAGS", mode.rustflags)
+        .env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .env_remove("MACRO_PUB_MANIFEST")
+        .env_remove("MACRO_PUB_DEBUG_DIR")
+        .env_remove("MACRO_PUB_CONFIG")
+        .env_remove("MACRO_PUB_LOCKFILE")
+        .env("CARGO_TARGET_DIR", target_dir);
+    command
+}
+
+#[track_caller]
+fn assert_success(mode: &Mode, output: &Output) {
+    assert!(
+        output.status.success(),
+        "{}: {}",
+        mode.name,
+        String::from_utf8_lossy(&output.stderr),
+    );
+}
+
+#[test]
+fn world_public_macros_are_usable() {
+    for mode in MODES {
+        assert_success(
+            mode,
+            &cargo(mode, &["test", "--quiet", "--package", "consumer"]),
+        );
+    }
+}
+
+#[test]
+fn package_macros_are_usable_in_tests() {
+    for mode in MODES {
+        assert_success(
+            mode,
+            &cargo(
+                mode,
+                &[
+                    "test",
+                    "--quiet",
+                    "--test",
+                    "package",
+                    "--package",
+                    "definer",
+                ],
+            ),
+        );
+    }
+}
+
+/// Doctests run against the `pub macro` in the nightly-doc mode, so this
+/// checks that documentation examples behave the same in every mode.
+#[test]
+fn doctests() {
+    for mode in MODES {
+        assert_success(
+            mode,
+            &cargo(mode, &["test", "--quiet", "--doc", "--package", "definer"]),
+        );
+    }
+}
+
+#[test]
+fn restricted_macros_are_rejected() {
+    for mode in MODES {
+        let output = cargo(mode, &["check", "--quiet", "--package", "rejected"]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "{}: {}", mode.name, stderr);
+        assert!(
+            stderr.contains("macro `internal` is private"),
+            "{}: {}",
+            mode.name,
+            stderr,
+        );
+        // The error points at the malformed part of the path, in the included file.
+        assert!(
+            stderr.contains("expects a path to an enclosing module after `in`")
+                && stderr.contains("rejected/src/malformed.rs:3:46"),
+            "{}: {}",
+            mode.name,
+            stderr,
+        );
+        // The error points at the expected path.
+        assert!(
+            stderr.contains("could not find `original` in the crate root")
+                && stderr.contains("rejected/src/lib.rs:25:49"),
+            "{}: {}",
+            mode.name,
+            stderr,
+        );
+        // Two macros of the same name in one module clash, at the second.
+        assert!(
+            stderr.contains("the name `twice` is defined multiple times")
+                && stderr.contains("rejected/src/lib.rs:38:18"),
+            "{}: {}",
+            mode.name,
+            stderr,
+        );
+        // Documentation shows feature-gated macros even without the feature.
+        if !mode.rustflags.contains("--cfg doc") {
+            assert!(
+                stderr.contains("`three!` requires the `extra` feature"),
+                "{}: {}",
+                mode.name,
+                stderr,
+            );
+            // A catch-all arm lists the macro's arms.
+            assert!(
+                stderr.contains("`pick!` doesn't accept `third 1, 2, 3`; expected one of:")
+                    && stderr.contains("pick!(first $a:expr, $b:expr)\n")
+                    && stderr.contains("pick!(second $a:expr, $b:expr)\n"),
+                "{}: {}",
+                mode.name,
+                stderr,
+            );
+        }
+    }
+}
+
+#[test]
+fn friend_macros_reject_strangers() {
+    for mode in MODES {
+        let output = cargo(mode, &["check", "--quiet", "--package", "stranger"]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "{}: {}", mode.name, stderr);
+        assert!(
+            stderr.contains("no rules expected `__macro_pub_friend_stranger`"),
+            "{}: {}",
+            mode.name,
+            stderr,
+        );
+        assert!(
+            stderr.contains("no rules expected `__macro_pub_package_stranger`"),
+            "{}: {}",
+            mode.name,
+            stderr,
+        );
+    }
+}
+
+#[test]
+fn docs_build() {
+    for mode in MODES {
+        assert_documents_macros(
+            mode,
+            &["doc", "--quiet", "--no-deps", "--package", "definer"],
+        );
+    }
+}
+
+#[test]
+fn private_docs_list_macros_once() {
+    for mode in MODES {
+        let target = format!("{}-private", mode.name);
+        let output = cargo_in(
+            &target,
+            mode,
+            &[
+                "doc",
+                "--quiet",
+                "--no-deps",
+                "--package",
+                "definer",
+                "--document-private-items",
+            ],
+        );
+        assert_success(mode, &output);
+        let doc = Path::new(env!("CARGO_TARGET_TMPDIR"))
+            .join("workspace")
+            .join(target)
+            .join("doc/definer");
+        let all = fs::read_to_string(doc.join("all.html")).unwrap();
+        for page in &[
+            "exported/macro.one.html",
+            "paths/macro.call_helper.html",
+            "paths/macro.call_macro.html",
+            "restricted/macro.internal.html",
+        ] {
+            let link = format!("href=\"{}\"", page);
+            assert_eq!(all.matches(&link).count(), 1, "{}: {}", mode.name, all);
+        }
+        assert!(!all.contains("macro_impl_"), "{}: {}", mode.name, all);
+        for module in &["exported", "paths", "restricted"] {
+            let index = fs::read_to_string(doc.join(module).join("index.html")).unwrap();
+            assert!(!index.contains("Re-exports"), "{}: {}", mode.name, index);
+        }
+    }
+}
+
+#[test]
+fn facades_reexport_macros() {
+    for mode in MODES {
+        assert_success(
+            mode,
+            &cargo(mode, &["test", "--quiet", "--package", "facade"]),
+        );
+        let output = cargo(
+            mode,
+            &["doc", "--quiet", "--no-deps", "--package", "facade"],
+        );
+        assert_success(mode, &output);
+        let doc = Path::new(env!("CARGO_TARGET_TMPDIR"))
+            .join("workspace")
+            .join(mode.name)
+            .join("doc/facade");
+        for page in &[
+            "macro.one.html",
+            "paths/macro.call_helper.html",
+            "paths/macro.call_macro.html",
+        ] {
+            let page = fs::read_to_string(doc.join(page)).unwrap();
+            assert!(!page.contains("macro_impl_"), "{}: {}", mode.name, page);
+        }
+    }
+}
+
+/// `adopter` trials `#[macro_pub]` behind a feature, so its macros must work
+/// the same with the feature enabled or not.
+#[test]
+fn feature_gated_adoption() {
+    for mode in MODES {
+        for features in &["", "macro-pub"] {
+            assert_success(
+                mode,
+                &cargo(
+                    mode,
+                    &[
+                        "test",
+                        "--quiet",
+                        "--package",
+                        "adopter",
+                        "--features",
+                        features,
+                    ],
+                ),
+            );
+        }
+    }
+}
+
+#[test]
+fn editions() {
+    for mode in MODES {
+        assert_success(
+            mode,
+            &cargo(
+                mode,
+                &[
+                    "test",
+                    "--quiet",
+                    "--package=edition2015",
+                    "--package=edition2018",
+                    "--package=edition2021",
+                    "--package=edition2024",
+                ],
+            ),
+        );
+    }
+}
+
+/// Documents `definer`, checking that its macros get their own pages and are
+/// summarized, once, on their module's page.
+#[track_caller]
+fn assert_documents_macros(mode: &Mode, args: &[&str]) -> Output {
+    let output = cargo(mode, args);
+    assert_success(mode, &output);
+    let module = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("workspace")
+        .join(mode.name)
+        .join("doc/definer/exported");
+    let page = module.join("macro.one.html");
+    assert!(page.exists(), "{} was not generated", page.display());
+    let page = fs::read_to_string(page).unwrap();
+    assert!(
+        page.contains("Available since version 0.1.0."),
+        "{}: {}",
+        mode.name,
+        page,
+    );
+    // Platform badges, from the enclosing module's `#[cfg]` and the macro's
+    // own `doc(cfg)`.
+    for page in &["../platform/macro.native.html", "../macro.portable.html"] {
+        let page = fs::read_to_string(module.join(page)).unwrap();
+        assert!(
+            page.contains("stab portability") && page.contains("Unix or Windows"),
+            "{}: {}",
+            mode.name,
+            page,
+        );
+    }
+    let unstable = module.join("../unstable/macro.two.html");
+    assert!(!unstable.exists(), "{} was generated", unstable.display());
+    let index = fs::read_to_string(module.join("index.html")).unwrap();
+    assert_eq!(
+        index.matches("Expands to <code>1</code>.").count(),
+        1,
+        "{}: {}",
+        mode.name,
+        index,
+    );
+    output
+}
+
+#[test]
+fn docs_rs_documents_macros() {
+    let output = assert_documents_macros(&DOCS_RS, &["doc", "--no-deps", "--package", "definer"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("feature(decl_macro, rustc_attrs)"),
+        "{}",
+        stderr,
+    );
+}
+
+#[test]
+fn nightly_docs_feature_documents_macros() {
+    assert_documents_macros(
+        &NIGHTLY_DOCS,
+        &[
+            "doc",
+            "--quiet",
+            "--no-deps",
+            "--package",
+            "definer",
+            "--features",
+            "macro_pub/nightly-docs",
+        ],
+    );
+}
+
+/// Rustdoc's JSON output is unstable, so it's only produced with a nightly
+/// rustdoc, where the documented expansion is detected. Check that each
+/// configuration using it describes macros only under their public paths.
+#[test]
+fn json_docs_use_public_paths() {
+    let nightly = &MODES[1];
+    let configurations: &[(&Mode, &[&str])] = &[
+        (nightly, &[]),
+        (&DOCS_RS, &[]),
+        (&NIGHTLY_DOCS, &["--features", "macro_pub/nightly-docs"]),
+    ];
+    for &(mode, features) in configurations {
+        let target = format!("{}-json", mode.name);
+        let mut args = vec!["rustdoc", "--quiet", "--package", "definer"];
+        args.extend(features);
+        args.extend(&["--", "-Zunstable-options", "--output-format", "json"]);
+        assert_success(mode, &cargo_in(&target, mode, &args));
+
+        let json = fs::read_to_string(
+            Path::new(env!("CARGO_TARGET_TMPDIR"))
+                .join("workspace")
+                .join(target)
+                .join("doc/definer.json"),
+        )
+        .unwrap();
+        for path in &[
+            r#"["definer","exported","one"]"#,
+            r#"["definer","paths","call_helper"]"#,
+            r#"["definer","paths","call_macro"]"#,
+            r#"["definer","hygiene","add_one"]"#,
+            r#"["definer","hygiene","declare"]"#,
+        ] {
+            let entry = format!(r#""path":{},"kind":"macro""#, path);
+            assert!(json.contains(&entry), "{}: {} is missing", mode.name, path);
+        }
+        assert!(!json.contains("macro_impl_"), "{}: {}", mode.name, json);
+    }
+}
+
+/// The manifest is only written when a crate is compiled, so this builds into
+/// a fresh target directory.
+#[test]
+fn manifest_lists_macros() {
+    let mode = &MODES[0];
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("workspace")
+        .join("manifest");
+    let _ = fs::remove_dir_all(&target_dir);
+    fs::create_dir_all(&target_dir).unwrap();
+    let manifest = target_dir.join("macros.jsonl");
+    let output = command("manifest", mode)
+        .env("MACRO_PUB_MANIFEST", &manifest)
+        .args(&["check", "--quiet", "--package", "definer"])
+        .output()
+        .unwrap();
+    assert_success(mode, &output);
+
+    let manifest = fs::read_to_string(manifest).unwrap();
+    let lines = manifest.lines().collect::<Vec<_>>();
+    for entry in &[
+        r#""name":"one","visibility":"pub","mangled":"macro_impl_"#,
+        r#""name":"internal","visibility":"pub(crate)","mangled":null,"#,
+        r#""export_as":"shared_unstable","deprecate_as":null,"aliases":[],"hygienic_as":null,"friends":["consumer"]"#,
+    ] {
+        let matches = lines.iter().filter(|line| line.contains(entry)).count();
+        assert_eq!(matches, 1, "{} in {}", entry, manifest);
+    }
+    assert!(
+        lines
+            .iter()
+            .all(|line| line.starts_with(r#"{"crate":"definer","#)),
+        "{}",
+        manifest,
+    );
+}
+
+/// Macros of the same name, in different files or with an `expect_path`, get
+/// an entry each in the lock file, and build against it again without errors.
+#[test]
+fn lock_file_tells_same_names_apart() {
+    let mode = &MODES[0];
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("workspace")
+        .join("lockfile");
+    let _ = fs::remove_dir_all(&target_dir);
+    fs::create_dir_all(&target_dir).unwrap();
+    let lock_file = target_dir.join("macro_pub.lock");
+    for target in &["lockfile", "lockfile-again"] {
+        let output = command(target, mode)
+            .env("MACRO_PUB_LOCKFILE", &lock_file)
+            .args(&["check", "--quiet", "--package", "definer"])
+            .output()
+            .unwrap();
+        assert_success(mode, &output);
+    }
+
+    let lock_file = fs::read_to_string(lock_file).unwrap();
+    for path in &[
+        "crate::twins::a::twin",
+        "crate::twins::b::twin",
+        "src/lib.rs:twin",
+        "src/included.rs:twin",
+    ] {
+        let prefix = format!("{} macro_impl_", path);
+        let matches = lock_file
+            .lines()
+            .filter(|line| line.starts_with(&prefix))
+            .count();
+        assert_eq!(matches, 1, "{} in {}", path, lock_file);
+    }
+}
+
+/// Rustc tracks the file at `MACRO_PUB_CONFIG`, so changing it rebuilds the
+/// crates using it without cleaning them.
+#[test]
+fn config_file_changes_rebuild() {
+    let mode = &MODES[0];
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("workspace")
+        .join("config");
+    let _ = fs::remove_dir_all(&target_dir);
+    fs::create_dir_all(&target_dir).unwrap();
+    let config = target_dir.join("macro_pub.toml");
+    let check = || {
+        command("config", mode)
+            .env("MACRO_PUB_CONFIG", &config)
+            .args(&["check", "--quiet", "--package", "definer"])
+            .output()
+            .unwrap()
+    };
+    fs::write(&config, "[lints]\npointless_options = \"allow\"\n").unwrap();
+    assert_success(mode, &check());
+
+    fs::write(&config, "[lints]\npointless_options = \"forbid\"\n").unwrap();
+    let output = check();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{}", stderr);
+    assert!(
+        stderr.contains("`MACRO_PUB_CONFIG` line 2: lint `pointless_options` expects"),
+        "{}",
+        stderr,
+    );
+}
+
+/// Uses recorded in the manifest show which tracked macros could be narrower,
+/// once the defining crate is rebuilt after the crates using them.
+#[test]
+fn manifest_suggests_narrowing() {
+    let mode = &MODES[0];
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("workspace")
+        .join("narrowing");
+    let _ = fs::remove_dir_all(&target_dir);
+    fs::create_dir_all(&target_dir).unwrap();
+    let manifest = target_dir.join("macros.jsonl");
+    let build = |args: &[&str]| {
+        let output = command("narrowing", mode)
+            .env("MACRO_PUB_MANIFEST", &manifest)
+            .args(args)
+            .output()
+            .unwrap();
+        assert_success(mode, &output);
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+    build(&["check", "--quiet", "--tests", "--package", "consumer"]);
+    build(&["clean", "--quiet", "--package", "definer"]);
+    let stderr = build(&["check", "--quiet", "--package", "definer"]);
+
+    assert!(
+        stderr.contains(
+            "`#[macro_pub]` macro `nine!` is `pub`, but `MACRO_PUB_MANIFEST` only records uses \
+             of it in `definer`; consider `#[macro_pub(crate)]`"
+        ),
+        "{}",
+        stderr,
+    );
+    assert!(!stderr.contains("`eight!` is `pub`"), "{}", stderr);
+}
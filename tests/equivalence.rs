@@ -0,0 +1,464 @@
+//! Checks that `#[macro_pub]` macros accept exactly the input the
+//! `macro_rules!` macros they were written as do.
+//!
+//! For each `#[macro_pub]` macro in the `tests/expand/*.rs` fixtures, sample
+//! invocations are derived from its arms' matchers: each fragment filled in
+//! with a few examples of its kind, each repetition repeated a few times, and
+//! some truncated or junk input besides. The invocations are put after the
+//! macro, and the fixture is compiled as it is and with `#[macro_pub]`
+//! replaced by an inert attribute, so that it's a plain `macro_rules!` macro.
+//! Both builds must fail to match the same invocations. Whatever the macros
+//! expand to is irrelevant, so other errors are ignored. Options meant to
+//! change what a macro accepts, `extend`, `extend_from`, and `internal_rules`
+//! for the internal rules, are left out.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+
+/// Examples of each kind of fragment, the first used by default.
+fn fragments(kind: &str) -> &'static [&'static str] {
+    match kind {
+        "expr" | "expr_2021" => &["1", "a + b", "f(x)"],
+        "ident" => &["a", "r#type"],
+        "ty" => &["u8", "Vec<u8>", "&'a str"],
+        "path" => &["a::b", "a::<u8>"],
+        "pat" | "pat_param" => &["_", "Some(x)"],
+        "literal" => &["1", "\"s\"", "-1"],
+        "lifetime" => &["'a"],
+        "block" => &["{}", "{ 1 }"],
+        "stmt" => &["let a = 1", "f()"],
+        "item" => &["struct S;", "fn f() {}"],
+        "vis" => &["", "pub", "pub(crate)"],
+        "meta" => &["a", "a = 1", "a(b)"],
+        _ => &["x", "(1 2)", "'a", "\"s\""],
+    }
+}
+
+/// Input no macro is meant to accept, and that most reject.
+const JUNK: &[&str] = &["", "! !", "@ 0 0 0", "; ;"];
+
+/// How a part of a matcher can be filled in: the first alternative by default,
+/// and each of the others in turn to vary it.
+fn alternatives(matcher: &[TokenTree], i: &mut usize) -> Vec<TokenStream> {
+    let tt = matcher[*i].clone();
+    *i += 1;
+    match tt {
+        TokenTree::Punct(ref dollar) if dollar.as_char() == '$' => match matcher.get(*i) {
+            // `$name:kind`.
+            Some(TokenTree::Ident(_)) => {
+                let kind = match matcher.get(*i + 2) {
+                    Some(TokenTree::Ident(kind)) => kind.to_string(),
+                    _ => String::new(),
+                };
+                *i += 3;
+                fragments(&kind)
+                    .iter()
+                    .map(|fragment| fragment.parse().unwrap())
+                    .collect()
+            }
+            // `$(...) sep op`.
+            Some(TokenTree::Group(group)) => {
+                let inner = samples(group.stream());
+                *i += 1;
+                let is_op = |tt: Option<&TokenTree>| matches!(tt, Some(TokenTree::Punct(op)) if "*+?".contains(op.as_char()));
+                let separator = if is_op(matcher.get(*i)) && !is_op(matcher.get(*i + 1)) {
+                    None
+                } else {
+                    *i += 1;
+                    Some(matcher[*i - 1].clone())
+                };
+                let op = match matcher.get(*i) {
+                    Some(TokenTree::Punct(op)) => op.as_char(),
+                    _ => '*',
+                };
+                *i += 1;
+                let repeat = |sample: &TokenStream, n: usize| {
+                    let mut repeated = TokenStream::new();
+                    for j in 0..n {
+                        if j > 0 {
+                            repeated.extend(separator.clone());
+                        }
+                        repeated.extend(sample.clone());
+                    }
+                    repeated
+                };
+                let counts: &[usize] = match op {
+                    '+' => &[1, 2],
+                    '?' => &[1, 0],
+                    _ => &[1, 0, 2],
+                };
+                let mut alternatives = counts
+                    .iter()
+                    .map(|&n| repeat(&inner[0], n))
+                    .collect::<Vec<_>>();
+                alternatives.extend(inner[1..].iter().map(|sample| repeat(sample, 1)));
+                alternatives
+            }
+            _ => vec![TokenStream::from(tt)],
+        },
+        TokenTree::Group(ref group) => samples(group.stream())
+            .into_iter()
+            .map(|sample| {
+                TokenStream::from(TokenTree::Group(Group::new(group.delimiter(), sample)))
+            })
+            .collect(),
+        tt => vec![TokenStream::from(tt)],
+    }
+}
+
+/// Invocations matching `matcher`: one with every part filled in by default,
+/// then one more for each other way to fill in each part.
+fn samples(matcher: TokenStream) -> Vec<TokenStream> {
+    let matcher = matcher.into_iter().collect::<Vec<_>>();
+    let mut parts = Vec::new();
+    let mut i = 0;
+    while i < matcher.len() {
+        parts.push(alternatives(&matcher, &mut i));
+    }
+    let sample = |varied: usize, alternative: usize| {
+        let mut sample = TokenStream::new();
+        for (j, part) in parts.iter().enumerate() {
+            sample.extend(part[if j == varied { alternative } else { 0 }].clone());
+        }
+        sample
+    };
+    let mut samples = vec![sample(usize::max_value(), 0)];
+    for (j, part) in parts.iter().enumerate() {
+        samples.extend((1..part.len()).map(|alternative| sample(j, alternative)));
+    }
+    samples
+}
+
+/// Sample invocations of the macro with the arms `body`: matches for each
+/// arm, each also without its last token, and junk. With `internal_rules`,
+/// arms starting with `@` are left out.
+fn invocations(body: TokenStream, internal_rules: bool) -> Vec<String> {
+    let mut invocations = Vec::new();
+    let mut tokens = body.into_iter();
+    while let Some(tt) = tokens.next() {
+        let matcher = match tt {
+            // Arm attributes.
+            TokenTree::Punct(ref punct) if punct.as_char() == '#' => {
+                tokens.next();
+                continue;
+            }
+            TokenTree::Group(matcher) => matcher,
+            _ => continue,
+        };
+        // `=> { ... }` and the `;`.
+        tokens.next();
+        tokens.next();
+        tokens.next();
+        let stream = matcher.stream();
+        if internal_rules && is_punct(stream.clone().into_iter().next().as_ref(), '@') {
+            continue;
+        }
+        for sample in samples(matcher.stream()) {
+            let sample = sample.into_iter().collect::<Vec<_>>();
+            invocations.push(sample.iter().cloned().collect::<TokenStream>().to_string());
+            if !sample.is_empty() {
+                let mut end = sample.len() - 1;
+                // Not splitting a lifetime.
+                if is_punct(sample.get(end.wrapping_sub(1)), '\'') {
+                    end -= 1;
+                }
+                let truncated = sample[..end].iter().cloned();
+                invocations.push(truncated.collect::<TokenStream>().to_string());
+            }
+        }
+    }
+    invocations.extend(JUNK.iter().map(|junk| junk.to_string()));
+    invocations.sort();
+    invocations.dedup();
+    invocations
+}
+
+fn is_punct(tt: Option<&TokenTree>, c: char) -> bool {
+    matches!(tt, Some(TokenTree::Punct(punct)) if punct.as_char() == c)
+}
+
+/// Splits `stream` into items, with their attributes.
+fn items(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let tokens = stream.into_iter().collect::<Vec<_>>();
+    let mut items = Vec::new();
+    let mut item = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        // Inner attributes are items of their own here.
+        if item.is_empty() && is_punct(tokens.get(i), '#') && is_punct(tokens.get(i + 1), '!') {
+            items.push(tokens[i..i + 3].to_vec());
+            i += 3;
+            continue;
+        }
+        let tt = tokens[i].clone();
+        i += 1;
+        let end = match tt {
+            TokenTree::Punct(ref punct) => punct.as_char() == ';',
+            TokenTree::Group(ref group) => {
+                group.delimiter() == Delimiter::Brace && !is_punct(item.last(), '#')
+            }
+            _ => false,
+        };
+        item.push(tt);
+        if end {
+            if is_punct(tokens.get(i), ';') {
+                item.push(tokens[i].clone());
+                i += 1;
+            }
+            items.push(item);
+            item = Vec::new();
+        }
+    }
+    items
+}
+
+/// The `#[macro_pub]` attribute macro's arguments, if `attr` is one.
+fn macro_pub_args(attr: &Group) -> Option<TokenStream> {
+    let tokens = attr.stream().into_iter().collect::<Vec<_>>();
+    let path = match tokens.len() {
+        n if n >= 4 && is_punct(tokens.get(1), ':') => &tokens[3..],
+        _ => &tokens[..],
+    };
+    match path {
+        [TokenTree::Ident(name)] if name == "macro_pub" => Some(TokenStream::new()),
+        [TokenTree::Ident(name), TokenTree::Group(args)] if name == "macro_pub" => {
+            Some(args.stream())
+        }
+        _ => None,
+    }
+}
+
+/// Whether `args` has the option `name`.
+fn has_option(args: &TokenStream, name: &str) -> bool {
+    args.clone()
+        .into_iter()
+        .any(|tt| matches!(tt, TokenTree::Ident(ident) if ident == name))
+}
+
+/// `body`, the arms of a macro, without attributes on the arms, which only
+/// `#[macro_pub]` accepts.
+fn without_arm_attributes(body: &Group) -> TokenTree {
+    let mut arms = TokenStream::new();
+    let mut tokens = body.stream().into_iter();
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Punct(ref punct) if punct.as_char() == '#' => {
+                tokens.next();
+            }
+            tt => arms.extend(Some(tt)),
+        }
+    }
+    TokenTree::Group(Group::new(body.delimiter(), arms))
+}
+
+/// Rewrites the items in `stream` to `source`, a line each, with modules
+/// opened and closed on lines of their own, and sample invocations after
+/// each `#[macro_pub]` macro, recording their lines in `lines`. With
+/// `original`, `#[macro_pub]` is replaced with an inert attribute.
+fn rewrite(stream: TokenStream, original: bool, source: &mut String, lines: &mut Vec<usize>) {
+    for item in items(stream) {
+        let mut line = TokenStream::new();
+        let mut cfgs = TokenStream::new();
+        let mut args = None;
+        let mut i = 0;
+        while is_punct(item.get(i), '#') {
+            let attr = match item.get(i + 1) {
+                Some(TokenTree::Group(attr)) => attr,
+                _ => break,
+            };
+            match macro_pub_args(attr) {
+                Some(macro_pub_args) => {
+                    args = Some(macro_pub_args);
+                    if original {
+                        line.extend("#[allow(unused_macros)]".parse::<TokenStream>());
+                    } else {
+                        line.extend(item[i..i + 2].iter().cloned());
+                    }
+                }
+                None => {
+                    let first = attr.stream().into_iter().next();
+                    if matches!(first, Some(TokenTree::Ident(ref ident)) if ident == "cfg") {
+                        cfgs.extend(item[i..i + 2].iter().cloned());
+                    }
+                    line.extend(item[i..i + 2].iter().cloned());
+                }
+            }
+            i += 2;
+        }
+        let mut rest = item[i..].to_vec();
+        let module = rest
+            .iter()
+            .any(|tt| matches!(tt, TokenTree::Ident(ident) if ident == "mod"));
+        match rest.last() {
+            Some(TokenTree::Group(body)) if module && body.delimiter() == Delimiter::Brace => {
+                let body = body.stream();
+                line.extend(rest[..rest.len() - 1].iter().cloned());
+                source.push_str(&format!("{} {{\n", line));
+                rewrite(body, original, source, lines);
+                source.push_str("}\n");
+                continue;
+            }
+            _ => {}
+        }
+        let (name, body) = match (&args, &rest[..]) {
+            (
+                Some(_),
+                [TokenTree::Ident(keyword), _, TokenTree::Ident(name), TokenTree::Group(body), ..],
+            ) if keyword == "macro_rules" => (name.clone(), body.clone()),
+            _ => {
+                line.extend(rest);
+                source.push_str(&format!("{}\n", line));
+                continue;
+            }
+        };
+        if original {
+            rest[3] = without_arm_attributes(&body);
+        }
+        line.extend(rest);
+        source.push_str(&format!("{}\n", line));
+        let args = args.unwrap();
+        // `extend` parts aren't macros of their own, and extending a macro
+        // deliberately accepts more input.
+        if has_option(&args, "extend") || has_option(&args, "extend_from") {
+            continue;
+        }
+        // `internal_rules` deliberately rejects internal rules by name.
+        let internal_rules = has_option(&args, "internal_rules");
+        for invocation in invocations(body.stream(), internal_rules) {
+            source.push_str(&format!("{} {}! {{ {} }}\n", cfgs, name, invocation));
+            lines.push(source.matches('\n').count());
+        }
+    }
+}
+
+/// An invocation no build can accept, so a build that stops before expanding
+/// macros, like for a syntax error, fails rather than rejecting nothing.
+const CANARY: &str = "macro_rules! canary { () => {}; }\ncanary! { rejected }\n";
+
+/// Checks `source`, returning which of the invocations on `lines` fail to
+/// match their macro.
+fn rejected(project: &Path, source: &str, lines: &[usize]) -> Vec<usize> {
+    fs::write(project.join("src/lib.rs"), format!("{}{}", source, CANARY)).unwrap();
+    let output = Command::new(env!("CARGO"))
+        .current_dir(project)
+        .env_remove("RUSTFLAGS")
+        .env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .args(["check", "--quiet", "--message-format=short"])
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut rejected = stderr
+        .lines()
+        .filter_map(|line| {
+            if !line.starts_with("src/lib.rs:") {
+                return None;
+            }
+            let split = line.find(": error: ")?;
+            let (location, message) = (&line["src/lib.rs:".len()..split], &line[split + 9..]);
+            let is_match_error = message.starts_with("no rules expected")
+                || message.starts_with("unexpected end of macro invocation")
+                || message.starts_with("local ambiguity");
+            match is_match_error {
+                true => location.split(':').next()?.parse::<usize>().ok(),
+                false => None,
+            }
+        })
+        .collect::<Vec<_>>();
+    let canary = source.matches('\n').count() + 2;
+    assert!(
+        rejected.contains(&canary),
+        "didn't expand macros in:\n{}\n{}",
+        source,
+        stderr,
+    );
+    rejected.retain(|line| lines.contains(line));
+    rejected.sort();
+    rejected.dedup();
+    rejected
+}
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/expand");
+    let mut fixtures = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_str().unwrap();
+            name.ends_with(".rs") && !name.ends_with(".expanded.rs")
+        })
+        .collect::<Vec<_>>();
+    fixtures.sort();
+    fixtures
+}
+
+#[test]
+fn equivalence() {
+    let project = Path::new(env!("CARGO_TARGET_TMPDIR")).join("equivalence");
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::write(
+        project.join("Cargo.toml"),
+        format!(
+            r#"
+                [package]
+                name = "equivalence"
+                version = "0.0.0"
+                edition = "2021"
+
+                [dependencies]
+                macro_pub = {{ path = {:?} }}
+
+                [workspace]
+            "#,
+            env!("CARGO_MANIFEST_DIR"),
+        ),
+    )
+    .unwrap();
+
+    let (mut invocations, mut rejections) = (0, 0);
+    let mut differences = Vec::new();
+    for fixture in fixtures() {
+        let stream = fs::read_to_string(&fixture)
+            .unwrap()
+            .parse::<TokenStream>()
+            .unwrap();
+        let (mut original, mut transformed, mut lines) = (String::new(), String::new(), Vec::new());
+        rewrite(stream.clone(), true, &mut original, &mut Vec::new());
+        rewrite(stream, false, &mut transformed, &mut lines);
+        let expected = rejected(&project, &original, &lines);
+        let actual = rejected(&project, &transformed, &lines);
+        invocations += lines.len();
+        rejections += expected.len();
+        let source = transformed.lines().collect::<Vec<_>>();
+        for line in expected.iter().filter(|line| !actual.contains(line)) {
+            differences.push(format!(
+                "{}: only rejected without `#[macro_pub]`: {}",
+                fixture.display(),
+                source[line - 1],
+            ));
+        }
+        for line in actual.iter().filter(|line| !expected.contains(line)) {
+            differences.push(format!(
+                "{}: only rejected with `#[macro_pub]`: {}",
+                fixture.display(),
+                source[line - 1],
+            ));
+        }
+    }
+
+    // Otherwise, the samples are too lenient to tell the builds apart.
+    assert!(
+        0 < rejections && rejections < invocations,
+        "{} of {} invocations rejected",
+        rejections,
+        invocations,
+    );
+    assert!(
+        differences.is_empty(),
+        "{} invocations matched differently:\n{}",
+        differences.len(),
+        differences.join("\n"),
+    );
+}
@@ -0,0 +1,130 @@
+//! Snapshot tests pinning the exact expansion of `#[macro_pub]`.
+//!
+//! Every `tests/expand/*.rs` fixture is compiled as a library with
+//! `-Zunpretty=expanded` in each configuration from `CONFIGS`, and the output
+//! is compared against the `*.<config>.expanded.rs` file next to it. Missing
+//! snapshots are written out; set `MACRO_PUB_BLESS=1` to overwrite changed
+//! ones.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+struct Config {
+    name: &'static str,
+    /// `RUSTFLAGS` for the whole build. The nightly configuration is selected
+    /// by setting the probed cfg directly rather than relying on the probe
+    /// passing on whatever toolchain runs the tests.
+    rustflags: &'static str,
+    /// Whether the fixture itself is expanded with `--cfg doc`.
+    cfg_doc: bool,
+}
+
+const CONFIGS: &[Config] = &[
+    Config {
+        name: "stable",
+        rustflags: "",
+        cfg_doc: false,
+    },
+    Config {
+        name: "nightly",
+        rustflags:
+            "--cfg has_simple_decl_macro --cfg has_semiopaque_transparency --cfg doc_decl_macro",
+        cfg_doc: false,
+    },
+    Config {
+        name: "nightly-doc",
+        rustflags:
+            "--cfg has_simple_decl_macro --cfg has_semiopaque_transparency --cfg doc_decl_macro",
+        cfg_doc: true,
+    },
+];
+
+fn expand(fixture: &Path, config: &Config) -> String {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("expand");
+    let project = root.join("fixture");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(
+        project.join("Cargo.toml"),
+        format!(
+            r#"
+                [package]
+                name = "fixture"
+                version = "0.0.0"
+                edition = "2021"
+
+                [lib]
+                path = {:?}
+
+                [dependencies]
+                macro_pub = {{ path = {:?} }}
+
+                [workspace]
+            "#,
+            fixture,
+            env!("CARGO_MANIFEST_DIR"),
+        ),
+    )
+    .unwrap();
+
+    let mut command = Command::new(env!("CARGO"));
+    command
+        .current_dir(&project)
+        .env("RUSTC_BOOTSTRAP", "fixture")
+        .env("RUSTFLAGS", config.rustflags)
+        .env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .args(["rustc", "--lib", "--quiet", "--target-dir"])
+        .arg(root.join(config.name))
+        .args(["--", "-Zunpretty=expanded"]);
+    if config.cfg_doc {
+        command.args(["--cfg", "doc"]);
+    }
+
+    let output = command.output().unwrap();
+    assert!(
+        output.status.success(),
+        "failed to expand {}:\n{}",
+        fixture.display(),
+        String::from_utf8_lossy(&output.stderr),
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/expand");
+    let mut fixtures = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_str().unwrap();
+            name.ends_with(".rs") && !name.ends_with(".expanded.rs")
+        })
+        .collect::<Vec<_>>();
+    fixtures.sort();
+    fixtures
+}
+
+#[test]
+fn expansions() {
+    let bless = env::var_os("MACRO_PUB_BLESS").is_some();
+    let mut mismatches = Vec::new();
+
+    for fixture in fixtures() {
+        for config in CONFIGS {
+            let actual = expand(&fixture, config);
+            let snapshot = fixture.with_extension(format!("{}.expanded.rs", config.name));
+            match fs::read_to_string(&snapshot) {
+                Ok(expected) if expected == actual => {}
+                Ok(_) if !bless => mismatches.push(snapshot),
+                _ => fs::write(&snapshot, actual).unwrap(),
+            }
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "expansion changed (rerun with MACRO_PUB_BLESS=1 to accept):\n{:#?}",
+        mismatches,
+    );
+}
@@ -0,0 +1,34 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[rustc_macro_transparency = "semiopaque"]
+pub macro documented_arms {
+    () => {}, ($e:expr) => { #[allow(unused_parens)] ($e) },
+}
+const _: () =
+    {
+        #[deprecated(note =
+        "`#[macro_pub]` removed this attribute; attributes are not allowed on macro arms")]
+        #[allow(non_upper_case_globals)]
+        const macro_pub_warning: () = ();
+        let _ = macro_pub_warning;
+    };
+const _: () =
+    {
+        #[deprecated(note =
+        "`#[macro_pub]` removed this attribute; attributes are not allowed on macro arms")]
+        #[allow(non_upper_case_globals)]
+        const macro_pub_warning: () = ();
+        let _ = macro_pub_warning;
+    };
+const _: () =
+    {
+        #[deprecated(note =
+        "`#[macro_pub]` removed this attribute; attributes are not allowed on macro arms")]
+        #[allow(non_upper_case_globals)]
+        const macro_pub_warning: () = ();
+        let _ = macro_pub_warning;
+    };
@@ -0,0 +1,36 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! macro_impl_164787463622351576886141499436360480237_documented_arms {
+    () => {}; ($e:expr) => { #[allow(unused_parens)] ($e) };
+}
+#[doc(inline)]
+pub use macro_impl_164787463622351576886141499436360480237_documented_arms as documented_arms;
+const _: () =
+    {
+        #[deprecated(note =
+        "`#[macro_pub]` removed this attribute; attributes are not allowed on macro arms")]
+        #[allow(non_upper_case_globals)]
+        const macro_pub_warning: () = ();
+        let _ = macro_pub_warning;
+    };
+const _: () =
+    {
+        #[deprecated(note =
+        "`#[macro_pub]` removed this attribute; attributes are not allowed on macro arms")]
+        #[allow(non_upper_case_globals)]
+        const macro_pub_warning: () = ();
+        let _ = macro_pub_warning;
+    };
+const _: () =
+    {
+        #[deprecated(note =
+        "`#[macro_pub]` removed this attribute; attributes are not allowed on macro arms")]
+        #[allow(non_upper_case_globals)]
+        const macro_pub_warning: () = ();
+        let _ = macro_pub_warning;
+    };
@@ -0,0 +1,13 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+#[macro_pub::macro_pub]
+macro_rules! documented_arms {
+    /// Expands to nothing.
+    () => {};
+    #[cfg(all())]
+    /// Expands to the expression.
+    ($e:expr) => {
+        #[allow(unused_parens)]
+        ($e)
+    };
+}
@@ -0,0 +1,18 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[doc = " Outer doc comment."]
+#[doc = " Inner doc comment, after the attribute."]
+#[allow(unused_macros)]
+#[rustc_macro_transparency = "semiopaque"]
+pub macro attributed { () => {}, }
+
+#[doc = "private"]
+#[rustc_macro_transparency = "semiopaque"]
+pub(self) macro private { () => {}, }
+#[doc = "private"]
+#[doc(hidden)]
+macro_rules! private { () => {}; }
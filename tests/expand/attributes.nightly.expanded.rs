@@ -0,0 +1,20 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[doc = " Outer doc comment."]
+#[doc = " Inner doc comment, after the attribute."]
+#[allow(unused_macros)]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! macro_impl_206514963479031802517283925996377248958_attributed {
+    () => {};
+}
+#[doc(inline)]
+pub use macro_impl_206514963479031802517283925996377248958_attributed as attributed;
+
+#[doc = "private"]
+macro_rules! private { () => {}; }
+#[allow(unused_imports)]
+pub(self) use private as private;
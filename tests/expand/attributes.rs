@@ -0,0 +1,15 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+/// Outer doc comment.
+#[macro_pub::macro_pub]
+/// Inner doc comment, after the attribute.
+#[allow(unused_macros)]
+macro_rules! attributed {
+    () => {};
+}
+
+#[macro_pub::macro_pub(self)]
+#[doc = "private"]
+macro_rules! private {
+    () => {};
+}
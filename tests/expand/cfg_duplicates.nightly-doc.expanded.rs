@@ -0,0 +1,11 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+
+#[rustc_macro_transparency = "semiopaque"]
+pub macro log { ($e:expr) => { () }, }
+
+pub fn logged() { () }
@@ -0,0 +1,15 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! macro_impl_35674162249552874049569258196379945002_log {
+    ($e:expr) => { () };
+}
+#[doc(inline)]
+pub use macro_impl_35674162249552874049569258196379945002_log as log;
+
+pub fn logged() { () }
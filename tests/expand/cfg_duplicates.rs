@@ -0,0 +1,21 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+#[macro_pub::macro_pub]
+#[cfg(feature = "std")]
+macro_rules! log {
+    ($e:expr) => {
+        $e
+    };
+}
+
+#[macro_pub::macro_pub]
+#[cfg(not(feature = "std"))]
+macro_rules! log {
+    ($e:expr) => {
+        ()
+    };
+}
+
+pub fn logged() {
+    crate::log!(())
+}
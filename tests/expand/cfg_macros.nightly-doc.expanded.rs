@@ -0,0 +1,14 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[doc = " Enabled or not."]
+#[rustc_macro_transparency = "semiopaque"]
+pub(crate) macro which { () => { "enabled" }, }
+#[doc = " Enabled or not."]
+#[doc(hidden)]
+macro_rules! which { () => { "enabled" }; }
+
+pub fn enabled() -> &'static str { "enabled" }
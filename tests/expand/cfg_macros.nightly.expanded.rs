@@ -0,0 +1,11 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[doc = " Enabled or not."]
+macro_rules! which { () => { "enabled" }; }
+#[allow(unused_imports)]
+pub(crate) use which as which;
+
+pub fn enabled() -> &'static str { "enabled" }
@@ -0,0 +1,18 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+macro_pub::cfg_macros! {
+    /// Enabled or not.
+    #[macro_pub(crate)]
+    macro_rules! which {
+        #[cfg(all())] {
+            () => { "enabled" };
+        }
+        #[cfg(any())] {
+            () => { "disabled" };
+        }
+    }
+}
+
+pub fn enabled() -> &'static str {
+    crate::which!()
+}
@@ -0,0 +1,14 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+mod inner {
+    #[rustc_macro_transparency = "semiopaque"]
+    pub(crate) macro restricted { () => {}, ($e:expr) => { let _ = $e; }, }
+    #[doc(hidden)]
+    macro_rules! restricted { () => {}; ($e:expr) => { let _ = $e; }; }
+}
+
+pub fn uses() { ; let _ = 1; }
@@ -0,0 +1,16 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+mod inner {
+    #[macro_pub::macro_pub(crate)]
+    macro_rules! restricted {
+        () => {};
+        ($e:expr) => {
+            let _ = $e;
+        };
+    }
+}
+
+pub fn uses() {
+    inner::restricted!();
+    inner::restricted!(1);
+}
@@ -0,0 +1,12 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+mod inner {
+    macro_rules! restricted { () => {}; ($e:expr) => { let _ = $e; }; }
+    #[allow(unused_imports)]
+    pub(crate) use restricted as restricted;
+}
+
+pub fn uses() { ; let _ = 1; }
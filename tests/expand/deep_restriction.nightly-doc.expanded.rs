@@ -0,0 +1,20 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+mod a {
+    mod b {
+        pub(super) mod c {
+            #[rustc_macro_transparency = "semiopaque"]
+            pub(in super::super) macro deep { () => { 1 }, }
+            #[doc(hidden)]
+            macro_rules! deep { () => { 1 }; }
+        }
+    }
+
+    pub fn uses() -> i32 { 1 }
+}
+
+pub fn uses() -> i32 { a::uses() }
@@ -0,0 +1,22 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+mod a {
+    mod b {
+        pub(super) mod c {
+            #[macro_pub::macro_pub(in super::super)]
+            macro_rules! deep {
+                () => {
+                    1
+                };
+            }
+        }
+    }
+
+    pub fn uses() -> i32 {
+        b::c::deep!()
+    }
+}
+
+pub fn uses() -> i32 {
+    a::uses()
+}
@@ -0,0 +1,18 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+mod a {
+    mod b {
+        pub(super) mod c {
+            macro_rules! deep { () => { 1 }; }
+            #[allow(unused_imports)]
+            pub(in super::super) use deep as deep;
+        }
+    }
+
+    pub fn uses() -> i32 { 1 }
+}
+
+pub fn uses() -> i32 { a::uses() }
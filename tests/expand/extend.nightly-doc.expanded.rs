@@ -0,0 +1,68 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+mod numbers {
+    #[doc(hidden)]
+    macro_rules! __macro_pub_extend_232509121603894475095954705331758879771_describe {
+        ($__macro_pub_dollar : tt [($ ($next : tt) *) $ ($rest : tt) *]
+        ($ ($mount : tt) *) $ ($arms : tt) *) =>
+        {
+            $ ($next) *!
+            {
+                $__macro_pub_dollar [$ ($rest) *] ($ ($mount) *) $ ($arms) *
+                ($__macro_pub_dollar n : literal) => { "a number" };
+            }
+        }; ($__macro_pub_dollar : tt [] ($ ($mount : tt) *) $ ($arms : tt) *)
+        =>
+        {
+            $ ($mount) *
+            {
+                $ ($arms) * ($__macro_pub_dollar n : literal) =>
+                { "a number" };
+            }
+        };
+    }
+    #[doc(hidden)]
+    pub(crate) use __macro_pub_extend_232509121603894475095954705331758879771_describe as describe;
+}
+
+mod lists {
+    #[doc(hidden)]
+    macro_rules! __macro_pub_extend_335310214142152822518532853705908110897_describe {
+        ($__macro_pub_dollar : tt [($ ($next : tt) *) $ ($rest : tt) *]
+        ($ ($mount : tt) *) $ ($arms : tt) *) =>
+        {
+            $ ($next) *!
+            {
+                $__macro_pub_dollar [$ ($rest) *] ($ ($mount) *) $ ($arms) *
+                ($__macro_pub_dollar($__macro_pub_dollar e : expr),+
+                $__macro_pub_dollar(,) ?) => { "a list" };
+            }
+        }; ($__macro_pub_dollar : tt [] ($ ($mount : tt) *) $ ($arms : tt) *)
+        =>
+        {
+            $ ($mount) *
+            {
+                $ ($arms) *
+                ($__macro_pub_dollar($__macro_pub_dollar e : expr),+
+                $__macro_pub_dollar(,) ?) => { "a list" };
+            }
+        };
+    }
+    #[doc(hidden)]
+    pub(crate) use __macro_pub_extend_335310214142152822518532853705908110897_describe as describe;
+}
+
+#[doc = " Describes its input."]
+#[rustc_macro_transparency = "semiopaque"]
+pub macro describe {
+    () => { "nothing" }, ($n : literal) => { "a number" },
+    ($ ($e : expr), + $ (,) ?) => { "a list" },
+}
+
+pub fn descriptions() -> [&'static str; 3] {
+    ["nothing", "a number", "a list"]
+}
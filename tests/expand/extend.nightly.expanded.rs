@@ -0,0 +1,70 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+mod numbers {
+    #[doc(hidden)]
+    macro_rules! __macro_pub_extend_232509121603894475095954705331758879771_describe {
+        ($__macro_pub_dollar : tt [($ ($next : tt) *) $ ($rest : tt) *]
+        ($ ($mount : tt) *) $ ($arms : tt) *) =>
+        {
+            $ ($next) *!
+            {
+                $__macro_pub_dollar [$ ($rest) *] ($ ($mount) *) $ ($arms) *
+                ($__macro_pub_dollar n : literal) => { "a number" };
+            }
+        }; ($__macro_pub_dollar : tt [] ($ ($mount : tt) *) $ ($arms : tt) *)
+        =>
+        {
+            $ ($mount) *
+            {
+                $ ($arms) * ($__macro_pub_dollar n : literal) =>
+                { "a number" };
+            }
+        };
+    }
+    #[doc(hidden)]
+    pub(crate) use __macro_pub_extend_232509121603894475095954705331758879771_describe as describe;
+}
+
+mod lists {
+    #[doc(hidden)]
+    macro_rules! __macro_pub_extend_335310214142152822518532853705908110897_describe {
+        ($__macro_pub_dollar : tt [($ ($next : tt) *) $ ($rest : tt) *]
+        ($ ($mount : tt) *) $ ($arms : tt) *) =>
+        {
+            $ ($next) *!
+            {
+                $__macro_pub_dollar [$ ($rest) *] ($ ($mount) *) $ ($arms) *
+                ($__macro_pub_dollar($__macro_pub_dollar e : expr),+
+                $__macro_pub_dollar(,) ?) => { "a list" };
+            }
+        }; ($__macro_pub_dollar : tt [] ($ ($mount : tt) *) $ ($arms : tt) *)
+        =>
+        {
+            $ ($mount) *
+            {
+                $ ($arms) *
+                ($__macro_pub_dollar($__macro_pub_dollar e : expr),+
+                $__macro_pub_dollar(,) ?) => { "a list" };
+            }
+        };
+    }
+    #[doc(hidden)]
+    pub(crate) use __macro_pub_extend_335310214142152822518532853705908110897_describe as describe;
+}
+
+#[doc = " Describes its input."]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! macro_impl_141706176046234805958234076131594069402_describe {
+    () => { "nothing" }; ($n : literal) => { "a number" };
+    ($ ($e : expr), + $ (,) ?) => { "a list" };
+}
+#[doc(inline)]
+pub use macro_impl_141706176046234805958234076131594069402_describe as describe;
+
+pub fn descriptions() -> [&'static str; 3] {
+    ["nothing", "a number", "a list"]
+}
@@ -0,0 +1,31 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+mod numbers {
+    #[macro_pub::macro_pub(extend)]
+    macro_rules! describe {
+        ($n:literal) => {
+            "a number"
+        };
+    }
+}
+
+mod lists {
+    #[macro_pub::macro_pub(extend)]
+    macro_rules! describe {
+        ($($e:expr),+ $(,)?) => {
+            "a list"
+        };
+    }
+}
+
+/// Describes its input.
+#[macro_pub::macro_pub(extend_from(crate::numbers::describe, crate::lists::describe))]
+macro_rules! describe {
+    () => {
+        "nothing"
+    };
+}
+
+pub fn descriptions() -> [&'static str; 3] {
+    [describe!(), describe!(1), describe!(x, y)]
+}
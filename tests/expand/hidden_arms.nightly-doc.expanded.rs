@@ -0,0 +1,36 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[doc = " Sums its arguments."]
+#[doc = ""]
+#[doc = "# Signatures"]
+#[doc = ""]
+#[doc = "```text"]
+#[doc = "sum!($($e:expr),* $(,)?)"]
+#[doc = "```"]
+pub macro sum {
+    ($ ($e : expr),* $ (,) ?) => { __macro_pub_impl_sum! { $ ($e),* } },
+}
+#[doc = " Sums its arguments."]
+#[doc = ""]
+#[doc = "# Signatures"]
+#[doc = ""]
+#[doc = "```text"]
+#[doc = "sum!($($e:expr),* $(,)?)"]
+#[doc = "```"]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! macro_impl_128860978531475008371802409575752971746_sum {
+    ($ ($e : expr),* $ (,) ?) =>
+    { $crate :: __macro_pub_impl_sum! (@ add 0; $ ($e),*) };
+    (@ add $acc : expr;) => { $acc };
+    (@ add $acc : expr; $e : expr $ (, $rest : expr) *) =>
+    { $crate :: __macro_pub_impl_sum! (@ add $acc + $e; $ ($rest),*) };
+}
+#[doc(hidden)]
+pub use macro_impl_128860978531475008371802409575752971746_sum as __macro_pub_impl_sum;
+
+pub fn six() -> i32 { 0 + 1 + 2 + 3 }
@@ -0,0 +1,27 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[doc = " Sums its arguments."]
+#[doc = ""]
+#[doc = "# Signatures"]
+#[doc = ""]
+#[doc = "```text"]
+#[doc = "sum!($($e:expr),* $(,)?)"]
+#[doc = "```"]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! macro_impl_128860978531475008371802409575752971746_sum {
+    ($ ($e : expr),* $ (,) ?) =>
+    { $crate :: __macro_pub_impl_sum! (@ add 0; $ ($e),*) };
+    (@ add $acc : expr;) => { $acc };
+    (@ add $acc : expr; $e : expr $ (, $rest : expr) *) =>
+    { $crate :: __macro_pub_impl_sum! (@ add $acc + $e; $ ($rest),*) };
+}
+#[doc(hidden)]
+pub use macro_impl_128860978531475008371802409575752971746_sum as __macro_pub_impl_sum;
+#[doc(inline)]
+pub use macro_impl_128860978531475008371802409575752971746_sum as sum;
+
+pub fn six() -> i32 { 0 + 1 + 2 + 3 }
@@ -0,0 +1,15 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+/// Sums its arguments.
+#[macro_pub::macro_pub(signatures)]
+macro_rules! sum {
+    ($($e:expr),* $(,)?) => { $crate::sum!(@add 0; $($e),*) };
+    #[doc(hidden)]
+    (@add $acc:expr;) => { $acc };
+    #[doc(hidden)]
+    (@add $acc:expr; $e:expr $(, $rest:expr)*) => { $crate::sum!(@add $acc + $e; $($rest),*) };
+}
+
+pub fn six() -> i32 {
+    sum!(1, 2, 3)
+}
@@ -0,0 +1,24 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+mod imp {
+    #[rustc_macro_transparency = "semiopaque"]
+    pub macro first { () => {}, }
+
+    #[rustc_macro_transparency = "semiopaque"]
+    pub macro second { () => {}, }
+}
+
+#[doc = " The macros."]
+pub mod macros {
+    #[doc(inline)]
+    pub use crate::imp::first;
+    #[doc = " A group."]
+    pub mod group {
+        #[doc(inline)]
+        pub use crate::imp::second;
+    }
+}
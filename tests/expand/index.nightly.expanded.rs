@@ -0,0 +1,33 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+mod imp {
+    #[macro_export]
+    #[doc(hidden)]
+    macro_rules! macro_impl_229043412469057631065142818823766277294_first {
+        () => {};
+    }
+    #[doc(inline)]
+    pub use macro_impl_229043412469057631065142818823766277294_first as first;
+
+    #[macro_export]
+    #[doc(hidden)]
+    macro_rules! macro_impl_257071391565646834818554309423317588929_second {
+        () => {};
+    }
+    #[doc(inline)]
+    pub use macro_impl_257071391565646834818554309423317588929_second as second;
+}
+
+#[doc = " The macros."]
+pub mod macros {
+    #[doc(inline)]
+    pub use crate::imp::first;
+    #[doc = " A group."]
+    pub mod group {
+        #[doc(inline)]
+        pub use crate::imp::second;
+    }
+}
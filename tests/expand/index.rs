@@ -0,0 +1,24 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+mod imp {
+    #[macro_pub::macro_pub]
+    macro_rules! first {
+        () => {};
+    }
+
+    #[macro_pub::macro_pub]
+    macro_rules! second {
+        () => {};
+    }
+}
+
+macro_pub::index! {
+    /// The macros.
+    pub mod macros {
+        crate::imp::first;
+        /// A group.
+        mod group {
+            crate::imp::second;
+        }
+    }
+}
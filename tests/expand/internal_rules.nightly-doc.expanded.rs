@@ -0,0 +1,28 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+pub mod count {
+    #[doc(hidden)]
+    #[rustc_macro_transparency = "semiopaque"]
+    pub macro __macro_pub_internal_count {
+        (@ sum $acc : expr;) => { $acc },
+        (@ sum $acc : expr; $e : expr $ (, $rest : expr) *) =>
+        {
+            $crate :: count :: __macro_pub_internal_count!
+            (@ sum $acc + 1; $ ($rest),*)
+        },
+    }
+    #[doc = " Counts its arguments."]
+    #[rustc_macro_transparency = "semiopaque"]
+    pub macro count {
+        ($ ($e : expr),* $ (,) ?) =>
+        {
+            $crate :: count :: __macro_pub_internal_count! (@ sum 0; $ ($e),*)
+        },
+    }
+}
+
+pub fn three() -> usize { 0 + 1 + 1 + 1 }
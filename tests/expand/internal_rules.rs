@@ -0,0 +1,17 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+pub mod count {
+    /// Counts its arguments.
+    #[macro_pub::macro_pub(internal_rules)]
+    macro_rules! count {
+        ($($e:expr),* $(,)?) => { $crate::count::count!(@sum 0; $($e),*) };
+        (@sum $acc:expr;) => { $acc };
+        (@sum $acc:expr; $e:expr $(, $rest:expr)*) => {
+            $crate::count::count!(@sum $acc + 1; $($rest),*)
+        };
+    }
+}
+
+pub fn three() -> usize {
+    count::count!(1, 2, 3)
+}
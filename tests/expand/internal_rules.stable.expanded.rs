@@ -0,0 +1,33 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+pub mod count {
+    #[doc(hidden)]
+    #[macro_export]
+    #[doc(hidden)]
+    macro_rules! macro_impl_48074665026995750678490891182992294609___macro_pub_internal_count {
+        (@ sum $acc : expr;) => { $acc };
+        (@ sum $acc : expr; $e : expr $ (, $rest : expr) *) =>
+        {
+            $crate :: count :: __macro_pub_internal_count!
+            (@ sum $acc + 1; $ ($rest),*)
+        };
+    }
+    #[doc(hidden)]
+    pub use macro_impl_48074665026995750678490891182992294609___macro_pub_internal_count as __macro_pub_internal_count;
+    #[doc = " Counts its arguments."]
+    #[macro_export]
+    #[doc(hidden)]
+    macro_rules! macro_impl_119596651544666108598017460245034037152_count {
+        ($ ($e : expr),* $ (,) ?) =>
+        {
+            $crate :: count :: __macro_pub_internal_count! (@ sum 0; $ ($e),*)
+        };
+    }
+    #[doc(inline)]
+    pub use macro_impl_119596651544666108598017460245034037152_count as count;
+}
+
+pub fn three() -> usize { 0 + 1 + 1 + 1 }
@@ -0,0 +1,40 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+mod __macro_pub_shadowable_add_one {
+    #[doc = "Adds one to a number."]
+    #[doc = "Use it as `add_one!(2)`."]
+    #[doc = ""]
+    #[doc = "# Signatures"]
+    #[doc = ""]
+    #[doc = "```text"]
+    #[doc = "add_one!($n:expr)"]
+    #[doc = "```"]
+    #[rustc_macro_transparency = "semiopaque"]
+    pub macro add_one { ($n:expr) => { $n + 1 }, }
+}
+#[doc(inline)]
+#[allow(unused_imports)]
+pub use self::__macro_pub_shadowable_add_one::*;
+
+#[doc = "Subtracts one."]
+#[doc = ""]
+#[doc = "# Signatures"]
+#[doc = ""]
+#[doc = "```text"]
+#[doc = "sub_one!($n:expr)"]
+#[doc = "```"]
+#[rustc_macro_transparency = "semiopaque"]
+pub(crate) macro sub_one { ($n:expr) => { $n - 1 }, }
+#[doc = "Subtracts one."]
+#[doc = ""]
+#[doc = "# Signatures"]
+#[doc = ""]
+#[doc = "```text"]
+#[doc = "sub_one!($n:expr)"]
+#[doc = "```"]
+#[doc(hidden)]
+macro_rules! sub_one { ($n:expr) => { $n - 1 }; }
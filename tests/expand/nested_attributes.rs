@@ -0,0 +1,18 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+#[macro_pub::macro_pub(signatures, shadowable)]
+#[doc = concat!("Adds ", stringify!(one), " to ", concat!("a ", stringify!(number)), ".")]
+#[cfg_attr(all(), doc = concat!("Use it as `", stringify!(add_one!(2)), "`."))]
+macro_rules! add_one {
+    ($n:expr) => {
+        $n + 1
+    };
+}
+
+#[macro_pub::macro_pub(crate, signatures)]
+#[doc = concat!("Subtracts ", stringify!(one), ".")]
+macro_rules! sub_one {
+    ($n:expr) => {
+        $n - 1
+    };
+}
@@ -0,0 +1,36 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[doc = "Adds one to a number."]
+#[doc = "Use it as `add_one!(2)`."]
+#[doc = ""]
+#[doc = "# Signatures"]
+#[doc = ""]
+#[doc = "```text"]
+#[doc = "add_one!($n:expr)"]
+#[doc = "```"]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! macro_impl_284392710359932675340734596221156128520_add_one {
+    ($n:expr) => { $n + 1 };
+}
+mod __macro_pub_shadowable_add_one {
+    #[doc(inline)]
+    pub use macro_impl_284392710359932675340734596221156128520_add_one as add_one;
+}
+#[doc(inline)]
+#[allow(unused_imports)]
+pub use self::__macro_pub_shadowable_add_one::*;
+
+#[doc = "Subtracts one."]
+#[doc = ""]
+#[doc = "# Signatures"]
+#[doc = ""]
+#[doc = "```text"]
+#[doc = "sub_one!($n:expr)"]
+#[doc = "```"]
+macro_rules! sub_one { ($n:expr) => { $n - 1 }; }
+#[allow(unused_imports)]
+pub(crate) use sub_one as sub_one;
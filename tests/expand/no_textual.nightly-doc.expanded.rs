@@ -0,0 +1,14 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[macro_use]
+mod macros {
+    #[doc = " Doubles its argument."]
+    #[rustc_macro_transparency = "semiopaque"]
+    pub(crate) macro double { ($e:expr) => { $e * 2 }, }
+}
+
+pub fn four() -> i32 { 2 * 2 }
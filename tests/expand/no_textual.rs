@@ -0,0 +1,16 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+#[macro_use]
+mod macros {
+    /// Doubles its argument.
+    #[macro_pub::macro_pub(crate, no_textual)]
+    macro_rules! double {
+        ($e:expr) => {
+            $e * 2
+        };
+    }
+}
+
+pub fn four() -> i32 {
+    crate::macros::double!(2)
+}
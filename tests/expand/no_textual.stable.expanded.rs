@@ -0,0 +1,16 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[macro_use]
+mod macros {
+    #[doc = " Doubles its argument."]
+    macro_rules! macro_impl_90439528768565662026251955259714098627_double {
+        ($e:expr) => { $e * 2 };
+    }
+    #[allow(unused_imports)]
+    pub(crate) use macro_impl_90439528768565662026251955259714098627_double as double;
+}
+
+pub fn four() -> i32 { 2 * 2 }
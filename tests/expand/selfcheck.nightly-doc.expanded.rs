@@ -0,0 +1,108 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[doc = " Sums its arguments."]
+#[rustc_macro_transparency = "semiopaque"]
+pub macro sum {
+    () => { 0 }, ($first:expr $(, $rest:expr)* $(,)?) =>
+    { $first $(+ $rest)* }, (@ [$($items:ident),+] $ty:ty) => { 0 },
+}
+const _: () =
+    {
+        macro_rules! __macro_pub_selfcheck_rules {
+            () =>
+            {
+                macro_rules! __macro_pub_selfcheck_arm
+                {
+                    (0) => {}; ($other:tt) =>
+                    {
+                        compile_error!
+                        {
+                            "`#[macro_pub(selfcheck)]` found an invocation that the documented `pub macro` matches with a different arm than the `macro_rules!` macro"
+                        }
+                    };
+                }
+            }; ($first:expr $(, $rest:expr)* $(,)?) =>
+            {
+                macro_rules! __macro_pub_selfcheck_arm
+                {
+                    (1) => {}; ($other:tt) =>
+                    {
+                        compile_error!
+                        {
+                            "`#[macro_pub(selfcheck)]` found an invocation that the documented `pub macro` matches with a different arm than the `macro_rules!` macro"
+                        }
+                    };
+                }
+            }; (@ [$($items:ident),+] $ty:ty) =>
+            {
+                macro_rules! __macro_pub_selfcheck_arm
+                {
+                    (2) => {}; ($other:tt) =>
+                    {
+                        compile_error!
+                        {
+                            "`#[macro_pub(selfcheck)]` found an invocation that the documented `pub macro` matches with a different arm than the `macro_rules!` macro"
+                        }
+                    };
+                }
+            }; ($($tokens:tt)*) =>
+            {
+                macro_rules! __macro_pub_selfcheck_arm
+                {
+                    (none) => {}; ($other:tt) =>
+                    {
+                        compile_error!
+                        {
+                            "`#[macro_pub(selfcheck)]` found an invocation that the documented `pub macro` matches with a different arm than the `macro_rules!` macro"
+                        }
+                    };
+                }
+            };
+        }
+        #[rustc_macro_transparency = "semiopaque"]
+        macro __macro_pub_selfcheck_decl {
+            () => { __macro_pub_selfcheck_arm! { 0 } },
+            ($first:expr $(, $rest:expr)* $(,)?) =>
+            { __macro_pub_selfcheck_arm! { 1 } },
+            (@ [$($items:ident),+] $ty:ty) =>
+            { __macro_pub_selfcheck_arm! { 2 } }, ($($tokens:tt)*) =>
+            { __macro_pub_selfcheck_arm! { none } },
+        }
+        {
+            macro_rules! __macro_pub_selfcheck_arm {
+                (0) => {}; ($other : tt) =>
+                {
+                    compile_error!
+                    {
+                        "`#[macro_pub(selfcheck)]` found an invocation that the documented `pub macro` matches with a different arm than the `macro_rules!` macro"
+                    }
+                };
+            }
+        }
+        {
+            macro_rules! __macro_pub_selfcheck_arm {
+                (1) => {}; ($other : tt) =>
+                {
+                    compile_error!
+                    {
+                        "`#[macro_pub(selfcheck)]` found an invocation that the documented `pub macro` matches with a different arm than the `macro_rules!` macro"
+                    }
+                };
+            }
+        }
+        {
+            macro_rules! __macro_pub_selfcheck_arm {
+                (2) => {}; ($other : tt) =>
+                {
+                    compile_error!
+                    {
+                        "`#[macro_pub(selfcheck)]` found an invocation that the documented `pub macro` matches with a different arm than the `macro_rules!` macro"
+                    }
+                };
+            }
+        }
+    };
@@ -0,0 +1,9 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+/// Sums its arguments.
+#[macro_pub::macro_pub(selfcheck)]
+macro_rules! sum {
+    () => { 0 };
+    ($first:expr $(, $rest:expr)* $(,)?) => { $first $(+ $rest)* };
+    (@ [$($items:ident),+] $ty:ty) => { 0 };
+}
@@ -0,0 +1,14 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[doc = " Sums its arguments."]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! macro_impl_236716659454825872498213907430327393916_sum {
+    () => { 0 }; ($first:expr $(, $rest:expr)* $(,)?) =>
+    { $first $(+ $rest)* }; (@ [$($items:ident),+] $ty:ty) => { 0 };
+}
+#[doc(inline)]
+pub use macro_impl_236716659454825872498213907430327393916_sum as sum;
@@ -0,0 +1,26 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+pub mod macros {
+    mod __macro_pub_shadowable_double {
+        #[doc = " Doubles its argument."]
+        #[rustc_macro_transparency = "semiopaque"]
+        pub macro double { ($e:expr) => { $e * 2 }, }
+    }
+    #[doc(inline)]
+    #[allow(unused_imports)]
+    pub use self::__macro_pub_shadowable_double::*;
+
+    mod fake {
+        macro_rules! fake_double { ($e:expr) => { 4 }; }
+        pub(crate) use fake_double as double;
+    }
+
+    // Replaces the macro within this module, without clashing with it.
+    use self::fake::double;
+
+    pub fn four() -> i32 { 4 }
+}
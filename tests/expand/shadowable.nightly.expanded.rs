@@ -0,0 +1,30 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+pub mod macros {
+    #[doc = " Doubles its argument."]
+    #[macro_export]
+    #[doc(hidden)]
+    macro_rules! macro_impl_90439528768565662026251955259714098627_double {
+        ($e:expr) => { $e * 2 };
+    }
+    mod __macro_pub_shadowable_double {
+        #[doc(inline)]
+        pub use macro_impl_90439528768565662026251955259714098627_double as double;
+    }
+    #[doc(inline)]
+    #[allow(unused_imports)]
+    pub use self::__macro_pub_shadowable_double::*;
+
+    mod fake {
+        macro_rules! fake_double { ($e:expr) => { 4 }; }
+        pub(crate) use fake_double as double;
+    }
+
+    // Replaces the macro within this module, without clashing with it.
+    use self::fake::double;
+
+    pub fn four() -> i32 { 4 }
+}
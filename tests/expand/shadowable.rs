@@ -0,0 +1,27 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+pub mod macros {
+    /// Doubles its argument.
+    #[macro_pub::macro_pub(shadowable)]
+    macro_rules! double {
+        ($e:expr) => {
+            $e * 2
+        };
+    }
+
+    mod fake {
+        macro_rules! fake_double {
+            ($e:expr) => {
+                4
+            };
+        }
+        pub(crate) use fake_double as double;
+    }
+
+    // Replaces the macro within this module, without clashing with it.
+    use self::fake::double;
+
+    pub fn four() -> i32 {
+        double!(2)
+    }
+}
@@ -0,0 +1,20 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[doc = " Adds things up."]
+#[doc = ""]
+#[doc = "# Signatures"]
+#[doc = ""]
+#[doc = "```text"]
+#[doc = "sum!()"]
+#[doc = "sum!($first:expr $(, $rest:expr)* $(,)?)"]
+#[doc = "sum![@from $iter:expr]"]
+#[doc = "```"]
+#[rustc_macro_transparency = "semiopaque"]
+pub macro sum {
+    () => { 0 }, ($first:expr $(, $rest:expr)* $(,)?) =>
+    { $first $(+ $rest)* }, [@from $iter:expr] => { $iter.into_iter().sum() },
+}
@@ -0,0 +1,22 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[doc = " Adds things up."]
+#[doc = ""]
+#[doc = "# Signatures"]
+#[doc = ""]
+#[doc = "```text"]
+#[doc = "sum!()"]
+#[doc = "sum!($first:expr $(, $rest:expr)* $(,)?)"]
+#[doc = "sum![@from $iter:expr]"]
+#[doc = "```"]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! macro_impl_118150923636207228192796491685704022533_sum {
+    () => { 0 }; ($first:expr $(, $rest:expr)* $(,)?) =>
+    { $first $(+ $rest)* }; [@from $iter:expr] => { $iter.into_iter().sum() };
+}
+#[doc(inline)]
+pub use macro_impl_118150923636207228192796491685704022533_sum as sum;
@@ -0,0 +1,15 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+/// Adds things up.
+#[macro_pub::macro_pub(signatures)]
+macro_rules! sum {
+    () => {
+        0
+    };
+    ($first:expr $(, $rest:expr)* $(,)?) => {
+        $first $(+ $rest)*
+    };
+    [@from $iter:expr] => {
+        $iter.into_iter().sum()
+    };
+}
@@ -0,0 +1,22 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[macro_use]
+mod macros {
+    #[doc = " Doubles its argument."]
+    #[rustc_macro_transparency = "semiopaque"]
+    pub macro double { ($e:expr) => { $e * 2 }, }
+    #[allow(unused_macros)]
+    macro_rules! double { ($e:expr) => { $e * 2 }; }
+
+    pub fn two() -> i32 { 1 * 2 }
+}
+
+pub mod uses {
+    pub fn four() -> i32 { 2 * 2 }
+}
+
+pub use macros::two;
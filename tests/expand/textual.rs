@@ -0,0 +1,24 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+#[macro_use]
+mod macros {
+    /// Doubles its argument.
+    #[macro_pub::macro_pub(textual)]
+    macro_rules! double {
+        ($e:expr) => {
+            $e * 2
+        };
+    }
+
+    pub fn two() -> i32 {
+        double!(1)
+    }
+}
+
+pub mod uses {
+    pub fn four() -> i32 {
+        double!(2)
+    }
+}
+
+pub use macros::two;
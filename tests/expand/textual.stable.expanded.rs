@@ -0,0 +1,26 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+#[macro_use]
+mod macros {
+    #[doc = " Doubles its argument."]
+    #[macro_export]
+    #[doc(hidden)]
+    macro_rules! macro_impl_90439528768565662026251955259714098627_double {
+        ($e:expr) => { $e * 2 };
+    }
+    #[doc(inline)]
+    pub use macro_impl_90439528768565662026251955259714098627_double as double;
+    #[allow(unused_macros)]
+    macro_rules! double { ($e:expr) => { $e * 2 }; }
+
+    pub fn two() -> i32 { 1 * 2 }
+}
+
+pub mod uses {
+    pub fn four() -> i32 { 2 * 2 }
+}
+
+pub use macros::two;
@@ -0,0 +1,17 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+pub mod numbers {
+    #[rustc_macro_transparency = "semiopaque"]
+    pub macro one { () => { 1 }, }
+}
+
+#[doc = " Imports the numbers."]
+#[rustc_macro_transparency = "semiopaque"]
+pub macro prelude {
+    () => { use $crate :: numbers :: one; }, ($vis:vis) =>
+    { $vis use $crate :: numbers :: one; },
+}
@@ -0,0 +1,15 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+pub mod numbers {
+    #[macro_pub::macro_pub]
+    macro_rules! one {
+        () => { 1 };
+    }
+}
+
+macro_pub::use_macros! {
+    /// Imports the numbers.
+    macro_rules! prelude {
+        crate::numbers::one;
+    }
+}
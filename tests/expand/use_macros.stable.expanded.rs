@@ -0,0 +1,24 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+pub mod numbers {
+    #[macro_export]
+    #[doc(hidden)]
+    macro_rules! macro_impl_143115220837112406033769703548551519122_one {
+        () => { 1 };
+    }
+    #[doc(inline)]
+    pub use macro_impl_143115220837112406033769703548551519122_one as one;
+}
+
+#[doc = " Imports the numbers."]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! macro_impl_239664898954295452230011465838665567859_prelude {
+    () => { use $crate :: numbers :: one; }; ($vis:vis) =>
+    { $vis use $crate :: numbers :: one; };
+}
+#[doc(inline)]
+pub use macro_impl_239664898954295452230011465838665567859_prelude as prelude;
@@ -0,0 +1,13 @@
+#![feature(prelude_import)]
+#![feature(decl_macro, rustc_attrs)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+pub mod inner {
+    #[doc = " Documented, world-public macro."]
+    #[rustc_macro_transparency = "semiopaque"]
+    pub macro exported {
+        () => {}, ($($t:tt)*) => { $crate::inner::exported!(); },
+    }
+}
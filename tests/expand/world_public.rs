@@ -0,0 +1,12 @@
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+pub mod inner {
+    /// Documented, world-public macro.
+    #[macro_pub::macro_pub]
+    macro_rules! exported {
+        () => {};
+        ($($t:tt)*) => {
+            $crate::inner::exported!();
+        };
+    }
+}
@@ -0,0 +1,15 @@
+#![feature(prelude_import)]
+extern crate std;
+#[prelude_import]
+use std::prelude::rust_2021::*;
+
+pub mod inner {
+    #[doc = " Documented, world-public macro."]
+    #[macro_export]
+    #[doc(hidden)]
+    macro_rules! macro_impl_319402700920542097370415403067610577323_exported {
+        () => {}; ($($t:tt)*) => { $crate::inner::exported!(); };
+    }
+    #[doc(inline)]
+    pub use macro_impl_319402700920542097370415403067610577323_exported as exported;
+}
@@ -0,0 +1,264 @@
+//! Randomized testing of `#[macro_pub]`'s input handling.
+//!
+//! rustc only hands attribute macros syntactically valid items, so rather than
+//! arbitrary token streams this generates perturbations of valid macros:
+//! arbitrary attribute arguments, assorted outer attributes, other kinds of
+//! items, and other delimiters. All cases are compiled together in one crate,
+//! each in its own module, and every case must either compile or fail only
+//! with one of `#[macro_pub]`'s own errors; the macro must never panic.
+//!
+//! The run is deterministic; set `MACRO_PUB_FUZZ_SEED` and
+//! `MACRO_PUB_FUZZ_CASES` to explore further.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// xorshift64*, to avoid depending on a full randomness crate.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a>(&mut self, options: &[&'a str]) -> &'a str {
+        options[self.below(options.len())]
+    }
+}
+
+const TOKENS: &[&str] = &[
+    "crate",
+    "self",
+    "super",
+    "in",
+    "pub",
+    "foo",
+    "_",
+    "::",
+    ":",
+    ",",
+    "=",
+    "!",
+    "#",
+    "$",
+    "'a",
+    "0",
+    "\"s\"",
+    "true",
+    "()",
+    "[]",
+    "{}",
+    "(crate)",
+    "(in self)",
+    "signatures",
+    "inline",
+    "no_inline",
+    "hidden",
+    "export_as",
+    "deprecate_as",
+    "friends",
+    "since",
+    "unstable",
+    "feature",
+    "also_export_legacy_name",
+    "strategy",
+    "metadata",
+    "debug",
+    "no_alias",
+    "unsafe_macro",
+];
+
+const VISIBILITIES: &[&str] = &[
+    "",
+    "crate",
+    "self",
+    "super",
+    "in crate",
+    "in self",
+    "in super",
+    "in self::super",
+    "signatures",
+    "crate, signatures,",
+    "crate, hidden",
+    "crate, export_as = other",
+    "crate, deprecate_as = \"old\"",
+    "crate, friends(\"other\")",
+    "since = \"1.0.0\", unstable",
+    "crate, feature = \"extra\"",
+    "strategy = \"use_alias\"",
+    "strategy = \"legacy_export\"",
+    "crate, metadata",
+    "no_alias",
+    "crate, unsafe_macro",
+];
+
+const ATTRIBUTES: &[&str] = &[
+    "/// Doc comment.",
+    "#[doc = \"doc\"]",
+    "#[doc(alias = \"alias\")]",
+    "#[allow(unused_macros)]",
+    "#[cfg(all())]",
+    "#[cfg_attr(all(), allow(unused))]",
+];
+
+const ARMS: &[&str] = &[
+    "() => {};",
+    "($e:expr) => { $e };",
+    "($($t:tt)*) => { $($t)* };",
+    "(@inner $i:ident) => { let $i = (); };",
+    "[$x:ty] => { ::core::marker::PhantomData::<$x> };",
+    "{ $l:lifetime } => { };",
+];
+
+/// Attributes on arms, which `#[macro_pub]` removes from braced macros.
+const ARM_ATTRIBUTES: &[&str] = &["/// Arm doc comment.", "#[cfg(all())]"];
+
+fn attribute_args(rng: &mut Rng) -> String {
+    if rng.chance(50) {
+        return rng.pick(VISIBILITIES).to_string();
+    }
+    let mut args = String::new();
+    for _ in 0..rng.below(5) {
+        args.push_str(rng.pick(TOKENS));
+        args.push(' ');
+    }
+    args
+}
+
+fn arms(rng: &mut Rng, attributes: bool) -> String {
+    let mut arms = String::new();
+    for _ in 0..=rng.below(3) {
+        if attributes && rng.chance(20) {
+            arms.push_str(rng.pick(ARM_ATTRIBUTES));
+            arms.push('\n');
+        }
+        arms.push_str(rng.pick(ARMS));
+    }
+    arms
+}
+
+fn item(rng: &mut Rng, name: &str) -> String {
+    let arms = arms(rng, false);
+    match rng.below(10) {
+        0 => format!("fn {}() {{}}", name),
+        1 => format!("struct {};", name),
+        2 => format!("mod {} {{}}", name),
+        3 => format!(
+            "thread_local! {{ static {}: () = (); }}",
+            name.to_uppercase()
+        ),
+        4 => format!("macro_rules! {} ({});", name, arms),
+        5 => format!("macro_rules! {} [{}];", name, arms),
+        _ => format!("macro_rules! {} {{ {} }}", name, self::arms(rng, true)),
+    }
+}
+
+/// Generates the fuzzing crate, returning it and the first line of each case.
+fn generate(rng: &mut Rng, cases: usize) -> (String, Vec<usize>) {
+    let mut source = String::from("#![allow(unused)]\n");
+    let mut lines = Vec::new();
+    for case in 0..cases {
+        lines.push(source.lines().count() + 1);
+        writeln!(source, "mod case{} {{", case).unwrap();
+        for _ in 0..rng.below(3) {
+            writeln!(source, "{}", rng.pick(ATTRIBUTES)).unwrap();
+        }
+        writeln!(source, "#[macro_pub::macro_pub({})]", attribute_args(rng)).unwrap();
+        for _ in 0..rng.below(3) {
+            writeln!(source, "{}", rng.pick(ATTRIBUTES)).unwrap();
+        }
+        writeln!(source, "{}", item(rng, &format!("m{}", case))).unwrap();
+        writeln!(source, "}}").unwrap();
+    }
+    (source, lines)
+}
+
+#[test]
+fn fuzz() {
+    let seed = env::var("MACRO_PUB_FUZZ_SEED").map_or(0x6d61_6372_6f5f_7075, |seed| {
+        seed.parse().expect("MACRO_PUB_FUZZ_SEED should be a u64")
+    });
+    let cases = env::var("MACRO_PUB_FUZZ_CASES").map_or(128, |cases| {
+        cases
+            .parse()
+            .expect("MACRO_PUB_FUZZ_CASES should be a usize")
+    });
+    let (source, lines) = generate(&mut Rng(seed | 1), cases);
+
+    let project = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fuzz");
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::write(project.join("src/lib.rs"), &source).unwrap();
+    fs::write(
+        project.join("Cargo.toml"),
+        format!(
+            r#"
+                [package]
+                name = "fuzz"
+                version = "0.0.0"
+                edition = "2021"
+
+                [dependencies]
+                macro_pub = {{ path = {:?} }}
+
+                [workspace]
+            "#,
+            env!("CARGO_MANIFEST_DIR"),
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO"))
+        .current_dir(&project)
+        .args(["check", "--quiet", "--message-format=short"])
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let mut failures = Vec::new();
+    for line in stderr.lines() {
+        let (location, message) = match line.strip_prefix("src/lib.rs:") {
+            Some(rest) => match rest.split_once(": error: ") {
+                Some(split) => split,
+                None => continue,
+            },
+            None => continue,
+        };
+        if message.starts_with("`#[macro_pub]`") {
+            continue;
+        }
+        let line = location
+            .split(':')
+            .next()
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        let case = lines.partition_point(|&start| start <= line) - 1;
+        let end = lines
+            .get(case + 1)
+            .map_or(source.lines().count(), |&end| end - 1);
+        let case_source = source.lines().collect::<Vec<_>>()[lines[case] - 1..end].join("\n");
+        failures.push(format!("{}\n{}", message, case_source));
+    }
+
+    assert!(
+        failures.is_empty(),
+        "seed {}: {} unexpected errors:\n\n{}",
+        seed,
+        failures.len(),
+        failures.join("\n\n"),
+    );
+}
@@ -0,0 +1,131 @@
+//! Scaling checks for `#[macro_pub]` on macro-heavy crates.
+//!
+//! This generates a crate with many `#[macro_pub]` macros, and one macro with
+//! tens of thousands of tokens, and checks it at two sizes, four times apart.
+//! The time per macro and per token should stay about the same, so the larger
+//! crate taking much more than four times as long means something in parsing
+//! or emitting has become quadratic.
+//!
+//! It takes a while, so it only runs on request, with
+//! `cargo test --test stress -- --ignored`. Set `MACRO_PUB_STRESS_MACROS` to
+//! the number of macros in the smaller crate to push further; the large macro
+//! has twice that many arms.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// A crate with `macros` small macros, alternately world-public and
+/// restricted, and one world-public macro with `2 * macros` arms.
+fn generate(macros: usize) -> String {
+    let mut source = String::new();
+    for i in 0..macros {
+        let restriction = if i % 2 == 0 { "" } else { "crate" };
+        writeln!(
+            source,
+            r#"
+                pub mod m{i} {{
+                    /// Adds up its arguments.
+                    #[macro_pub::macro_pub({restriction})]
+                    macro_rules! add{i} {{
+                        () => {{ 0 }};
+                        ($e:expr $(, $rest:expr)* $(,)?) => {{
+                            $e + $crate::m{i}::add{i}!($($rest),*)
+                        }};
+                    }}
+                }}
+            "#,
+            i = i,
+            restriction = restriction,
+        )
+        .unwrap();
+    }
+    source.push_str(
+        "/// Looks up a number by name.\n#[macro_pub::macro_pub]\nmacro_rules! large {\n",
+    );
+    for i in 0..2 * macros {
+        writeln!(
+            source,
+            "    (n{i} $(+ $e:expr)*) => {{ {i} $(+ $e)* }};",
+            i = i,
+        )
+        .unwrap();
+    }
+    source.push_str("}\n");
+    source
+}
+
+/// Checks the crate generated with `macros` macros, returning how long it took.
+fn check(project: &Path, macros: usize) -> Duration {
+    fs::write(project.join("src/lib.rs"), generate(macros)).unwrap();
+    let start = Instant::now();
+    let output = Command::new(env!("CARGO"))
+        .current_dir(project)
+        .args(["check", "--quiet"])
+        .output()
+        .unwrap();
+    let elapsed = start.elapsed();
+    assert!(
+        output.status.success(),
+        "{} macros: {}",
+        macros,
+        String::from_utf8_lossy(&output.stderr),
+    );
+    elapsed
+}
+
+#[test]
+#[ignore = "takes a while; run with `cargo test --test stress -- --ignored`"]
+fn scaling() {
+    let macros = env::var("MACRO_PUB_STRESS_MACROS").map_or(500, |macros| {
+        macros
+            .parse()
+            .expect("MACRO_PUB_STRESS_MACROS should be a usize")
+    });
+
+    let project = Path::new(env!("CARGO_TARGET_TMPDIR")).join("stress");
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::write(
+        project.join("Cargo.toml"),
+        format!(
+            r#"
+                [package]
+                name = "stress"
+                version = "0.0.0"
+                edition = "2021"
+
+                [dependencies]
+                macro_pub = {{ path = {:?} }}
+
+                [workspace]
+            "#,
+            env!("CARGO_MANIFEST_DIR"),
+        ),
+    )
+    .unwrap();
+
+    // The first check also builds `macro_pub`, so it isn't measured.
+    check(&project, 1);
+    let small = check(&project, macros);
+    let large = check(&project, 4 * macros);
+    eprintln!(
+        "{} macros: {:?}, {} macros: {:?}",
+        macros,
+        small,
+        4 * macros,
+        large,
+    );
+
+    // Linear growth takes about four times as long; quadratic, sixteen.
+    assert!(
+        large < small * 8,
+        "checking {} macros took {:?}, but {} took {:?}",
+        4 * macros,
+        large,
+        macros,
+        small,
+    );
+}
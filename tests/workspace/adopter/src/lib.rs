@@ -0,0 +1,49 @@
+//! Trials `#[macro_pub]` behind a feature, as a library adopting it would,
+//! with macros usable the same way whether or not it's enabled.
+
+#![cfg_attr(all(doc, feature = "macro-pub"), feature(decl_macro, rustc_attrs))]
+
+pub mod exported {
+    /// Returns one.
+    #[cfg_attr(feature = "macro-pub", macro_pub::macro_pub)]
+    #[cfg_attr(not(feature = "macro-pub"), macro_export)]
+    macro_rules! one {
+        () => {
+            1
+        };
+    }
+}
+
+// Without the feature, `#[macro_export]` mounts `one` at the crate root, so
+// keep it there with it too.
+#[cfg(feature = "macro-pub")]
+pub use exported::one;
+
+pub mod inner {
+    #[cfg_attr(feature = "macro-pub", macro_pub::macro_pub(crate))]
+    macro_rules! two {
+        () => {
+            2
+        };
+    }
+
+    pub fn call_two() -> i32 {
+        two!()
+    }
+}
+
+#[test]
+fn textual() {
+    assert_eq!(inner::call_two(), 2);
+}
+
+#[test]
+fn exported() {
+    assert_eq!(crate::one!(), 1);
+}
+
+#[cfg(feature = "macro-pub")]
+#[test]
+fn paths() {
+    assert_eq!(crate::exported::one!() + crate::inner::two!(), 3);
+}
@@ -0,0 +1,161 @@
+//! Uses `definer`'s world-public macros from another crate, and those it
+//! shares with this one as a friend.
+
+macro_pub::friend!(definer::shared::shared);
+
+#[cfg(test)]
+#[macro_use]
+extern crate definer;
+
+#[test]
+fn world_public() {
+    assert_eq!(definer::exported::one!(), 1);
+}
+
+#[test]
+fn crate_paths() {
+    assert_eq!(definer::paths::call_helper!(), 42);
+    assert_eq!(definer::paths::call_macro!(), 43);
+}
+
+#[test]
+fn self_calls() {
+    assert_eq!(definer::paths::sum!(twice 2), 4);
+}
+
+#[test]
+fn internal_rules() {
+    assert_eq!(definer::paths::count!(1, 2, 3), 3);
+}
+
+#[test]
+fn extended() {
+    assert_eq!(definer::extended::measure!(), 0);
+    assert_eq!(definer::extended::measure!(offset 1), 43);
+}
+
+#[test]
+fn tracked() {
+    assert_eq!(definer::tracked::eight!(), 8);
+    assert_eq!(definer::uses_tracked(), 17);
+}
+
+#[test]
+fn namespaced() {
+    assert_eq!(definer::net::retry!(), 3);
+    assert_eq!(definer::net::http::attempts!(), 3);
+}
+
+#[test]
+fn raw_names() {
+    assert_eq!(definer::keywords::r#match!(), 4);
+}
+
+#[test]
+fn renamed() {
+    assert_eq!(definer::renamed::fifth!(), 5);
+    assert_eq!(definer::renamed::old_five!(), 5);
+    assert_eq!(definer::renamed::cinco!(), 5);
+}
+
+#[test]
+fn unmangled() {
+    assert_eq!(definer::unmangled::six!(), 6);
+    assert_eq!(definer::unmangled::seven!(), 7);
+}
+
+#[test]
+fn twins() {
+    assert_eq!(definer::twins::a::twin!(), 'a');
+    assert_eq!(definer::twins::b::twin!(), 'b');
+}
+
+#[test]
+fn local_inner_macros() {
+    assert_eq!(definer::local_inner::doubled!(21), 42);
+}
+
+#[test]
+fn recursive() {
+    assert_eq!(definer::recursive::count!(a b c), 3);
+    assert_eq!(definer::recursive::sum!(1, 2, 3), 6);
+}
+
+#[test]
+fn imported() {
+    use definer::exported::one;
+    assert_eq!(one!(), 1);
+}
+
+mod prelude {
+    definer::prelude!(pub);
+}
+
+#[test]
+fn use_macros() {
+    definer::prelude!();
+    assert_eq!(one!() + call_helper!() + call_macro!(), 86);
+    assert_eq!(prelude::one!(), 1);
+}
+
+#[test]
+fn index() {
+    assert_eq!(definer::macros::one!(), 1);
+    assert_eq!(definer::macros::paths::call_helper!(), 42);
+    assert_eq!(definer::macros::paths::call_macro!(), 43);
+}
+
+#[test]
+fn export_as() {
+    assert_eq!(definer::shared::shared_unstable!(), 42);
+}
+
+#[test]
+#[allow(deprecated)]
+fn deprecate_as() {
+    assert_eq!(definer::exported::uno!(), 1);
+}
+
+#[test]
+fn friends() {
+    assert_eq!(shared!(), 42);
+    assert_eq!(crate::shared!(), 42);
+}
+
+#[test]
+fn unstable() {
+    assert_eq!(definer::unstable::two!(), 2);
+}
+
+#[test]
+fn feature() {
+    assert_eq!(definer::gated::three!(), 3);
+}
+
+#[test]
+fn also_export_legacy_name() {
+    assert_eq!(four!(), 4);
+    assert_eq!(definer::four!(), 4);
+    assert_eq!(definer::legacy::four!(), 4);
+    assert_eq!(definer::five!(), 5);
+}
+
+#[test]
+fn included() {
+    assert_eq!(definer::included::seven!(), 7);
+}
+
+#[test]
+fn legacy_export() {
+    assert_eq!(definer::six!(), 6);
+    assert_eq!(six!(), 6);
+}
+
+#[test]
+fn metadata() {
+    use definer::exported::__macro_pub_metadata_one as one;
+    assert_eq!(one::NAME, "one");
+    assert_eq!(one::ARMS, 1);
+    assert_eq!(one::VISIBILITY, "pub");
+    assert!(one::MANGLED.unwrap().starts_with("macro_impl_"));
+}
@@ -0,0 +1,17 @@
+// Included into `definer::included`.
+
+/// Expands to `7`, from an `include!`d file.
+#[macro_pub]
+macro_rules! seven {
+    () => {
+        7
+    };
+}
+
+/// Expands to `'d'`, told apart from `twins::c::twin!` by its file.
+#[macro_pub]
+macro_rules! twin {
+    () => {
+        'd'
+    };
+}
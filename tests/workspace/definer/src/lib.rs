@@ -0,0 +1,485 @@
+//! Defines `#[macro_pub]` macros for use by the other crates in this
+//! workspace.
+
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+#![cfg_attr(doc, feature(doc_cfg))]
+
+use macro_pub::macro_pub;
+
+// Where `#[macro_pub(track_uses)]` macros find it.
+#[doc(hidden)]
+pub use macro_pub::track_use as __macro_pub_track_use;
+
+pub fn helper() -> u32 {
+    42
+}
+
+pub mod exported {
+    use super::*;
+
+    /// Expands to `1`.
+    ///
+    /// ```
+    /// assert_eq!(definer::exported::one!(), 1);
+    /// ```
+    #[macro_pub(deprecate_as = "uno", since = "0.1.0", metadata)]
+    macro_rules! one {
+        () => {
+            1
+        };
+    }
+}
+
+pub mod unstable {
+    use super::*;
+
+    /// Expands to `2`, for now.
+    #[macro_pub(unstable)]
+    macro_rules! two {
+        () => {
+            2
+        };
+    }
+}
+
+pub mod gated {
+    use super::*;
+
+    /// Expands to `3`.
+    #[macro_pub(feature = "extra")]
+    macro_rules! three {
+        () => {
+            3
+        };
+    }
+}
+
+pub mod keywords {
+    use super::*;
+
+    /// Expands to `4`, or the expression it's given.
+    #[macro_pub]
+    macro_rules! r#match {
+        () => {
+            $crate::keywords::r#match!(4)
+        };
+        ($e:expr) => {
+            $e
+        };
+    }
+}
+
+pub mod renamed {
+    use super::*;
+
+    /// Expands to `5`.
+    #[macro_pub(vis = pub, rename = "fifth", alias = "old_five", alias = "cinco")]
+    macro_rules! old_five {
+        () => {
+            5
+        };
+    }
+}
+
+/// Macros exported under readable names.
+pub mod unmangled {
+    use super::*;
+
+    /// Expands to `6`.
+    #[macro_pub(mangle = "definer")]
+    macro_rules! six {
+        () => {
+            6
+        };
+    }
+
+    /// Expands to `7`.
+    #[macro_pub(mangle = false, expect_path = "crate::unmangled::seven")]
+    macro_rules! seven {
+        () => {
+            7
+        };
+    }
+}
+
+/// Macros written for `#[macro_export(local_inner_macros)]`.
+pub mod local_inner {
+    use super::*;
+
+    /// Doubles an expression with a helper it names without `$crate::`.
+    #[macro_pub]
+    #[macro_export(local_inner_macros)]
+    macro_rules! doubled {
+        ($e:expr) => {
+            __definer_double!($e)
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __definer_double {
+        ($e:expr) => {
+            $e * 2
+        };
+    }
+}
+
+/// Macros of the same name in different modules.
+pub mod twins {
+    /// The first `twin!`.
+    pub mod a {
+        use macro_pub::macro_pub;
+
+        /// Expands to `'a'`.
+        #[macro_pub(expect_path = "crate::twins::a::twin")]
+        macro_rules! twin {
+            () => {
+                'a'
+            };
+        }
+    }
+
+    /// The second `twin!`.
+    pub mod b {
+        use macro_pub::macro_pub;
+
+        /// Expands to `'b'`.
+        #[macro_pub(expect_path = "crate::twins::b::twin")]
+        macro_rules! twin {
+            () => {
+                'b'
+            };
+        }
+    }
+
+    /// A third `twin!`, told apart from the one in `included.rs` by its file.
+    pub mod c {
+        use macro_pub::macro_pub;
+
+        /// Expands to `'c'`.
+        #[macro_pub]
+        macro_rules! twin {
+            () => {
+                'c'
+            };
+        }
+    }
+}
+
+/// Macros that invoke themselves.
+pub mod recursive {
+    use super::*;
+
+    /// Counts the tokens it's given.
+    #[macro_pub(expect_path = "crate::recursive::count")]
+    macro_rules! count {
+        () => {
+            0
+        };
+        ($first:tt $($rest:tt)*) => {
+            1 + count!($($rest)*)
+        };
+    }
+
+    /// Sums the expressions it's given, under the name it's renamed to.
+    #[macro_pub(rename = "sum", expect_path = "crate::recursive::sum")]
+    macro_rules! add_up {
+        () => {
+            0
+        };
+        ($first:expr $(, $rest:expr)*) => {
+            $first + add_up!($($rest),*)
+        };
+    }
+}
+
+/// Macros only some platforms have.
+#[cfg(any(unix, windows))]
+pub mod platform {
+    use super::*;
+
+    /// Expands to `"native"`.
+    #[macro_pub]
+    macro_rules! native {
+        () => {
+            "native"
+        };
+    }
+}
+
+/// Expands to `"portable"`.
+#[macro_pub]
+#[cfg(any(unix, windows))]
+#[cfg_attr(doc, doc(cfg(any(unix, windows))))]
+macro_rules! portable {
+    () => {
+        "portable"
+    };
+}
+
+pub mod legacy {
+    // Not `super::*`, which would import the legacy names too, making them
+    // ambiguous with the macros' own.
+    use macro_pub::macro_pub;
+
+    /// Expands to `4`, also as `definer::four!` and with `#[macro_use]`.
+    #[macro_pub(also_export_legacy_name)]
+    macro_rules! four {
+        () => {
+            4
+        };
+    }
+
+    /// Expands to `5`, within `definer` and as `definer::five!`.
+    #[macro_pub(crate, also_export_legacy_name)]
+    macro_rules! five {
+        () => {
+            5
+        };
+    }
+
+    /// Expands to `6`, as `definer::six!` only.
+    #[macro_pub(strategy = "legacy_export")]
+    macro_rules! six {
+        () => {
+            6
+        };
+    }
+
+    pub fn uses_legacy() -> u32 {
+        four!() + five!() + self::four!() + crate::legacy::five!()
+    }
+}
+
+pub mod included {
+    use super::*;
+
+    include!("included.rs");
+}
+
+pub mod paths {
+    use super::*;
+
+    /// Calls a function through `$crate`.
+    #[macro_pub]
+    macro_rules! call_helper {
+        () => {
+            $crate::helper()
+        };
+    }
+
+    /// Calls another `#[macro_pub]` macro through `$crate`.
+    #[macro_pub]
+    macro_rules! call_macro {
+        () => {
+            $crate::paths::call_helper!() + $crate::exported::one!()
+        };
+    }
+
+    /// Sums its arguments, or twice one argument.
+    #[macro_pub]
+    macro_rules! sum {
+        () => {
+            0
+        };
+        (twice $e:expr) => {
+            sum!($e, $e)
+        };
+        ($e:expr $(, $rest:expr)*) => {
+            $e + $crate::paths::sum!($($rest),*)
+        };
+    }
+
+    /// Counts its arguments, recursing through a hidden helper.
+    #[macro_pub(internal_rules)]
+    macro_rules! count {
+        ($($e:expr),* $(,)?) => {
+            $crate::paths::count!(@count 0; $($e),*)
+        };
+        (@count $n:expr;) => {
+            $n
+        };
+        (@count $n:expr; $e:expr $(, $rest:expr)*) => {
+            $crate::paths::count!(@count $n + 1; $($rest),*)
+        };
+    }
+
+    /// Picks the first or second of two expressions.
+    #[macro_pub(catch_all, expect_path = "crate::paths::pick")]
+    macro_rules! pick {
+        (first $a:expr, $b:expr) => {
+            $a
+        };
+        (second $a:expr, $b:expr) => {
+            $b
+        };
+    }
+}
+
+pub mod extended {
+    use super::*;
+
+    mod offsets {
+        use super::*;
+
+        #[macro_pub(extend)]
+        macro_rules! measure {
+            (offset $e:expr) => {
+                $crate::helper() + $e
+            };
+        }
+    }
+
+    /// Measures things, with arms defined across modules.
+    #[macro_pub(extend_from(self::offsets::measure))]
+    macro_rules! measure {
+        () => {
+            0
+        };
+    }
+}
+
+pub mod hygiene {
+    use super::*;
+
+    /// Adds one to `$e`, using a local variable that `$e` can't see.
+    ///
+    /// ```
+    /// let one = 2;
+    /// assert_eq!(definer::hygiene::add_one!(one), 3);
+    /// ```
+    #[macro_pub(hide_arms)]
+    macro_rules! add_one {
+        ($e:expr) => {{
+            let one = 1;
+            $e + one
+        }};
+    }
+
+    /// Declares `$name` in the caller's scope.
+    ///
+    /// ```
+    /// definer::hygiene::declare!(value);
+    /// assert_eq!(value, 1);
+    /// ```
+    #[macro_pub]
+    macro_rules! declare {
+        ($name:ident) => {
+            let $name = 1;
+        };
+    }
+}
+
+macro_pub::index! {
+    /// Every world-public macro in this crate.
+    pub mod macros {
+        crate::exported::one;
+        /// Macros using `$crate` paths.
+        mod paths {
+            crate::paths::{call_helper, call_macro};
+        }
+    }
+}
+
+macro_pub::namespace! {
+    /// Macros grouped by what they're for.
+    pub mod net {
+        /// Expands to `3`, the number of attempts.
+        macro_rules! retry {
+            () => {
+                3
+            };
+        }
+        /// Macros for HTTP.
+        mod http {
+            /// Expands to the number of attempts, through `retry!`.
+            macro_rules! attempts {
+                () => {
+                    $crate::net::retry!()
+                };
+            }
+        }
+    }
+}
+
+macro_pub::use_macros! {
+    /// Imports the most used macros in this crate.
+    macro_rules! prelude {
+        crate::exported::one;
+        crate::paths::{call_helper, call_macro};
+    }
+}
+
+pub mod restricted {
+    use super::*;
+
+    #[macro_pub(crate)]
+    macro_rules! internal {
+        () => {
+            0
+        };
+    }
+
+    /// Expands to `11`, within `definer` and its examples:
+    ///
+    /// ```
+    /// assert_eq!(eleven!(), 11);
+    /// ```
+    #[macro_pub(crate, doctest)]
+    macro_rules! eleven {
+        () => {
+            11
+        };
+    }
+
+    /// Expands to `10`, within `definer` and its tests.
+    #[macro_pub(package)]
+    macro_rules! ten {
+        () => {
+            10
+        };
+    }
+}
+
+pub mod shared {
+    use super::*;
+
+    /// Internal, but exposed for companion crates as `shared_unstable!`, and
+    /// to `consumer` as a friend.
+    #[macro_pub(crate, export_as = shared_unstable, friends("consumer"))]
+    macro_rules! shared {
+        () => {
+            $crate::helper()
+        };
+    }
+}
+
+pub mod tracked {
+    use super::*;
+
+    /// Expands to `8`, recording its uses.
+    #[macro_pub(track_uses)]
+    macro_rules! eight {
+        () => {
+            8
+        };
+    }
+
+    /// Declares `$name` as `9`, recording its uses, which are only in this
+    /// crate.
+    #[macro_pub(track_uses)]
+    macro_rules! nine {
+        ($name:ident) => {
+            let $name = 9;
+        };
+    }
+}
+
+pub fn uses_tracked() -> u32 {
+    tracked::nine!(nine);
+    nine + tracked::eight!()
+}
+
+pub fn uses_internal() -> u32 {
+    restricted::internal!() + restricted::ten!() + restricted::eleven!() + shared::shared!()
+}
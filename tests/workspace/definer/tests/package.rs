@@ -0,0 +1,9 @@
+//! Uses the macros `definer` shares with its own tests.
+
+macro_pub::package!(definer::restricted::ten);
+
+#[test]
+fn package() {
+    assert_eq!(ten!(), 10);
+    assert_eq!(definer::uses_internal(), 63);
+}
@@ -0,0 +1,58 @@
+//! Compiled as a library in every edition, to check that the expansion does
+//! not depend on the edition of the crate using `#[macro_pub]`.
+
+#![cfg_attr(doc, feature(decl_macro, rustc_attrs))]
+
+#[macro_use]
+extern crate macro_pub;
+extern crate definer;
+
+pub mod inner {
+    #[macro_pub(crate)]
+    macro_rules! restricted {
+        () => {
+            1
+        };
+    }
+
+    #[macro_pub(super)]
+    macro_rules! parent {
+        () => {
+            2
+        };
+    }
+
+    #[macro_pub(in crate::inner)]
+    macro_rules! local {
+        () => {
+            3
+        };
+    }
+
+    /// Expands through `$crate`.
+    #[macro_pub]
+    macro_rules! exported {
+        () => {
+            $crate::inner::restricted!()
+        };
+    }
+
+    pub fn within() -> i32 {
+        restricted!() + self::parent!() + self::local!() + exported!()
+    }
+}
+
+pub fn uses() -> i32 {
+    inner::restricted!() + inner::parent!() + inner::exported!() + crate::inner::exported!()
+}
+
+pub fn cross_crate() -> u32 {
+    definer::exported::one!() + definer::paths::call_macro!()
+}
+
+#[test]
+fn expansions() {
+    assert_eq!(inner::within(), 7);
+    assert_eq!(uses(), 5);
+    assert_eq!(cross_crate(), 44);
+}
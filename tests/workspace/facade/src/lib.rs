@@ -0,0 +1,15 @@
+//! Re-exports `definer`'s macros, as a facade would.
+
+macro_pub::reexport! {
+    definer::exported::one;
+    /// Macros using `$crate` paths.
+    mod paths {
+        definer::paths::{call_helper, call_macro};
+    }
+}
+
+#[test]
+fn reexported() {
+    assert_eq!(crate::one!(), 1);
+    assert_eq!(paths::call_helper!() + paths::call_macro!(), 85);
+}
@@ -0,0 +1,41 @@
+//! Tries to use one of `definer`'s `pub(crate)` macros, one behind a feature
+//! that isn't enabled, and one with arguments it doesn't accept, which must
+//! all fail, and includes a macro with a malformed restriction, one that isn't
+//! at its expected path, and two with the same name in one module.
+
+pub fn uses_gated() -> u32 {
+    definer::gated::three!()
+}
+
+pub fn uses_internal() -> u32 {
+    definer::restricted::internal!()
+}
+
+// Documentation leaves the catch-all arm out with the other hidden arms.
+#[cfg(not(doc))]
+pub fn uses_pick() -> u32 {
+    definer::paths::pick!(third 1, 2, 3)
+}
+
+mod malformed {
+    include!("malformed.rs");
+}
+
+mod moved {
+    #[macro_pub::macro_pub(crate, expect_path = "crate::original::m")]
+    macro_rules! m {
+        () => {};
+    }
+}
+
+mod duplicated {
+    #[macro_pub::macro_pub(crate)]
+    macro_rules! twice {
+        () => {};
+    }
+
+    #[macro_pub::macro_pub(crate)]
+    macro_rules! twice {
+        ($e:expr) => {};
+    }
+}
@@ -0,0 +1,6 @@
+// Included into `rejected::malformed`.
+
+#[macro_pub::macro_pub(in super::super::utils.more)]
+macro_rules! malformed {
+    () => {};
+}
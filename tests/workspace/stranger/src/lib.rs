@@ -0,0 +1,13 @@
+//! Tries to use a macro `definer` only shares with `consumer`, and one it only
+//! shares with its own tests, which must fail.
+
+macro_pub::friend!(definer::shared::shared);
+macro_pub::package!(definer::restricted::ten);
+
+pub fn uses_shared() -> u32 {
+    shared!()
+}
+
+pub fn uses_package() -> u32 {
+    ten!()
+}
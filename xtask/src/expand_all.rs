@@ -0,0 +1,288 @@
+//! `expand-all`: a per-crate report of a workspace's `#[macro_pub]` macros.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+
+use crate::run;
+
+/// Runs `expand-all`, writing a report for each crate with `#[macro_pub]`
+/// macros, and returning whether there were any.
+pub(crate) fn expand_all(args: &[String]) -> Result<bool, String> {
+    let mut manifest_path = None;
+    let mut out = PathBuf::from("target/expand-all");
+    let mut cargo_args = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("`{}` expects a value", arg))
+        };
+        match arg.as_str() {
+            "--manifest-path" => manifest_path = Some(value()?),
+            "--out" => out = PathBuf::from(value()?),
+            "--" => cargo_args.extend(args.by_ref().cloned()),
+            arg => return Err(format!("unknown option `{}`", arg)),
+        }
+    }
+
+    // Cargo doesn't rebuild crates when the environment variables change, so
+    // build everything from scratch, in a target directory of its own.
+    let target = out.join("target");
+    let manifest = out.join("manifest.jsonl");
+    let expanded = out.join("expanded");
+    let reports = out.join("reports");
+    for dir in [&target, &expanded, &reports] {
+        remove(dir, |dir| fs::remove_dir_all(dir))?;
+    }
+    remove(&manifest, |file| fs::remove_file(file))?;
+    fs::create_dir_all(&reports).map_err(|error| error.to_string())?;
+    let absolute = |path: &Path| env::current_dir().unwrap().join(path);
+
+    let mut command = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    command
+        .env("CARGO_TARGET_DIR", absolute(&target))
+        .env("MACRO_PUB_MANIFEST", absolute(&manifest))
+        .env("MACRO_PUB_DEBUG_DIR", absolute(&expanded))
+        .args(["check", "--quiet", "--workspace", "--all-targets"]);
+    if let Some(manifest_path) = &manifest_path {
+        command.args(["--manifest-path", manifest_path]);
+    }
+    run(command.args(&cargo_args))?;
+
+    // A workspace without `#[macro_pub]` macros never creates the manifest.
+    let entries = fs::read_to_string(&manifest).unwrap_or_default();
+    let mut crates = BTreeMap::<String, Vec<Macro>>::new();
+    for line in entries.lines() {
+        if let Some(entry) = Macro::parse(line) {
+            crates.entry(entry.krate.clone()).or_default().push(entry);
+        }
+    }
+    for (krate, macros) in &mut crates {
+        // Macros are recorded in the order they were compiled, which varies
+        // between builds, and once for each target of the crate.
+        macros.sort_by(|a, b| a.name.cmp(&b.name).then(a.mangled.cmp(&b.mangled)));
+        macros.dedup();
+        let path = reports.join(format!("{}.txt", krate));
+        fs::write(&path, report(krate, macros, &expanded))
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+        println!("{}", path.display());
+    }
+    println!("{} crates with `#[macro_pub]` macros", crates.len());
+    Ok(!crates.is_empty())
+}
+
+/// Removes `path` with `remove`, if it exists.
+fn remove(path: &Path, remove: impl FnOnce(&Path) -> io::Result<()>) -> Result<(), String> {
+    match remove(path) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => {
+            Err(format!("{}: {}", path.display(), error))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The parts of a manifest entry the report shows.
+#[derive(Debug, PartialEq)]
+struct Macro {
+    krate: String,
+    name: String,
+    visibility: String,
+    mangled: Option<String>,
+    export_path: Option<String>,
+}
+
+impl Macro {
+    /// Parses a line of the manifest, if it describes a macro rather than a
+    /// use of one.
+    fn parse(line: &str) -> Option<Macro> {
+        let string = |key| field(line, key).and_then(unquote);
+        Some(Macro {
+            krate: string("crate")?,
+            name: string("name")?,
+            visibility: string("visibility")?,
+            mangled: string("mangled"),
+            export_path: string("export_path"),
+        })
+    }
+}
+
+/// The JSON value of `key` in the manifest entry `line`.
+///
+/// Entries are flat objects written by `macro_pub`, so this only needs to
+/// skip over strings and arrays to find the end of the value.
+fn field<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let start = line.find(&format!("\"{}\":", key))? + key.len() + 3;
+    let value = &line[start..];
+    let (mut depth, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            _ if quoted => {}
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' | '}' if depth == 0 => return Some(&value[..i]),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The contents of the JSON string `value`, or `None` if it's `null`.
+fn unquote(value: &str) -> Option<String> {
+    let value = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut string = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                'u' => {
+                    let code = chars.by_ref().take(4).collect::<String>();
+                    string.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                c => string.push(c),
+            },
+            c => string.push(c),
+        }
+    }
+    Some(string)
+}
+
+/// The report for `krate`, with the items generated for each macro read from
+/// the files `macro_pub` wrote to `expanded`.
+fn report(krate: &str, macros: &[Macro], expanded: &Path) -> String {
+    let mut report = format!("# {}\n", krate);
+    for entry in macros {
+        report.push_str(&format!("\n{}!\n", entry.name));
+        report.push_str(&format!("    visibility: {}\n", entry.visibility));
+        let none = "none".to_string();
+        let mangled = entry.mangled.as_ref().unwrap_or(&none);
+        report.push_str(&format!("    mangled: {}\n", mangled));
+        let export_path = entry.export_path.as_ref().unwrap_or(&none);
+        report.push_str(&format!("    export path: {}\n", export_path));
+        // Macros of the same name in one crate overwrite each other's files.
+        let path = expanded.join(krate).join(format!("{}.rs", entry.name));
+        match fs::read_to_string(&path)
+            .ok()
+            .and_then(|code| code.parse().ok())
+        {
+            Some(code) => {
+                report.push_str("    generated:\n");
+                for item in items(code) {
+                    report.push_str(&format!("        {}\n", item));
+                }
+            }
+            None => report.push_str(&format!("    generated: unreadable {}\n", path.display())),
+        }
+    }
+    report
+}
+
+/// A line for each top-level item in `code`: its `#[cfg]`s, and its tokens up
+/// to its body, like `pub use macro_impl_…_m as m`.
+fn items(code: TokenStream) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut item = TokenStream::new();
+    let mut tokens = code.into_iter().peekable();
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Punct(ref punct) if punct.as_char() == '#' && item.is_empty() => {
+                let attr = match tokens.next() {
+                    Some(TokenTree::Group(attr)) => attr,
+                    _ => continue,
+                };
+                let is_cfg = matches!(
+                    attr.stream().into_iter().next(),
+                    Some(TokenTree::Ident(ref ident)) if ident == "cfg"
+                );
+                if is_cfg {
+                    items.push(format!("#{}", attr));
+                }
+            }
+            TokenTree::Punct(ref punct) if punct.as_char() == ';' => {
+                items.push(item.to_string());
+                item = TokenStream::new();
+            }
+            TokenTree::Group(ref group) if group.delimiter() == Delimiter::Brace => {
+                items.push(item.to_string());
+                item = TokenStream::new();
+                // Like the one after `const _: () = { ... };`.
+                if matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == ';')
+                {
+                    tokens.next();
+                }
+            }
+            tt => item.extend(Some(tt)),
+        }
+    }
+    // `#[cfg]`s apply to the item after them, so put them on its line.
+    let mut lines = Vec::<String>::new();
+    let mut cfgs = String::new();
+    for item in items {
+        if item.starts_with('#') {
+            cfgs.push_str(&item);
+            cfgs.push(' ');
+        } else {
+            lines.push(format!("{}{}", cfgs, item));
+            cfgs.clear();
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_entries() {
+        let line = r#"{"crate":"c","name":"m","visibility":"pub(crate)","mangled":null,"export_path":"c::a\"b","friends":["x,y"],"salt":null}"#;
+        assert_eq!(
+            Macro::parse(line),
+            Some(Macro {
+                krate: "c".to_string(),
+                name: "m".to_string(),
+                visibility: "pub(crate)".to_string(),
+                mangled: None,
+                export_path: Some("c::a\"b".to_string()),
+            }),
+        );
+        assert_eq!(field(line, "friends"), Some(r#"["x,y"]"#));
+        assert_eq!(field(line, "salt"), Some("null"));
+        assert_eq!(
+            Macro::parse(r#"{"crate":"c","uses":"macro_impl_1_m"}"#),
+            None
+        );
+        assert_eq!(unquote(r#""a\u000ab""#), Some("a\nb".to_string()));
+    }
+
+    #[test]
+    fn lists_items() {
+        let code = "
+            #[macro_export]
+            #[doc(hidden)]
+            macro_rules! macro_impl_1_m { () => {}; }
+            #[cfg(not(doc))]
+            #[doc(inline)]
+            pub use macro_impl_1_m as m;
+            const _: () = { use m as _; };
+        ";
+        assert_eq!(
+            items(code.parse().unwrap()),
+            [
+                "macro_rules ! macro_impl_1_m",
+                "#[cfg (not (doc))] pub use macro_impl_1_m as m",
+                "const _ : () =",
+            ],
+        );
+    }
+}
@@ -0,0 +1,524 @@
+//! Developer tools for `macro_pub`, run with `cargo xtask <command>`.
+//!
+//! # `expand-diff`
+//!
+//! ```text
+//! cargo xtask expand-diff [--old SOURCE] [--new SOURCE] [--config CONFIG]
+//!     [--old-config CONFIG] [--new-config CONFIG] [--ignore-hashes] [FIXTURE...]
+//! ```
+//!
+//! Expands each fixture, `tests/expand/*.rs` by default, with two versions or
+//! configurations of `macro_pub`, and prints the tokens that differ between
+//! them, to see how an upgrade changes the generated code before releasing
+//! it. A `SOURCE` is a path to a `macro_pub` checkout, or `git:REV` for a
+//! revision of this repository; by default, `HEAD` is compared with the
+//! working tree. A `CONFIG` is `stable`, `nightly`, or `nightly-doc`, as in
+//! `tests/expand.rs`, and defaults to `stable`. `--ignore-hashes` ignores
+//! changes to the hashes in mangled names, which change with any change to
+//! the macro's tokens.
+//!
+//! Exits with 1 if any expansion differs.
+//!
+//! # `expand-all`
+//!
+//! ```text
+//! cargo xtask expand-all [--manifest-path PATH] [--out DIR] [-- CARGO_ARGS...]
+//! ```
+//!
+//! Checks every target of a workspace, the one in the current directory by
+//! default, from scratch with `MACRO_PUB_MANIFEST` and `MACRO_PUB_DEBUG_DIR`
+//! set, and writes a report for each crate to `DIR/reports/CRATE.txt`,
+//! listing each `#[macro_pub]` macro's visibility, mangled name, export path,
+//! and the items generated for it, to review during audits and refactors.
+//! `DIR` is `target/expand-all` by default, and also holds the manifest and
+//! the generated code. `CARGO_ARGS` are passed to `cargo check`, e.g.
+//! `--features`.
+//!
+//! Exits with 1 if no crate has `#[macro_pub]` macros.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+
+mod expand_all;
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let result = match args.first().map(String::as_str) {
+        Some("expand-diff") => expand_diff(&args[1..]),
+        Some("expand-all") => expand_all::expand_all(&args[1..]),
+        _ => Err(
+            "usage: cargo xtask expand-diff [options] [fixtures]\n       cargo xtask expand-all [options] [-- cargo args]"
+                .to_string(),
+        ),
+    };
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(message) => {
+            eprintln!("error: {}", message);
+            process::exit(2);
+        }
+    }
+}
+
+/// The repository root.
+fn root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+/// A toolchain configuration to expand fixtures in, as in `tests/expand.rs`.
+struct Config {
+    name: &'static str,
+    rustflags: &'static str,
+    cfg_doc: bool,
+}
+
+const CONFIGS: &[Config] = &[
+    Config {
+        name: "stable",
+        rustflags: "",
+        cfg_doc: false,
+    },
+    Config {
+        name: "nightly",
+        rustflags: "--cfg has_simple_decl_macro --cfg has_semiopaque_transparency --cfg doc_decl_macro",
+        cfg_doc: false,
+    },
+    Config {
+        name: "nightly-doc",
+        rustflags: "--cfg has_simple_decl_macro --cfg has_semiopaque_transparency --cfg doc_decl_macro",
+        cfg_doc: true,
+    },
+];
+
+fn config(name: &str) -> Result<&'static Config, String> {
+    CONFIGS
+        .iter()
+        .find(|config| config.name == name)
+        .ok_or_else(|| {
+            format!(
+                "unknown configuration `{}`; expected `stable`, `nightly`, or `nightly-doc`",
+                name,
+            )
+        })
+}
+
+/// Runs `expand-diff`, returning whether every expansion was the same.
+fn expand_diff(args: &[String]) -> Result<bool, String> {
+    let mut old = "git:HEAD".to_string();
+    let mut new = ".".to_string();
+    let mut old_config = config("stable")?;
+    let mut new_config = old_config;
+    let mut ignore_hashes = false;
+    let mut fixtures = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("`{}` expects a value", arg))
+        };
+        match arg.as_str() {
+            "--old" => old = value()?,
+            "--new" => new = value()?,
+            "--config" => {
+                old_config = config(&value()?)?;
+                new_config = old_config;
+            }
+            "--old-config" => old_config = config(&value()?)?,
+            "--new-config" => new_config = config(&value()?)?,
+            "--ignore-hashes" => ignore_hashes = true,
+            arg if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+            fixture => fixtures.push(
+                fs::canonicalize(fixture)
+                    .map_err(|error| format!("can't find fixture {}: {}", fixture, error))?,
+            ),
+        }
+    }
+    if fixtures.is_empty() {
+        fixtures = default_fixtures()?;
+    }
+
+    let work = root().join("target/xtask");
+    let old_source = source(&old, &work)?;
+    let new_source = source(&new, &work)?;
+
+    let mut differing = 0;
+    for fixture in &fixtures {
+        let old_expansion = expand(fixture, &old_source, old_config, &work.join("old"));
+        let new_expansion = expand(fixture, &new_source, new_config, &work.join("new"));
+        let (old_expansion, new_expansion) = match (old_expansion, new_expansion) {
+            (Ok(old_expansion), Ok(new_expansion)) => (old_expansion, new_expansion),
+            // A fixture using a new feature won't expand with an old version.
+            (old_expansion, new_expansion) => {
+                differing += 1;
+                for error in old_expansion.err().into_iter().chain(new_expansion.err()) {
+                    println!("{}\n", error);
+                }
+                continue;
+            }
+        };
+        let old_tokens = tokens(&old_expansion, ignore_hashes);
+        let new_tokens = tokens(&new_expansion, ignore_hashes);
+        let changes = diff(&old_tokens, &new_tokens);
+        if changes
+            .iter()
+            .all(|change| matches!(change, Change::Same(_)))
+        {
+            continue;
+        }
+        differing += 1;
+        println!("--- {} ({}, {})", fixture.display(), old, old_config.name);
+        println!("+++ {} ({}, {})", fixture.display(), new, new_config.name);
+        print_changes(&changes);
+        println!();
+    }
+    println!("{} of {} expansions differ", differing, fixtures.len());
+    Ok(differing == 0)
+}
+
+fn default_fixtures() -> Result<Vec<PathBuf>, String> {
+    let dir = root().join("tests/expand");
+    let entries = fs::read_dir(&dir).map_err(|error| format!("{}: {}", dir.display(), error))?;
+    let mut fixtures = entries
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_str().unwrap();
+            name.ends_with(".rs") && !name.ends_with(".expanded.rs")
+        })
+        .collect::<Vec<_>>();
+    fixtures.sort();
+    Ok(fixtures)
+}
+
+/// The directory of `source`, extracting it from git into `work` if it's a
+/// `git:REV`.
+fn source(source: &str, work: &Path) -> Result<PathBuf, String> {
+    let rev = match source.strip_prefix("git:") {
+        Some(rev) => rev,
+        None => {
+            return fs::canonicalize(source)
+                .map_err(|error| format!("can't find source {}: {}", source, error))
+        }
+    };
+    let commit = run(Command::new("git")
+        .current_dir(root())
+        .args(["rev-parse", "--verify", rev]))?;
+    let dir = work.join("sources").join(commit.trim());
+    if dir.exists() {
+        return Ok(dir);
+    }
+    let partial = dir.with_extension("partial");
+    let _ = fs::remove_dir_all(&partial);
+    fs::create_dir_all(&partial).map_err(|error| error.to_string())?;
+    let archive = Command::new("git")
+        .current_dir(root())
+        .args(["archive", "--format=tar", commit.trim()])
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|error| format!("can't run git: {}", error))?;
+    run(Command::new("tar")
+        .arg("-x")
+        .current_dir(&partial)
+        .stdin(archive.stdout.unwrap()))?;
+    fs::rename(&partial, &dir).map_err(|error| error.to_string())?;
+    Ok(dir)
+}
+
+/// Expands `fixture` with the `macro_pub` at `source`, building in `dir`.
+fn expand(fixture: &Path, source: &Path, config: &Config, dir: &Path) -> Result<String, String> {
+    let project = dir.join("fixture");
+    fs::create_dir_all(&project).map_err(|error| error.to_string())?;
+    fs::write(
+        project.join("Cargo.toml"),
+        format!(
+            r#"
+                [package]
+                name = "fixture"
+                version = "0.0.0"
+                edition = "2021"
+
+                [lib]
+                path = {:?}
+
+                [dependencies]
+                macro_pub = {{ path = {:?} }}
+
+                [workspace]
+            "#,
+            fixture, source,
+        ),
+    )
+    .map_err(|error| error.to_string())?;
+
+    let mut command = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    command
+        .current_dir(&project)
+        .env("RUSTC_BOOTSTRAP", "fixture")
+        .env("RUSTFLAGS", config.rustflags)
+        .env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .args(["rustc", "--lib", "--quiet", "--target-dir"])
+        .arg(dir.join(config.name))
+        .args(["--", "-Zunpretty=expanded"]);
+    if config.cfg_doc {
+        command.args(["--cfg", "doc"]);
+    }
+    run(&mut command).map_err(|error| {
+        format!(
+            "{} failed to expand with {}: {}",
+            fixture.display(),
+            source.display(),
+            error,
+        )
+    })
+}
+
+/// Runs `command`, returning its output.
+fn run(command: &mut Command) -> Result<String, String> {
+    let output = command
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|error| format!("can't run {:?}: {}", command, error))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{:?} failed:\n{}",
+            command,
+            String::from_utf8_lossy(&output.stderr),
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|error| error.to_string())
+}
+
+/// The tokens of `code`, with groups as their opening and closing delimiters.
+fn tokens(code: &str, ignore_hashes: bool) -> Vec<String> {
+    let mut tokens = Vec::new();
+    match code.parse::<TokenStream>() {
+        Ok(stream) => flatten(stream, ignore_hashes, &mut tokens),
+        // Still compare something, if the output doesn't lex.
+        Err(_) => tokens.extend(code.split_whitespace().map(str::to_string)),
+    }
+    tokens
+}
+
+fn flatten(stream: TokenStream, ignore_hashes: bool, tokens: &mut Vec<String>) {
+    for tt in stream {
+        match tt {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+                if !open.is_empty() {
+                    tokens.push(open.to_string());
+                }
+                flatten(group.stream(), ignore_hashes, tokens);
+                if !close.is_empty() {
+                    tokens.push(close.to_string());
+                }
+            }
+            TokenTree::Ident(ident) if ignore_hashes => {
+                tokens.push(without_hash(&ident.to_string()))
+            }
+            tt => tokens.push(tt.to_string()),
+        }
+    }
+}
+
+/// `name`, with the hash in a mangled name like `macro_impl_HASH_name`
+/// replaced by `HASH`.
+fn without_hash(name: &str) -> String {
+    for prefix in &["macro_impl_", "macro_pub_feature_"] {
+        if let Some(rest) = name.strip_prefix(prefix) {
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if digits > 0 {
+                return format!("{}HASH{}", prefix, &rest[digits..]);
+            }
+        }
+    }
+    name.to_string()
+}
+
+enum Change<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// The changes from `old` to `new`, as a longest common subsequence.
+fn diff<'a>(old: &'a [String], new: &'a [String]) -> Vec<Change<'a>> {
+    // Most of an expansion is unchanged, so only compare the middle.
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    // lengths[i][j] is the length of the longest common subsequence of
+    // `a[i..]` and `b[j..]`.
+    let mut lengths = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = old[..prefix]
+        .iter()
+        .map(|token| Change::Same(token))
+        .collect::<Vec<_>>();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            changes.push(Change::Same(&a[i]));
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lengths[i][j + 1] >= lengths[i + 1][j]) {
+            changes.push(Change::Added(&b[j]));
+            j += 1;
+        } else {
+            changes.push(Change::Removed(&a[i]));
+            i += 1;
+        }
+    }
+    changes.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|token| Change::Same(token)),
+    );
+    changes
+}
+
+/// Unchanged tokens shown around each change.
+const CONTEXT: usize = 8;
+
+fn print_changes(changes: &[Change]) {
+    let changed = |change: &Change| !matches!(change, Change::Same(_));
+    let mut i = 0;
+    while i < changes.len() {
+        if !changed(&changes[i]) {
+            i += 1;
+            continue;
+        }
+        // A hunk continues until there's more unchanged context than would be
+        // shown around it.
+        let start = i;
+        let mut end = i;
+        while end < changes.len() {
+            let same = changes[end..]
+                .iter()
+                .take_while(|change| !changed(change))
+                .count();
+            if end + same == changes.len() || same > 2 * CONTEXT {
+                break;
+            }
+            end += same;
+            end += changes[end..]
+                .iter()
+                .take_while(|change| changed(change))
+                .count();
+        }
+
+        println!("@@ token {} @@", start);
+        let before = &changes[start.saturating_sub(CONTEXT)..start];
+        println!("  {}", join(before));
+        let mut j = start;
+        while j < end {
+            let run = changes[j..end]
+                .iter()
+                .take_while(|change| changed(change) == changed(&changes[j]))
+                .count();
+            let hunk = &changes[j..j + run];
+            if changed(&changes[j]) {
+                let removed = hunk
+                    .iter()
+                    .filter(|change| matches!(change, Change::Removed(_)));
+                let added = hunk
+                    .iter()
+                    .filter(|change| matches!(change, Change::Added(_)));
+                let removed = removed.collect::<Vec<_>>();
+                let added = added.collect::<Vec<_>>();
+                if !removed.is_empty() {
+                    println!("- {}", join(removed));
+                }
+                if !added.is_empty() {
+                    println!("+ {}", join(added));
+                }
+            } else {
+                println!("  {}", join(hunk));
+            }
+            j += run;
+        }
+        let after = &changes[end..(end + CONTEXT).min(changes.len())];
+        println!("  {}", join(after));
+        i = end;
+    }
+}
+
+fn join<'a>(changes: impl IntoIterator<Item = &'a Change<'a>>) -> String {
+    changes
+        .into_iter()
+        .map(|change| match *change {
+            Change::Same(token) | Change::Removed(token) | Change::Added(token) => token,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(code: &str) -> Vec<String> {
+        tokens(code, false)
+    }
+
+    #[test]
+    fn diffs() {
+        let old = strings("macro_rules! m { () => {}; } pub use m as m;");
+        let new = strings("macro_rules! m { () => { 1 }; } pub(crate) use m as m;");
+        let changes = diff(&old, &new);
+        let added = changes
+            .iter()
+            .filter_map(|change| match *change {
+                Change::Added(token) => Some(token),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(added, ["1", "(", "crate", ")"]);
+        assert!(!changes
+            .iter()
+            .any(|change| matches!(change, Change::Removed(_))));
+    }
+
+    #[test]
+    fn hashes() {
+        assert_eq!(
+            tokens("macro_impl_123_m macro_pub_feature_4_m macro_impl_m", true),
+            [
+                "macro_impl_HASH_m",
+                "macro_pub_feature_HASH_m",
+                "macro_impl_m"
+            ],
+        );
+    }
+}